@@ -8,6 +8,8 @@ def main():
     print("🚀 Starting Funding Event Consumer/Simulator...")
     r = redis.Redis.from_url(os.getenv("REDIS_URL", "redis://redis:6379"), decode_responses=True)
     tokens = ["SOL_MEME1", "SOL_MEME2", "SOL_MEME3", "SOL_MEME4", "SOL_MEME5"]
+    # Simulated oracle prices the perp marks drift around
+    oracle_prices = {t: 1.0 for t in tokens}
 
     while True:
         for token in tokens:
@@ -25,6 +27,17 @@ def main():
             }
             # P-7: Use XADD for Redis Streams
             r.xadd("events:funding", {"event": json.dumps(event)})
+
+            # Simulate perp mark trading at a premium/discount that leans with funding
+            oracle_prices[token] = max(0.01, oracle_prices[token] + random.uniform(-0.005, 0.005))
+            mark_price = oracle_prices[token] * (1 + funding_rate_pct * 2 + random.uniform(-0.002, 0.002))
+            mark_event = {
+                "type": "PerpMark",
+                "token_address": token,
+                "mark_price_usd": mark_price,
+                "oracle_price_usd": oracle_prices[token],
+            }
+            r.xadd("events:perp_mark", {"event": json.dumps(mark_event)})
         
         time.sleep(30) # Funding rates update less frequently
 
@@ -0,0 +1,37 @@
+import redis
+import json
+import time
+import os
+import requests
+
+TIP_FLOOR_URL = os.getenv("JITO_TIP_FLOOR_URL", "https://bundles.jito.wtf/api/v1/bundles/tip_floor")
+LAMPORTS_PER_SOL = 1_000_000_000
+
+def get_tip_floor() -> dict | None:
+    """Fetches recent landed Jito tip percentiles (reported in SOL) and converts them to lamports."""
+    try:
+        response = requests.get(TIP_FLOOR_URL, timeout=5)
+        response.raise_for_status()
+        latest = response.json()[0]
+        return {
+            f"p{p}_lamports": int(latest[f"landed_tips_{p}th_percentile"] * LAMPORTS_PER_SOL)
+            for p in (25, 50, 75, 95, 99)
+        }
+    except Exception as e:
+        print(f"Error fetching Jito tip floor: {e}")
+        return None
+
+def main():
+    print("🚀 Starting Jito Tip Floor Consumer...")
+    r = redis.Redis.from_url(os.getenv("REDIS_URL", "redis://redis:6379"), decode_responses=True)
+
+    while True:
+        floor = get_tip_floor()
+        if floor is not None:
+            event = {"type": "JitoTipFloor", **floor}
+            r.xadd("events:jito_tip_floor", {"event": json.dumps(event)})
+            print(f"Published Jito tip floor: p50={floor['p50_lamports']} p75={floor['p75_lamports']} lamports")
+        time.sleep(30) # Tip floor moves slowly
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,119 @@
+// executor/src/algo.rs
+use crate::config::CONFIG;
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::Duration};
+use tokio::sync::watch;
+
+/// How a parent order is split into child swaps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExecAlgo {
+    /// Equal children at a fixed interval.
+    Twap { slices: u32, interval: Duration },
+    /// Each child is the largest size whose quoted price impact stays under `max_impact_pct`.
+    Liquidity { max_impact_pct: f64, interval: Duration },
+}
+
+impl ExecAlgo {
+    /// The algorithm for a spot order of `size_usd`, or `None` when it should go out in one swap.
+    pub fn for_size(size_usd: f64) -> Option<Self> {
+        if size_usd <= CONFIG.slice_threshold_usd {
+            return None;
+        }
+        let interval = Duration::from_secs(CONFIG.slice_interval_secs);
+        Some(match CONFIG.slice_mode.as_str() {
+            "liquidity" => ExecAlgo::Liquidity { max_impact_pct: CONFIG.slice_max_impact_pct, interval },
+            _ => ExecAlgo::Twap { slices: CONFIG.twap_slices.max(1), interval },
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExecAlgo::Twap { .. } => "twap",
+            ExecAlgo::Liquidity { .. } => "liquidity",
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        match self {
+            ExecAlgo::Twap { interval, .. } | ExecAlgo::Liquidity { interval, .. } => *interval,
+        }
+    }
+
+    /// Size of the next child. `quoted_impact_pct` is the impact of quoting all of `remaining_usd`,
+    /// needed only by the liquidity algorithm.
+    pub fn child_size(&self, target_usd: f64, remaining_usd: f64, quoted_impact_pct: Option<f64>) -> f64 {
+        match self {
+            ExecAlgo::Twap { slices, .. } => (target_usd / *slices as f64).min(remaining_usd),
+            ExecAlgo::Liquidity { max_impact_pct, .. } => match quoted_impact_pct {
+                // Impact grows roughly linearly with size on constant-product pools.
+                Some(impact) if impact > *max_impact_pct => remaining_usd * max_impact_pct / impact,
+                _ => remaining_usd,
+            },
+        }
+    }
+}
+
+struct WorkingOrder {
+    strategy_id: String,
+    token_address: String,
+    cancel: watch::Sender<Option<String>>,
+}
+
+/// Parent orders still sending children, so they can be cancelled from outside their task.
+#[derive(Clone, Default)]
+pub struct WorkingOrders {
+    inner: Arc<Mutex<HashMap<i64, WorkingOrder>>>,
+}
+
+impl WorkingOrders {
+    /// Registers a parent order; the receiver yields `Some(reason)` once it is cancelled.
+    pub fn register(&self, parent_id: i64, strategy_id: &str, token_address: &str) -> watch::Receiver<Option<String>> {
+        let (cancel, rx) = watch::channel(None);
+        self.inner.lock().unwrap().insert(parent_id, WorkingOrder {
+            strategy_id: strategy_id.to_string(),
+            token_address: token_address.to_string(),
+            cancel,
+        });
+        rx
+    }
+
+    pub fn finish(&self, parent_id: i64) {
+        self.inner.lock().unwrap().remove(&parent_id);
+    }
+
+    /// Cancels the strategy's working orders, all of them or only those on `token_address`.
+    /// Returns how many were cancelled.
+    pub fn cancel(&self, strategy_id: &str, token_address: Option<&str>, reason: &str) -> usize {
+        let orders = self.inner.lock().unwrap();
+        let mut cancelled = 0;
+        for order in orders.values() {
+            if order.strategy_id == strategy_id && token_address.is_none_or(|t| t == order.token_address) {
+                order.cancel.send_replace(Some(reason.to_string()));
+                cancelled += 1;
+            }
+        }
+        cancelled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWAP: ExecAlgo = ExecAlgo::Twap { slices: 4, interval: Duration::from_secs(10) };
+    const LIQUIDITY: ExecAlgo = ExecAlgo::Liquidity { max_impact_pct: 1.0, interval: Duration::from_secs(10) };
+
+    #[test]
+    fn twap_slices_the_target_evenly() {
+        assert_eq!(TWAP.child_size(1_000.0, 1_000.0, None), 250.0);
+        assert_eq!(TWAP.child_size(1_000.0, 500.0, Some(5.0)), 250.0, "impact is ignored");
+        assert_eq!(TWAP.child_size(1_000.0, 100.0, None), 100.0, "last child takes what is left");
+    }
+
+    #[test]
+    fn liquidity_caps_children_by_quoted_impact() {
+        assert_eq!(LIQUIDITY.child_size(1_000.0, 800.0, Some(4.0)), 200.0);
+        assert_eq!(LIQUIDITY.child_size(1_000.0, 800.0, Some(1.0)), 800.0, "impact at the cap sends everything");
+        assert_eq!(LIQUIDITY.child_size(1_000.0, 800.0, Some(0.2)), 800.0);
+        assert_eq!(LIQUIDITY.child_size(1_000.0, 800.0, None), 800.0, "no impact figure sends everything");
+    }
+}
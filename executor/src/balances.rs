@@ -0,0 +1,202 @@
+// executor/src/balances.rs
+use crate::{config::CONFIG, database::Database, jupiter::SOL_MINT, rpc::SolanaRpc, signer_client::SignerClient};
+use anyhow::Result;
+use std::{collections::{HashMap, HashSet}, sync::Arc};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+/// What one signer wallet holds on-chain.
+#[derive(Debug, Clone)]
+pub struct WalletBalances {
+    pub sol_lamports: u64,
+    /// Raw amount per mint, summed over all of the wallet's token accounts.
+    pub tokens: HashMap<String, u64>,
+}
+
+/// A token whose DB position disagrees with the wallet of the signer key holding it.
+#[derive(Debug, Clone)]
+pub struct PositionDrift {
+    pub signer_key: String,
+    pub token_address: String,
+    pub db_amount: u64,
+    pub chain_amount: u64,
+}
+
+/// Tracks the SOL and SPL balances of each strategy's signer wallet, by signer key, refreshed
+/// when the strategy starts and after every confirmed trade.
+pub struct BalanceTracker {
+    rpc: Arc<dyn SolanaRpc>,
+    signer: Arc<SignerClient>,
+    latest: RwLock<HashMap<String, WalletBalances>>,
+    sol_price: Arc<Mutex<f64>>,
+}
+
+impl BalanceTracker {
+    pub fn new(rpc: Arc<dyn SolanaRpc>, signer: Arc<SignerClient>, sol_price: Arc<Mutex<f64>>) -> Self {
+        Self { rpc, signer, latest: RwLock::new(HashMap::new()), sol_price }
+    }
+
+    pub async fn refresh(&self, signer_key: &str) -> Result<WalletBalances> {
+        let owner = self.signer.pubkey(signer_key).await?;
+        let sol_lamports = self.rpc.lamports(&owner).await?;
+        let mut tokens: HashMap<String, u64> = HashMap::new();
+        for holding in self.rpc.token_accounts(&owner).await? {
+            *tokens.entry(holding.mint).or_default() += holding.amount_raw;
+        }
+        let balances = WalletBalances { sol_lamports, tokens };
+        info!(key = signer_key, %owner, sol = sol_lamports as f64 / 1e9, tokens = balances.tokens.len(), "wallet balances refreshed");
+        self.latest.write().await.insert(signer_key.to_string(), balances.clone());
+        Ok(balances)
+    }
+
+    /// USD of SOL `signer_key`'s wallet can spend after keeping `WALLET_SOL_RESERVE_LAMPORTS` for
+    /// fees and rent. `None` until its first refresh and the first SOL/USD price.
+    pub async fn buying_power_usd(&self, signer_key: &str) -> Option<f64> {
+        let sol_lamports = self.latest.read().await.get(signer_key)?.sol_lamports;
+        let spendable = sol_lamports.saturating_sub(CONFIG.wallet_sol_reserve_lamports);
+        let sol_usd = *self.sol_price.lock().await;
+        (sol_usd > 0.0).then(|| spendable as f64 / 1e9 * sol_usd)
+    }
+
+    /// Raw on-chain balance of `mint` in `signer_key`'s wallet; `None` until its first refresh.
+    pub async fn token_balance(&self, signer_key: &str, mint: &str) -> Option<u64> {
+        self.latest.read().await.get(signer_key).map(|b| b.tokens.get(mint).copied().unwrap_or(0))
+    }
+
+    /// Compares, per signer key fetched so far, the open spot inventory of the strategies trading
+    /// from that key with what its wallet holds, for every token the executor has traded. Keys
+    /// not fetched yet are left out. Drift beyond `BALANCE_DRIFT_TOLERANCE_PCT` is logged and
+    /// recorded.
+    pub async fn reconcile(&self, db: &Database) -> Result<Vec<PositionDrift>> {
+        let latest = self.latest.read().await.clone();
+        if latest.is_empty() {
+            return Ok(vec![]);
+        }
+        let traded: HashSet<String> = db.get_traded_spot_tokens()?.into_iter().collect();
+
+        let mut drifts = Vec::new();
+        for token in traded.iter().filter(|t| t.as_str() != SOL_MINT) {
+            let mut net_by_key: HashMap<String, i64> = HashMap::new();
+            for (strategy_id, net) in db.get_spot_inventory_by_strategy(token)? {
+                *net_by_key.entry(CONFIG.signer_key(&strategy_id)).or_default() += net;
+            }
+            for (signer_key, wallet) in &latest {
+                let db_amount = net_by_key.get(signer_key).copied().unwrap_or(0).max(0) as u64;
+                let chain_amount = wallet.tokens.get(token).copied().unwrap_or(0);
+                let diff = db_amount.abs_diff(chain_amount) as f64;
+                if diff > db_amount.max(chain_amount) as f64 * CONFIG.balance_drift_tolerance_pct / 100.0 {
+                    let drift = PositionDrift { signer_key: signer_key.clone(), token_address: token.clone(), db_amount, chain_amount };
+                    warn!(key = %drift.signer_key, token = %drift.token_address, db_amount, chain_amount, "position drift between DB and wallet");
+                    db.log_balance_drift(&drift.token_address, drift.db_amount, drift.chain_amount)?;
+                    drifts.push(drift);
+                }
+            }
+        }
+        Ok(drifts)
+    }
+
+    /// Stands in for a refresh, which needs the signer.
+    #[cfg(test)]
+    pub async fn seed(&self, signer_key: &str, balances: WalletBalances) {
+        self.latest.write().await.insert(signer_key.to_string(), balances);
+    }
+
+    /// Refresh `signer_key`'s wallet then reconcile; what runs after each confirmed trade.
+    pub async fn sync(&self, signer_key: &str, db: &Database) -> Result<Vec<PositionDrift>> {
+        self.refresh(signer_key).await?;
+        self.reconcile(db).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config, rpc::StubRpc};
+    use shared_models::{OrderDetails, Side};
+
+    fn tracker(sol_usd: f64) -> BalanceTracker {
+        config::test_env();
+        BalanceTracker::new(Arc::new(StubRpc::default()), Arc::new(SignerClient::from_config()), Arc::new(Mutex::new(sol_usd)))
+    }
+
+    fn wallet(sol_lamports: u64, tokens: &[(&str, u64)]) -> WalletBalances {
+        WalletBalances { sol_lamports, tokens: tokens.iter().map(|&(m, a)| (m.to_string(), a)).collect() }
+    }
+
+    fn db() -> Database {
+        let path = std::env::temp_dir().join(format!("balances-test-{}-{}.db", std::process::id(), rand::random::<u64>()));
+        Database::new(path.to_str().unwrap()).unwrap()
+    }
+
+    /// An open spot long of `amount_raw` by `strategy_id`, as a confirmed buy leaves it.
+    fn hold(db: &Database, strategy_id: &str, token: &str, amount_raw: u64) {
+        let details = OrderDetails { token_address: token.to_string(), suggested_size_usd: 10.0, confidence: 1.0, side: Side::Long };
+        let id = db.log_trade_attempt(&details, strategy_id, 1.0).unwrap();
+        db.set_instrument(id, "Spot").unwrap();
+        db.set_filled_amount(id, amount_raw).unwrap();
+        db.open_trade(id, "sig").unwrap();
+    }
+
+    #[tokio::test]
+    async fn buying_power_keeps_the_reserve_and_needs_a_price() {
+        let balances = tracker(100.0);
+        assert_eq!(balances.buying_power_usd("test").await, None, "unknown before the first refresh");
+
+        balances.seed("test", wallet(CONFIG.wallet_sol_reserve_lamports + 2_000_000_000, &[])).await;
+        assert_eq!(balances.buying_power_usd("test").await, Some(200.0));
+        balances.seed("test", wallet(CONFIG.wallet_sol_reserve_lamports / 2, &[])).await;
+        assert_eq!(balances.buying_power_usd("test").await, Some(0.0));
+
+        let unpriced = tracker(0.0);
+        unpriced.seed("test", wallet(10_000_000_000, &[])).await;
+        assert_eq!(unpriced.buying_power_usd("test").await, None);
+    }
+
+    #[tokio::test]
+    async fn reconcile_records_drift_beyond_tolerance() {
+        let (balances, db) = (tracker(100.0), db());
+        hold(&db, "test", "within", 1_000_000);
+        hold(&db, "test", "beyond", 1_000_000);
+        hold(&db, "test", "missing", 1_000_000);
+        // The default tolerance is 1%.
+        balances.seed("default", wallet(0, &[("within", 990_000), ("beyond", 980_000)])).await;
+
+        let mut drifted: Vec<(String, u64, u64)> = balances.reconcile(&db).await.unwrap()
+            .into_iter().map(|d| (d.token_address, d.db_amount, d.chain_amount)).collect();
+        drifted.sort();
+        assert_eq!(drifted, vec![("beyond".to_string(), 1_000_000, 980_000), ("missing".to_string(), 1_000_000, 0)]);
+    }
+
+    #[tokio::test]
+    async fn reconcile_waits_for_balances_and_ignores_untraded_tokens() {
+        let (balances, db) = (tracker(100.0), db());
+        hold(&db, "test", "token", 1_000_000);
+        assert!(balances.reconcile(&db).await.unwrap().is_empty(), "nothing to compare before a refresh");
+
+        balances.seed("default", wallet(0, &[("token", 1_000_000), ("airdrop", 5)])).await;
+        assert!(balances.reconcile(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reconcile_compares_each_key_with_its_own_strategies() {
+        let (balances, db) = (tracker(100.0), db());
+        hold(&db, "momentum_v1", "token", 1_000_000);
+        hold(&db, "meanrev_v1", "token", 500_000);
+        // Together the wallets hold what the DB does, but split the other way round.
+        balances.seed("momentum", wallet(0, &[("token", 500_000)])).await;
+        assert_eq!(balances.reconcile(&db).await.unwrap().len(), 1, "meanrev's wallet is not fetched yet");
+
+        balances.seed("meanrev", wallet(0, &[("token", 1_000_000)])).await;
+        let mut drifted: Vec<(String, u64, u64)> = balances.reconcile(&db).await.unwrap()
+            .into_iter().map(|d| (d.signer_key, d.db_amount, d.chain_amount)).collect();
+        drifted.sort();
+        assert_eq!(drifted, vec![("meanrev".to_string(), 500_000, 1_000_000), ("momentum".to_string(), 1_000_000, 500_000)]);
+    }
+
+    #[test]
+    fn unmapped_strategies_trade_from_the_default_key() {
+        config::test_env();
+        assert_eq!(CONFIG.signer_key("momentum_v1"), "momentum");
+        assert_eq!(CONFIG.signer_key("new_strategy"), "default");
+    }
+}
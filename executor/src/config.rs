@@ -1,17 +1,19 @@
 // executor/src/config.rs
 use lazy_static::lazy_static;
-use std::env;
-
-// executor/src/config.rs
-use lazy_static::lazy_static;
-use std::env;
+use std::{collections::HashMap, env};
 
 pub struct Config {
     pub paper_trading_mode: bool,
-    pub jito_auth_keypair_path: String,
     pub solana_rpc_url: String,
     pub jito_rpc_url: String,
     pub signer_url: String,
+    pub signer_key_id: String,
+    pub signer_hmac_secret: String,
+    /// Strategy id → signer key; strategies without an entry trade from `signer_default_key`.
+    pub signer_strategy_keys: HashMap<String, String>,
+    pub signer_default_key: String,
+    pub signer_breaker_failures: u32,
+    pub signer_breaker_cooldown_secs: u64,
     pub global_max_position_usd: f64,
     pub portfolio_stop_loss_percent: f64,
     pub jupiter_api_url: String,
@@ -19,16 +21,63 @@ pub struct Config {
     pub jito_tip_lamports: u64,
     pub database_path: String,
     pub redis_url: String,
+    pub drift_perp_markets_path: Option<String>,
+    pub lending_adapter_url: Option<String>,
+    pub mock_venues: bool,
+    pub confirm_poll_ms: u64,
+    pub confirm_timeout_secs: u64,
+    pub retry_max_rebuilds: u32,
+    pub retry_max_requotes: u32,
+    pub retry_max_signer_retries: u32,
+    pub retry_max_venue_retries: u32,
+    pub retry_slippage_step_bps: u16,
+    pub retry_max_slippage_bps: u16,
+    pub retry_backoff_ms: u64,
+    pub max_signal_age_secs: u64,
+    pub max_price_impact_pct: f64,
+    pub pretrade_min_liquidity_multiple: f64,
+    pub pretrade_max_spread_bps: f64,
+    pub pretrade_min_size_usd: f64,
+    pub depth_max_age_secs: u64,
+    pub perp_mark_max_age_secs: u64,
+    pub slice_threshold_usd: f64,
+    pub slice_mode: String,
+    pub twap_slices: u32,
+    pub slice_interval_secs: u64,
+    pub slice_max_impact_pct: f64,
+    pub jito_bundles: bool,
+    pub jito_tip_floor_path: Option<String>,
+    pub tip_edge_fraction: f64,
+    pub tip_assumed_edge_bps: f64,
+    pub tip_percentile: u8,
+    pub tip_min_lamports: u64,
+    pub tip_max_lamports: u64,
+    pub submit_path: String,
+    pub cu_limit: u32,
+    pub priority_fee_percentile: u8,
+    pub priority_fee_min_micro_lamports: u64,
+    pub priority_fee_max_micro_lamports: u64,
+    pub sim_sol_fee_allowance_lamports: u64,
+    pub wallet_sol_reserve_lamports: u64,
+    pub balance_drift_tolerance_pct: f64,
 }
 
 impl Config {
     fn load() -> Self {
         Self {
             paper_trading_mode: env::var("PAPER_TRADING_MODE").unwrap_or_else(|_| "true".to_string()) == "true",
-            jito_auth_keypair_path: env::var("JITO_AUTH_KEYPAIR_FILENAME").expect("JITO_AUTH_KEYPAIR_FILENAME must be set"),
             solana_rpc_url: env::var("SOLANA_RPC_URL").expect("SOLANA_RPC_URL must be set"),
             jito_rpc_url: env::var("JITO_RPC_URL").expect("JITO_RPC_URL must be set"),
             signer_url: env::var("SIGNER_URL").expect("SIGNER_URL must be set"),
+            signer_key_id: env::var("SIGNER_KEY_ID").unwrap_or_else(|_| "executor".to_string()),
+            signer_hmac_secret: env::var("SIGNER_HMAC_SECRET").expect("SIGNER_HMAC_SECRET must be set"),
+            signer_strategy_keys: env::var("SIGNER_STRATEGY_KEYS").unwrap_or_default().split(',')
+                .filter_map(|entry| entry.split_once('='))
+                .map(|(strategy, key)| (strategy.trim().to_string(), key.trim().to_string()))
+                .collect(),
+            signer_default_key: env::var("SIGNER_DEFAULT_KEY").unwrap_or_else(|_| "default".to_string()),
+            signer_breaker_failures: env::var("SIGNER_BREAKER_FAILURES").unwrap_or_else(|_| "5".to_string()).parse().unwrap(),
+            signer_breaker_cooldown_secs: env::var("SIGNER_BREAKER_COOLDOWN_SECS").unwrap_or_else(|_| "30".to_string()).parse().unwrap(),
             global_max_position_usd: env::var("GLOBAL_MAX_POSITION_USD").expect("GLOBAL_MAX_POSITION_USD must be set").parse().unwrap(),
             portfolio_stop_loss_percent: env::var("PORTFOLIO_STOP_LOSS_PERCENT").expect("PORTFOLIO_STOP_LOSS_PERCENT must be set").parse().unwrap(),
             jupiter_api_url: env::var("JUPITER_API_URL").expect("JUPITER_API_URL must be set"),
@@ -36,10 +85,81 @@ impl Config {
             jito_tip_lamports: env::var("JITO_TIP_LAMPORTS").expect("JITO_TIP_LAMPORTS must be set").parse().unwrap(),
             database_path: env::var("DATABASE_PATH").expect("DATABASE_PATH must be set"),
             redis_url: env::var("REDIS_URL").expect("REDIS_URL must be set"),
+            drift_perp_markets_path: env::var("DRIFT_PERP_MARKETS_PATH").ok(),
+            lending_adapter_url: env::var("LENDING_ADAPTER_URL").ok(),
+            mock_venues: env::var("MOCK_VENUES").unwrap_or_else(|_| "false".to_string()) == "true",
+            confirm_poll_ms: env::var("CONFIRM_POLL_MS").unwrap_or_else(|_| "500".to_string()).parse().unwrap(),
+            confirm_timeout_secs: env::var("CONFIRM_TIMEOUT_SECS").unwrap_or_else(|_| "90".to_string()).parse().unwrap(),
+            retry_max_rebuilds: env::var("RETRY_MAX_REBUILDS").unwrap_or_else(|_| "2".to_string()).parse().unwrap(),
+            retry_max_requotes: env::var("RETRY_MAX_REQUOTES").unwrap_or_else(|_| "2".to_string()).parse().unwrap(),
+            retry_max_signer_retries: env::var("RETRY_MAX_SIGNER_RETRIES").unwrap_or_else(|_| "3".to_string()).parse().unwrap(),
+            retry_max_venue_retries: env::var("RETRY_MAX_VENUE_RETRIES").unwrap_or_else(|_| "3".to_string()).parse().unwrap(),
+            retry_slippage_step_bps: env::var("RETRY_SLIPPAGE_STEP_BPS").unwrap_or_else(|_| "50".to_string()).parse().unwrap(),
+            retry_max_slippage_bps: env::var("RETRY_MAX_SLIPPAGE_BPS").unwrap_or_else(|_| "300".to_string()).parse().unwrap(),
+            retry_backoff_ms: env::var("RETRY_BACKOFF_MS").unwrap_or_else(|_| "500".to_string()).parse().unwrap(),
+            max_signal_age_secs: env::var("MAX_SIGNAL_AGE_SECS").unwrap_or_else(|_| "30".to_string()).parse().unwrap(),
+            max_price_impact_pct: env::var("MAX_PRICE_IMPACT_PCT").unwrap_or_else(|_| "2.0".to_string()).parse().unwrap(),
+            pretrade_min_liquidity_multiple: env::var("PRETRADE_MIN_LIQUIDITY_MULTIPLE").unwrap_or_else(|_| "20.0".to_string()).parse().unwrap(),
+            pretrade_max_spread_bps: env::var("PRETRADE_MAX_SPREAD_BPS").unwrap_or_else(|_| "150.0".to_string()).parse().unwrap(),
+            pretrade_min_size_usd: env::var("PRETRADE_MIN_SIZE_USD").unwrap_or_else(|_| "10.0".to_string()).parse().unwrap(),
+            depth_max_age_secs: env::var("DEPTH_MAX_AGE_SECS").unwrap_or_else(|_| "15".to_string()).parse().unwrap(),
+            perp_mark_max_age_secs: env::var("PERP_MARK_MAX_AGE_SECS").unwrap_or_else(|_| "30".to_string()).parse().unwrap(),
+            slice_threshold_usd: env::var("SLICE_THRESHOLD_USD").unwrap_or_else(|_| "500.0".to_string()).parse().unwrap(),
+            slice_mode: env::var("SLICE_MODE").unwrap_or_else(|_| "twap".to_string()),
+            twap_slices: env::var("TWAP_SLICES").unwrap_or_else(|_| "4".to_string()).parse().unwrap(),
+            slice_interval_secs: env::var("SLICE_INTERVAL_SECS").unwrap_or_else(|_| "20".to_string()).parse().unwrap(),
+            slice_max_impact_pct: env::var("SLICE_MAX_IMPACT_PCT").unwrap_or_else(|_| "0.5".to_string()).parse().unwrap(),
+            jito_bundles: env::var("JITO_BUNDLES").unwrap_or_else(|_| "false".to_string()) == "true",
+            jito_tip_floor_path: env::var("JITO_TIP_FLOOR_PATH").ok(),
+            tip_edge_fraction: env::var("TIP_EDGE_FRACTION").unwrap_or_else(|_| "0.1".to_string()).parse().unwrap(),
+            tip_assumed_edge_bps: env::var("TIP_ASSUMED_EDGE_BPS").unwrap_or_else(|_| "100.0".to_string()).parse().unwrap(),
+            tip_percentile: env::var("TIP_PERCENTILE").unwrap_or_else(|_| "50".to_string()).parse().unwrap(),
+            tip_min_lamports: env::var("TIP_MIN_LAMPORTS").unwrap_or_else(|_| "1000".to_string()).parse().unwrap(),
+            tip_max_lamports: env::var("TIP_MAX_LAMPORTS").unwrap_or_else(|_| "1000000".to_string()).parse().unwrap(),
+            submit_path: env::var("SUBMIT_PATH").unwrap_or_else(|_| "jito".to_string()),
+            cu_limit: env::var("CU_LIMIT").unwrap_or_else(|_| "300000".to_string()).parse().unwrap(),
+            priority_fee_percentile: env::var("PRIORITY_FEE_PERCENTILE").unwrap_or_else(|_| "75".to_string()).parse().unwrap(),
+            priority_fee_min_micro_lamports: env::var("PRIORITY_FEE_MIN_MICRO_LAMPORTS").unwrap_or_else(|_| "1000".to_string()).parse().unwrap(),
+            priority_fee_max_micro_lamports: env::var("PRIORITY_FEE_MAX_MICRO_LAMPORTS").unwrap_or_else(|_| "2000000".to_string()).parse().unwrap(),
+            sim_sol_fee_allowance_lamports: env::var("SIM_SOL_FEE_ALLOWANCE_LAMPORTS").unwrap_or_else(|_| "5000000".to_string()).parse().unwrap(),
+            wallet_sol_reserve_lamports: env::var("WALLET_SOL_RESERVE_LAMPORTS").unwrap_or_else(|_| "50000000".to_string()).parse().unwrap(),
+            balance_drift_tolerance_pct: env::var("BALANCE_DRIFT_TOLERANCE_PCT").unwrap_or_else(|_| "1.0".to_string()).parse().unwrap(),
         }
     }
+
+    /// The signer key a strategy trades from: its `SIGNER_STRATEGY_KEYS` entry, else `SIGNER_DEFAULT_KEY`,
+    /// which defaults to the `default` key the signer loads when `SIGNER_KEYS` is unset.
+    pub fn signer_key(&self, strategy_id: &str) -> String {
+        self.signer_strategy_keys.get(strategy_id).cloned().unwrap_or_else(|| self.signer_default_key.clone())
+    }
 }
 
 lazy_static! {
     pub static ref CONFIG: Config = Config::load();
 }
+
+/// Sets the required variables, live mode included, before the first test reads `CONFIG`.
+#[cfg(test)]
+pub fn test_env() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        for (key, value) in [
+            ("PAPER_TRADING_MODE", "false"),
+            ("SOLANA_RPC_URL", "http://127.0.0.1:1"),
+            ("JITO_RPC_URL", "http://127.0.0.1:1"),
+            // Nothing listens here, so signer calls fail fast.
+            ("SIGNER_URL", "http://127.0.0.1:1"),
+            ("SIGNER_HMAC_SECRET", "test"),
+            ("SIGNER_STRATEGY_KEYS", "momentum_v1=momentum,meanrev_v1=meanrev"),
+            ("GLOBAL_MAX_POSITION_USD", "1000"),
+            ("PORTFOLIO_STOP_LOSS_PERCENT", "10"),
+            ("JUPITER_API_URL", "http://127.0.0.1:1"),
+            ("SLIPPAGE_BPS", "50"),
+            ("JITO_TIP_LAMPORTS", "10000"),
+            ("DATABASE_PATH", "/tmp/executor-test.db"),
+            ("REDIS_URL", "redis://127.0.0.1:1"),
+        ] {
+            env::set_var(key, value);
+        }
+    });
+}
@@ -0,0 +1,110 @@
+// executor/src/confirmation.rs
+use crate::{config::CONFIG, rpc::SolanaRpc, venues::{Confirmation, ExecutionVenue}};
+use anyhow::Result;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::{str::FromStr, sync::Arc, time::{Duration, Instant}};
+use tracing::{debug, warn};
+
+pub struct ConfirmationPolicy {
+    pub poll_interval: Duration,
+    /// Give up polling after this long; the trade is marked FAILED, never resubmitted blindly.
+    pub max_wait: Duration,
+}
+
+impl ConfirmationPolicy {
+    pub fn from_config() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(CONFIG.confirm_poll_ms),
+            max_wait: Duration::from_secs(CONFIG.confirm_timeout_secs),
+        }
+    }
+}
+
+/// What actually landed, parsed from the transaction meta.
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub amount_raw: u64,
+    pub price_usd: f64,
+    pub size_usd: f64,
+}
+
+pub struct ConfirmationTracker {
+    rpc: Arc<dyn SolanaRpc>,
+    sol_price: Arc<tokio::sync::Mutex<f64>>,
+    pub policy: ConfirmationPolicy,
+}
+
+impl ConfirmationTracker {
+    pub fn new(rpc: Arc<dyn SolanaRpc>, sol_price: Arc<tokio::sync::Mutex<f64>>, policy: ConfirmationPolicy) -> Self {
+        Self { rpc, sol_price, policy }
+    }
+
+    /// Polls the venue until the order is confirmed, failed or expired. Returns `Pending` on timeout.
+    pub async fn wait(&self, venue: &dyn ExecutionVenue, signature: &str) -> Result<Confirmation> {
+        let started = Instant::now();
+        loop {
+            match venue.confirm(signature).await {
+                Ok(Confirmation::Pending) => {}
+                Ok(terminal) => return Ok(terminal),
+                Err(e) => warn!(%signature, %e, "signature status poll failed"),
+            }
+            if started.elapsed() >= self.policy.max_wait {
+                return Ok(Confirmation::Pending);
+            }
+            tokio::time::sleep(self.policy.poll_interval).await;
+        }
+    }
+
+    /// Reads the real amounts exchanged for `mint` from a confirmed spot transaction.
+    /// `None` when the signature is not an on-chain one or the node has not indexed it.
+    pub async fn spot_fill(&self, signature: &str, mint: &str, tip_lamports: u64) -> Result<Option<Fill>> {
+        let Ok(sig) = Signature::from_str(signature) else { return Ok(None) };
+        let Some(meta) = self.rpc.transaction_meta(&sig).await? else { return Ok(None) };
+        let Some(&token_delta) = meta.token_deltas.get(mint) else { return Ok(None) };
+        let decimals = match meta.token_decimals.get(mint) {
+            Some(&decimals) => decimals,
+            None => self.rpc.token_decimals(&Pubkey::from_str(mint)?).await?,
+        };
+
+        // Buys: SOL out = -(delta) - fee - tip. Sells: SOL in = delta + fee + tip.
+        let swapped_lamports = (meta.sol_delta_lamports + meta.fee_lamports as i64 + tip_lamports as i64).unsigned_abs();
+        let amount_raw = token_delta.unsigned_abs() as u64;
+        if amount_raw == 0 { return Ok(None); }
+
+        // The trade has landed, so without a price fall back to the quote rather than fail it.
+        let sol_usd = *self.sol_price.lock().await;
+        if sol_usd <= 0.0 { return Ok(None); }
+        let size_usd = swapped_lamports as f64 / 1e9 * sol_usd;
+        let tokens = amount_raw as f64 / 10f64.powi(decimals as i32);
+        let fill = Fill { amount_raw, price_usd: size_usd / tokens, size_usd };
+        debug!(%signature, ?fill, "parsed spot fill");
+        Ok(Some(fill))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::{StubRpc, TxMeta};
+
+    #[tokio::test]
+    async fn spot_fill_resolves_missing_decimals_from_the_mint() {
+        let rpc = Arc::new(StubRpc::default());
+        let signature = Signature::new_unique();
+        let mint = Pubkey::new_unique().to_string();
+        // Bought 2 tokens of a 6-decimal mint for 1 SOL plus a 5000-lamport fee; the meta has no decimals.
+        rpc.seed(signature, TxMeta {
+            fee_lamports: 5_000,
+            sol_delta_lamports: -1_000_005_000,
+            token_deltas: [(mint.clone(), 2_000_000)].into(),
+            ..Default::default()
+        });
+        let policy = ConfirmationPolicy { poll_interval: Duration::from_millis(1), max_wait: Duration::from_millis(1) };
+        let tracker = ConfirmationTracker::new(rpc, Arc::new(tokio::sync::Mutex::new(100.0)), policy);
+
+        let fill = tracker.spot_fill(&signature.to_string(), &mint, 0).await.unwrap().unwrap();
+        assert_eq!(fill.amount_raw, 2_000_000);
+        assert_eq!(fill.size_usd, 100.0);
+        assert_eq!(fill.price_usd, 50.0);
+    }
+}
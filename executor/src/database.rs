@@ -1,12 +1,15 @@
 // executor/src/database.rs
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection, Row};
-use shared_models::OrderDetails;
-use std::path::Path;
+use rusqlite::{params, Connection};
+use crate::{jupiter::JupiterQuote, pretrade::{PreTradeCheck, Verdict}};
+use shared_models::{LegStatus, OrderDetails};
+use std::{path::Path, sync::{Mutex, MutexGuard}};
 use tracing::info;
 
 // --- Trade Record Struct ---
+/// A row of the trade log. The executor only writes trades; this is for code reading them back.
+#[allow(dead_code)]
 #[derive(Debug)]
 pub struct TradeRecord {
     pub id: i64,
@@ -25,8 +28,9 @@ pub struct TradeRecord {
 }
 
 // --- Database Manager ---
+/// The connection sits behind a mutex so the database can be shared by `Send` futures and tasks.
 pub struct Database {
-    conn: Connection,
+    conn: Mutex<Connection>,
 }
 
 impl Database {
@@ -38,7 +42,11 @@ impl Database {
         let conn = Connection::open(path).with_context(|| format!("Failed to open database at {}", db_path))?;
         info!("Database opened at {}", db_path);
         Self::init_db(&conn)?;
-        Ok(Self { conn })
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap()
     }
 
     fn init_db(conn: &Connection) -> Result<()> {
@@ -49,7 +57,7 @@ impl Database {
                 token_address TEXT NOT NULL,
                 symbol TEXT NOT NULL,
                 amount_usd REAL NOT NULL,
-                status TEXT NOT NULL, -- PENDING, OPEN, CLOSED_PROFIT, CLOSED_LOSS, CANCELED
+                status TEXT NOT NULL, -- PENDING, SUBMITTED, OPEN, CLOSED_PROFIT, CLOSED_LOSS, CANCELED, FAILED, REJECTED, UNWOUND
                 signature TEXT,
                 entry_time INTEGER NOT NULL,
                 entry_price_usd REAL NOT NULL,
@@ -60,14 +68,98 @@ impl Database {
             )",
             [],
         )?;
+        // Multi-leg groups: legs of one hedged order share a group_id.
+        Self::ensure_column(conn, "trades", "group_id", "TEXT")?;
+        Self::ensure_column(conn, "trades", "leg_index", "INTEGER")?;
+        Self::ensure_column(conn, "trades", "leg_status", "TEXT")?;
+        Self::ensure_column(conn, "trades", "instrument", "TEXT")?;
+        // Spot inventory and short-venue bookkeeping.
+        Self::ensure_column(conn, "trades", "side", "TEXT")?;
+        Self::ensure_column(conn, "trades", "filled_amount", "INTEGER")?;
+        Self::ensure_column(conn, "trades", "short_venue", "TEXT")?;
+        // Confirmation tracking
+        Self::ensure_column(conn, "trades", "failure_reason", "TEXT")?;
+        Self::ensure_column(conn, "trades", "submit_attempts", "INTEGER")?;
+        // The Jupiter quote the swap was built from
+        Self::ensure_column(conn, "trades", "quote_slot", "INTEGER")?;
+        Self::ensure_column(conn, "trades", "quote_route", "TEXT")?;
+        Self::ensure_column(conn, "trades", "quote_out_amount", "INTEGER")?;
+        Self::ensure_column(conn, "trades", "quote_min_out_amount", "INTEGER")?;
+        Self::ensure_column(conn, "trades", "quote_price_impact_pct", "REAL")?;
+        // Pre-trade liquidity check
+        Self::ensure_column(conn, "trades", "pretrade_decision", "TEXT")?;
+        Self::ensure_column(conn, "trades", "pretrade_requested_usd", "REAL")?;
+        Self::ensure_column(conn, "trades", "pretrade_allowed_usd", "REAL")?;
+        Self::ensure_column(conn, "trades", "pretrade_liquidity_usd", "REAL")?;
+        Self::ensure_column(conn, "trades", "pretrade_spread_bps", "REAL")?;
+        // Sliced orders: each child swap is a trade row pointing at its parent.
+        Self::ensure_column(conn, "trades", "parent_id", "INTEGER")?;
+        // Landing costs of the confirmed attempt, for cost attribution
+        Self::ensure_column(conn, "trades", "tip_lamports", "INTEGER")?;
+        Self::ensure_column(conn, "trades", "submit_path", "TEXT")?;
+        Self::ensure_column(conn, "trades", "priority_fee_lamports", "INTEGER")?;
+        Self::ensure_column(conn, "trades", "simulated_units", "INTEGER")?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS parent_orders (
+                id INTEGER PRIMARY KEY,
+                strategy_id TEXT NOT NULL,
+                token_address TEXT NOT NULL,
+                side TEXT NOT NULL,
+                algo TEXT NOT NULL,
+                target_usd REAL NOT NULL,
+                filled_usd REAL NOT NULL DEFAULT 0,
+                children INTEGER NOT NULL DEFAULT 0,
+                status TEXT NOT NULL, -- WORKING, COMPLETED, CANCELED, FAILED
+                reason TEXT,
+                created_time INTEGER NOT NULL,
+                updated_time INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS balance_drift (
+                id INTEGER PRIMARY KEY,
+                token_address TEXT NOT NULL,
+                db_amount INTEGER NOT NULL,
+                chain_amount INTEGER NOT NULL,
+                detected_time INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS execution_attempts (
+                id INTEGER PRIMARY KEY,
+                trade_id INTEGER NOT NULL REFERENCES trades(id),
+                attempt INTEGER NOT NULL,
+                failure_class TEXT NOT NULL,
+                error TEXT NOT NULL,
+                slippage_bps INTEGER NOT NULL,
+                attempt_time INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Adds `column` to `table` if an older database predates it.
+    fn ensure_column(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+        if !exists {
+            conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl), [])?;
+        }
         Ok(())
     }
 
     pub fn log_trade_attempt(&self, details: &OrderDetails, strategy_id: &str, entry_price_usd: f64) -> Result<i64> {
         let now: DateTime<Utc> = Utc::now();
-        self.conn.execute(
-            "INSERT INTO trades (strategy_id, token_address, symbol, amount_usd, status, entry_time, entry_price_usd, confidence)
-             VALUES (?1, ?2, ?3, ?4, 'PENDING', ?5, ?6, ?7)",
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO trades (strategy_id, token_address, symbol, amount_usd, status, entry_time, entry_price_usd, confidence, side)
+             VALUES (?1, ?2, ?3, ?4, 'PENDING', ?5, ?6, ?7, ?8)",
             params![
                 strategy_id,
                 details.token_address,
@@ -76,18 +168,227 @@ impl Database {
                 now.timestamp(),
                 entry_price_usd,
                 details.confidence,
+                format!("{:?}", details.side),
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn set_trade_leg(&self, trade_id: i64, group_id: &str, leg_index: usize) -> Result<()> {
+        self.conn().execute(
+            "UPDATE trades SET group_id = ?1, leg_index = ?2, leg_status = ?3 WHERE id = ?4",
+            params![group_id, leg_index as i64, LegStatus::Pending.as_str(), trade_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_leg_status(&self, group_id: &str, leg_index: usize, status: LegStatus) -> Result<()> {
+        self.conn().execute(
+            "UPDATE trades SET leg_status = ?1 WHERE group_id = ?2 AND leg_index = ?3",
+            params![status.as_str(), group_id, leg_index as i64],
+        )?;
+        Ok(())
+    }
+
+    pub fn create_parent_order(&self, details: &OrderDetails, strategy_id: &str, algo: &str, target_usd: f64) -> Result<i64> {
+        let now = Utc::now().timestamp();
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO parent_orders (strategy_id, token_address, side, algo, target_usd, status, created_time, updated_time)
+             VALUES (?1, ?2, ?3, ?4, ?5, 'WORKING', ?6, ?6)",
+            params![strategy_id, details.token_address, format!("{:?}", details.side), algo, target_usd, now],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn set_trade_parent(&self, trade_id: i64, parent_id: i64) -> Result<()> {
+        self.conn().execute("UPDATE trades SET parent_id = ?1 WHERE id = ?2", params![parent_id, trade_id])?;
+        Ok(())
+    }
+
+    pub fn add_parent_fill(&self, parent_id: i64, filled_usd: f64) -> Result<()> {
+        self.conn().execute(
+            "UPDATE parent_orders SET filled_usd = filled_usd + ?1, children = children + 1, updated_time = ?2 WHERE id = ?3",
+            params![filled_usd, Utc::now().timestamp(), parent_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn finish_parent_order(&self, parent_id: i64, status: &str, reason: Option<&str>) -> Result<()> {
+        self.conn().execute(
+            "UPDATE parent_orders SET status = ?1, reason = ?2, updated_time = ?3 WHERE id = ?4",
+            params![status, reason, Utc::now().timestamp(), parent_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_instrument(&self, trade_id: i64, instrument: &str) -> Result<()> {
+        self.conn().execute("UPDATE trades SET instrument = ?1 WHERE id = ?2", params![instrument, trade_id])?;
+        Ok(())
+    }
+
+    pub fn set_trade_status(&self, trade_id: i64, status: &str) -> Result<()> {
+        self.conn().execute("UPDATE trades SET status = ?1 WHERE id = ?2", params![status, trade_id])?;
+        Ok(())
+    }
+
+    pub fn set_filled_amount(&self, trade_id: i64, filled_amount: u64) -> Result<()> {
+        self.conn().execute("UPDATE trades SET filled_amount = ?1 WHERE id = ?2", params![filled_amount as i64, trade_id])?;
+        Ok(())
+    }
+
+    pub fn set_short_venue(&self, trade_id: i64, venue: &str) -> Result<()> {
+        self.conn().execute("UPDATE trades SET short_venue = ?1 WHERE id = ?2", params![venue, trade_id])?;
+        Ok(())
+    }
+
+    /// Net raw units of `token` per strategy from open spot longs less inventory already sold,
+    /// before netting across strategies: a strategy's shorts may offset another's longs only if
+    /// they trade from the same wallet. Borrowed shorts are excluded: those tokens were never ours.
+    pub fn get_spot_inventory_by_strategy(&self, token: &str) -> Result<Vec<(String, i64)>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT strategy_id, SUM(CASE WHEN side = 'Long' THEN filled_amount ELSE -filled_amount END)
+             FROM trades
+             WHERE token_address = ?1 AND instrument = 'Spot' AND status = 'OPEN' AND filled_amount IS NOT NULL
+               AND (short_venue IS NULL OR short_venue = 'inventory')
+             GROUP BY strategy_id",
+        )?;
+        let nets = stmt.query_map(params![token], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(nets)
+    }
+
+    /// Every token ever traded on spot, i.e. every token the wallet might hold because of us.
+    pub fn get_traded_spot_tokens(&self) -> Result<Vec<String>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT DISTINCT token_address FROM trades WHERE instrument = 'Spot'")?;
+        let tokens = stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(tokens)
+    }
+
+    pub fn log_balance_drift(&self, token: &str, db_amount: u64, chain_amount: u64) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO balance_drift (token_address, db_amount, chain_amount, detected_time) VALUES (?1, ?2, ?3, ?4)",
+            params![token, db_amount as i64, chain_amount as i64, Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_entry_price(&self, trade_id: i64, entry_price_usd: f64) -> Result<()> {
+        self.conn().execute("UPDATE trades SET entry_price_usd = ?1 WHERE id = ?2", params![entry_price_usd, trade_id])?;
+        Ok(())
+    }
+
+    /// Records which quote the swap is built from; overwritten on re-quote.
+    pub fn set_quote(&self, trade_id: i64, quote: &JupiterQuote) -> Result<()> {
+        self.conn().execute(
+            "UPDATE trades SET quote_slot = ?1, quote_route = ?2, quote_out_amount = ?3, quote_min_out_amount = ?4,
+                    quote_price_impact_pct = ?5
+             WHERE id = ?6",
+            params![
+                quote.context_slot.map(|s| s as i64),
+                quote.route_label(),
+                quote.out_amount()? as i64,
+                quote.min_out_amount()? as i64,
+                quote.price_impact() * 100.0,
+                trade_id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Records the pre-trade verdict. A downsized order logs its allowed size; the fill overwrites `amount_usd`.
+    pub fn log_pretrade(&self, trade_id: i64, check: &PreTradeCheck) -> Result<()> {
+        let allowed_usd = match &check.verdict {
+            Verdict::Accept => check.size_usd,
+            Verdict::Downsize(size) => *size,
+            Verdict::Reject(_) => 0.0,
+        };
+        self.conn().execute(
+            "UPDATE trades SET pretrade_decision = ?1, pretrade_requested_usd = ?2, pretrade_allowed_usd = ?3,
+                    pretrade_liquidity_usd = ?4, pretrade_spread_bps = ?5, quote_price_impact_pct = ?6
+             WHERE id = ?7",
+            params![
+                check.verdict.as_str(),
+                check.size_usd,
+                allowed_usd,
+                check.liquidity_usd,
+                check.spread_bps,
+                check.price_impact_pct,
+                trade_id,
             ],
         )?;
-        Ok(self.conn.last_insert_rowid())
+        Ok(())
+    }
+
+    pub fn mark_rejected(&self, trade_id: i64, reason: &str) -> Result<()> {
+        self.conn().execute(
+            "UPDATE trades SET status = 'REJECTED', failure_reason = ?1 WHERE id = ?2",
+            params![reason, trade_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn log_execution_attempt(&self, trade_id: i64, attempt: u32, failure_class: &str, error: &str, slippage_bps: u16) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO execution_attempts (trade_id, attempt, failure_class, error, slippage_bps, attempt_time)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![trade_id, attempt, failure_class, error, slippage_bps, Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// Records a submitted (not yet confirmed) transaction.
+    pub fn set_submitted(&self, trade_id: i64, signature: &str, attempt: u32) -> Result<()> {
+        self.conn().execute(
+            "UPDATE trades SET status = 'SUBMITTED', signature = ?1, submit_attempts = ?2 WHERE id = ?3",
+            params![signature, attempt, trade_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_simulated_units(&self, trade_id: i64, units_consumed: Option<u64>) -> Result<()> {
+        self.conn().execute(
+            "UPDATE trades SET simulated_units = ?1 WHERE id = ?2",
+            params![units_consumed.map(|u| u as i64), trade_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_submission_cost(&self, trade_id: i64, path: &str, tip_lamports: u64, priority_fee_lamports: u64) -> Result<()> {
+        self.conn().execute(
+            "UPDATE trades SET submit_path = ?1, tip_lamports = ?2, priority_fee_lamports = ?3 WHERE id = ?4",
+            params![path, tip_lamports as i64, priority_fee_lamports as i64, trade_id],
+        )?;
+        Ok(())
+    }
+
+    /// Overwrites the quoted entry with what actually landed on-chain.
+    pub fn record_fill(&self, trade_id: i64, filled_amount: u64, fill_price_usd: f64, amount_usd: f64) -> Result<()> {
+        self.conn().execute(
+            "UPDATE trades SET filled_amount = ?1, entry_price_usd = ?2, amount_usd = ?3 WHERE id = ?4",
+            params![filled_amount as i64, fill_price_usd, amount_usd, trade_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn mark_failed(&self, trade_id: i64, reason: &str) -> Result<()> {
+        self.conn().execute(
+            "UPDATE trades SET status = 'FAILED', failure_reason = ?1 WHERE id = ?2",
+            params![reason, trade_id],
+        )?;
+        Ok(())
     }
 
     pub fn open_trade(&self, trade_id: i64, signature: &str) -> Result<()> {
-        self.conn.execute("UPDATE trades SET status = 'OPEN', signature = ?1 WHERE id = ?2", params![signature, trade_id])?;
+        self.conn().execute("UPDATE trades SET status = 'OPEN', signature = ?1 WHERE id = ?2", params![signature, trade_id])?;
         Ok(())
     }
-    
+
+    #[allow(dead_code)]
     pub fn get_all_trades(&self) -> Result<Vec<TradeRecord>> {
-        let mut stmt = self.conn.prepare("SELECT * FROM trades ORDER BY entry_time DESC")?;
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT * FROM trades ORDER BY entry_time DESC")?;
         let trades_iter = stmt.query_map([], |row| {
             Ok(TradeRecord {
                 id: row.get(0)?,
@@ -109,9 +410,20 @@ impl Database {
         trades_iter.collect::<Result<Vec<TradeRecord>, rusqlite::Error>>().map_err(anyhow::Error::from)
     }
 
+    /// Status, filled USD and child count of a parent order.
+    #[cfg(test)]
+    pub fn parent_order(&self, parent_id: i64) -> Result<(String, f64, i64)> {
+        let parent = self.conn().query_row(
+            "SELECT status, filled_usd, children FROM parent_orders WHERE id = ?1",
+            params![parent_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        Ok(parent)
+    }
+
     pub fn update_trade_pnl(&self, trade_id: i64, status: &str, close_price_usd: f64, pnl_usd: f64) -> Result<()> {
         let now: DateTime<Utc> = Utc::now();
-        self.conn.execute(
+        self.conn().execute(
             "UPDATE trades SET status = ?1, close_time = ?2, close_price_usd = ?3, pnl_usd = ?4 WHERE id = ?5",
             params![status, now.timestamp(), close_price_usd, pnl_usd, trade_id],
         )?;
@@ -120,7 +432,7 @@ impl Database {
 
     // P-6: New function to get total PnL for portfolio monitor
     pub fn get_total_pnl(&self) -> Result<f64> {
-        let total: f64 = self.conn.query_row(
+        let total: f64 = self.conn().query_row(
             "SELECT SUM(pnl_usd) FROM trades WHERE status LIKE 'CLOSED_%'",
             [],
             |row| row.get(0),
@@ -0,0 +1,388 @@
+// executor/src/drift.rs
+use crate::{drift_markets::PerpMarketRegistry, rpc::SolanaRpc};
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use shared_models::Side;
+use solana_sdk::{
+    hash::{hash, Hash},
+    instruction::{AccountMeta, Instruction},
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    system_program, sysvar,
+    transaction::VersionedTransaction,
+};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+use tokio::sync::Mutex;
+
+pub const DRIFT_PROGRAM_ID: &str = "dRiftyHA39MWEi3m9aunc5MzRF1JYuBsbn6VPcn33UH";
+
+/// Sub-account every order goes through.
+const SUB_ACCOUNT_ID: u16 = 0;
+/// USDC, the quote spot market every perp margins against.
+const QUOTE_SPOT_MARKET_INDEX: u16 = 0;
+/// Both `PerpMarket.amm.oracle` and `SpotMarket.oracle` follow the discriminator and the
+/// market's own pubkey.
+const MARKET_ORACLE_OFFSET: usize = 8 + 32;
+/// `User.perp_positions` follows the discriminator, authority, delegate, name and eight
+/// 40-byte spot positions.
+const USER_PERP_POSITIONS_OFFSET: usize = 8 + 32 * 3 + 8 * 40;
+const PERP_POSITION_LEN: usize = 96;
+const PERP_POSITION_SLOTS: usize = 8;
+
+fn program_id() -> Pubkey {
+    Pubkey::from_str(DRIFT_PROGRAM_ID).unwrap()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &program_id()).0
+}
+
+pub fn state_pda() -> Pubkey {
+    pda(&[b"drift_state"])
+}
+
+pub fn user_pda(authority: &Pubkey) -> Pubkey {
+    pda(&[b"user", authority.as_ref(), &SUB_ACCOUNT_ID.to_le_bytes()])
+}
+
+pub fn user_stats_pda(authority: &Pubkey) -> Pubkey {
+    pda(&[b"user_stats", authority.as_ref()])
+}
+
+pub fn perp_market_pda(market_index: u16) -> Pubkey {
+    pda(&[b"perp_market", &market_index.to_le_bytes()])
+}
+
+pub fn spot_market_pda(market_index: u16) -> Pubkey {
+    pda(&[b"spot_market", &market_index.to_le_bytes()])
+}
+
+/// Anchor instruction discriminator.
+fn discriminator(name: &str) -> [u8; 8] {
+    let mut out = [0u8; 8];
+    out.copy_from_slice(&hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+    out
+}
+
+/// Drift prices carry six decimals.
+const PRICE_PRECISION: f64 = 1e6;
+
+/// Worst price an order may fill at: `slippage_bps` past the oracle price, against the order.
+pub fn limit_price(side: &Side, oracle_price_usd: f64, slippage_bps: u16) -> Result<u64> {
+    if !(oracle_price_usd.is_finite() && oracle_price_usd > 0.0) {
+        return Err(anyhow!("cannot bound an order at oracle price {}", oracle_price_usd));
+    }
+    let slippage = slippage_bps as f64 / 10_000.0;
+    let factor = match side { Side::Long => 1.0 + slippage, Side::Short => 1.0 - slippage };
+    Ok((oracle_price_usd * factor * PRICE_PRECISION).round() as u64)
+}
+
+/// Borsh encoding of Drift's `OrderParams` for a market order with default auction, which
+/// never fills past `price`.
+fn market_order_params(market_index: u16, side: &Side, base_asset_amount: u64, price: u64, reduce_only: bool) -> Vec<u8> {
+    let mut data = Vec::with_capacity(48);
+    data.push(0); // order_type: Market
+    data.push(1); // market_type: Perp
+    data.push(match side { Side::Long => 0, Side::Short => 1 }); // direction
+    data.push(0); // user_order_id
+    data.extend_from_slice(&base_asset_amount.to_le_bytes());
+    data.extend_from_slice(&price.to_le_bytes()); // limit: bounds the auction Drift derives from the oracle
+    data.extend_from_slice(&market_index.to_le_bytes());
+    data.push(reduce_only as u8);
+    data.push(0); // post_only: None
+    data.push(0); // immediate_or_cancel
+    data.push(0); // max_ts: None
+    data.push(0); // trigger_price: None
+    data.push(0); // trigger_condition: Above
+    data.push(0); // oracle_price_offset: None
+    data.push(0); // auction_duration: None
+    data.push(0); // auction_start_price: None
+    data.push(0); // auction_end_price: None
+    data
+}
+
+/// A user's position in one perp market, as Drift stores it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PerpPosition {
+    /// Signed base-asset units: positive long, negative short.
+    pub base_asset_amount: i64,
+    /// Signed quote units (USDC, 1e6) paid or received for the position, fees included.
+    pub quote_asset_amount: i64,
+    /// Orders still working in the market.
+    pub open_orders: u8,
+}
+
+/// The position in `market_index` from a `User` account; empty if the user holds none.
+fn parse_perp_position(data: &[u8], market_index: u16) -> Result<PerpPosition> {
+    let end = USER_PERP_POSITIONS_OFFSET + PERP_POSITION_SLOTS * PERP_POSITION_LEN;
+    let slots = data.get(USER_PERP_POSITIONS_OFFSET..end).ok_or_else(|| anyhow!("Drift user account is too short"))?;
+    let i64_at = |slot: &[u8], at: usize| i64::from_le_bytes(slot[at..at + 8].try_into().unwrap());
+    Ok(slots.chunks_exact(PERP_POSITION_LEN)
+        .map(|slot| (u16::from_le_bytes([slot[92], slot[93]]), PerpPosition {
+            base_asset_amount: i64_at(slot, 8),
+            quote_asset_amount: i64_at(slot, 16),
+            open_orders: slot[94],
+        }))
+        // Free slots are zeroed, so market 0 is only matched by a slot in use.
+        .find(|(index, position)| *index == market_index && *position != PerpPosition::default())
+        .map(|(_, position)| position)
+        .unwrap_or_default())
+}
+
+/// Builds Drift instructions for the signer's wallet, which the executor never holds a key for.
+/// Market and oracle accounts are read from chain once and cached.
+pub struct DriftInstructions {
+    rpc: Arc<dyn SolanaRpc>,
+    perp_markets: Arc<PerpMarketRegistry>,
+    oracles: Mutex<HashMap<Pubkey, Pubkey>>,
+}
+
+impl DriftInstructions {
+    pub fn new(rpc: Arc<dyn SolanaRpc>, perp_markets: Arc<PerpMarketRegistry>) -> Self {
+        Self { rpc, perp_markets, oracles: Mutex::new(HashMap::new()) }
+    }
+
+    async fn oracle_of(&self, market: &Pubkey) -> Result<Pubkey> {
+        if let Some(oracle) = self.oracles.lock().await.get(market) {
+            return Ok(*oracle);
+        }
+        let data = self.rpc.account_data(market).await?
+            .ok_or_else(|| anyhow!("Drift market account {} not found", market))?;
+        let bytes = data.get(MARKET_ORACLE_OFFSET..MARKET_ORACLE_OFFSET + 32)
+            .ok_or_else(|| anyhow!("Drift market account {} is too short", market))?;
+        let oracle = Pubkey::try_from(bytes)?;
+        self.oracles.lock().await.insert(*market, oracle);
+        Ok(oracle)
+    }
+
+    /// Oracles, then spot markets, then perp markets: what Drift loads its market maps from.
+    /// Every registered perp market is included, since margin is checked across all positions;
+    /// only `writable_market` is writable.
+    async fn remaining_accounts(&self, writable_market: Option<u16>) -> Result<Vec<AccountMeta>> {
+        let quote_market = spot_market_pda(QUOTE_SPOT_MARKET_INDEX);
+        let mut oracles = vec![self.oracle_of(&quote_market).await?];
+        let mut perps = Vec::new();
+        for market in self.perp_markets.all() {
+            let key = perp_market_pda(market.market_index);
+            let oracle = self.oracle_of(&key).await?;
+            if !oracles.contains(&oracle) {
+                oracles.push(oracle);
+            }
+            perps.push(if writable_market == Some(market.market_index) {
+                AccountMeta::new(key, false)
+            } else {
+                AccountMeta::new_readonly(key, false)
+            });
+        }
+        let mut accounts: Vec<AccountMeta> = oracles.into_iter().map(|o| AccountMeta::new_readonly(o, false)).collect();
+        accounts.push(AccountMeta::new_readonly(quote_market, false));
+        accounts.extend(perps);
+        Ok(accounts)
+    }
+
+    /// Creates the user stats and sub-account 0 for `authority` if they do not exist yet.
+    async fn init_user_if_missing(&self, authority: &Pubkey) -> Result<Vec<Instruction>> {
+        let mut ixs = Vec::new();
+        let user_stats = user_stats_pda(authority);
+        if self.rpc.account_data(&user_stats).await?.is_none() {
+            ixs.push(Instruction {
+                program_id: program_id(),
+                accounts: vec![
+                    AccountMeta::new(user_stats, false),
+                    AccountMeta::new(state_pda(), false),
+                    AccountMeta::new_readonly(*authority, true),
+                    AccountMeta::new(*authority, true),
+                    AccountMeta::new_readonly(sysvar::rent::id(), false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+                data: discriminator("initialize_user_stats").to_vec(),
+            });
+        }
+        let user = user_pda(authority);
+        if self.rpc.account_data(&user).await?.is_none() {
+            let mut data = discriminator("initialize_user").to_vec();
+            data.extend_from_slice(&SUB_ACCOUNT_ID.to_le_bytes());
+            let mut name = [b' '; 32];
+            name[..8].copy_from_slice(b"executor");
+            data.extend_from_slice(&name);
+            ixs.push(Instruction {
+                program_id: program_id(),
+                accounts: vec![
+                    AccountMeta::new(user, false),
+                    AccountMeta::new(user_stats, false),
+                    AccountMeta::new(state_pda(), false),
+                    AccountMeta::new_readonly(*authority, true),
+                    AccountMeta::new(*authority, true),
+                    AccountMeta::new_readonly(sysvar::rent::id(), false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+                data,
+            });
+        }
+        Ok(ixs)
+    }
+
+    /// Unsigned transaction placing a perp market order limited to `price`, creating the Drift
+    /// user first if needed.
+    pub async fn place_perp_order(&self, authority: &Pubkey, market_index: u16, side: &Side, base_asset_amount: u64, price: u64, reduce_only: bool) -> Result<String> {
+        let mut ixs = self.init_user_if_missing(authority).await?;
+        let mut data = discriminator("place_perp_order").to_vec();
+        data.extend(market_order_params(market_index, side, base_asset_amount, price, reduce_only));
+        let mut accounts = vec![
+            AccountMeta::new_readonly(state_pda(), false),
+            AccountMeta::new(user_pda(authority), false),
+            AccountMeta::new_readonly(*authority, true),
+        ];
+        accounts.extend(self.remaining_accounts(Some(market_index)).await?);
+        ixs.push(Instruction { program_id: program_id(), accounts, data });
+        unsigned_transaction(authority, &ixs)
+    }
+
+    /// `authority`'s current position in `market_index`; empty before its Drift user exists.
+    pub async fn perp_position(&self, authority: &Pubkey, market_index: u16) -> Result<PerpPosition> {
+        match self.rpc.account_data(&user_pda(authority)).await? {
+            Some(data) => parse_perp_position(&data, market_index),
+            None => Ok(PerpPosition::default()),
+        }
+    }
+
+    /// Unsigned transaction cancelling every open order on the sub-account.
+    pub async fn cancel_orders(&self, authority: &Pubkey) -> Result<String> {
+        let mut data = discriminator("cancel_orders").to_vec();
+        data.extend_from_slice(&[0, 0, 0]); // market_type, market_index, direction: all None
+        let mut accounts = vec![
+            AccountMeta::new_readonly(state_pda(), false),
+            AccountMeta::new(user_pda(authority), false),
+            AccountMeta::new_readonly(*authority, true),
+        ];
+        accounts.extend(self.remaining_accounts(None).await?);
+        unsigned_transaction(authority, &[Instruction { program_id: program_id(), accounts, data }])
+    }
+}
+
+/// Base64 v0 transaction with empty signature slots; the submitter stamps the blockhash.
+fn unsigned_transaction(payer: &Pubkey, ixs: &[Instruction]) -> Result<String> {
+    let message = v0::Message::try_compile(payer, ixs, &[], Hash::default())?;
+    let signatures = vec![Signature::default(); message.header.num_required_signatures as usize];
+    let tx = VersionedTransaction { signatures, message: VersionedMessage::V0(message) };
+    Ok(B64.encode(bincode::serialize(&tx)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::StubRpc;
+
+    /// A market account with `oracle` where Drift stores it.
+    fn market_account(oracle: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; 256];
+        data[MARKET_ORACLE_OFFSET..MARKET_ORACLE_OFFSET + 32].copy_from_slice(oracle.as_ref());
+        data
+    }
+
+    /// A `User` account holding `positions` as `(slot, market_index, position)`.
+    fn user_account(positions: &[(usize, u16, PerpPosition)]) -> Vec<u8> {
+        let mut data = vec![0u8; USER_PERP_POSITIONS_OFFSET + PERP_POSITION_SLOTS * PERP_POSITION_LEN + 64];
+        for (slot, market_index, position) in positions {
+            let at = USER_PERP_POSITIONS_OFFSET + slot * PERP_POSITION_LEN;
+            data[at + 8..at + 16].copy_from_slice(&position.base_asset_amount.to_le_bytes());
+            data[at + 16..at + 24].copy_from_slice(&position.quote_asset_amount.to_le_bytes());
+            data[at + 92..at + 94].copy_from_slice(&market_index.to_le_bytes());
+            data[at + 94] = position.open_orders;
+        }
+        data
+    }
+
+    #[test]
+    fn perp_position_is_read_from_its_market_slot() {
+        let short = PerpPosition { base_asset_amount: -2_000_000_000, quote_asset_amount: 300_000_000, open_orders: 1 };
+        let long = PerpPosition { base_asset_amount: 5, quote_asset_amount: -10, open_orders: 0 };
+        let data = user_account(&[(1, 0, short), (3, 7, long)]);
+        assert_eq!(parse_perp_position(&data, 0).unwrap(), short, "free slot 0 is not mistaken for market 0");
+        assert_eq!(parse_perp_position(&data, 7).unwrap(), long);
+        assert_eq!(parse_perp_position(&data, 2).unwrap(), PerpPosition::default());
+        assert!(parse_perp_position(&data[..500], 0).is_err());
+    }
+
+    #[test]
+    fn discriminators_match_drift_idl() {
+        assert_eq!(discriminator("place_perp_order"), [69, 161, 93, 202, 120, 126, 76, 185]);
+        assert_eq!(discriminator("cancel_orders"), [238, 225, 95, 158, 227, 103, 8, 194]);
+        assert_eq!(discriminator("initialize_user"), [111, 17, 185, 250, 60, 122, 38, 254]);
+        assert_eq!(discriminator("initialize_user_stats"), [254, 243, 72, 98, 251, 130, 168, 213]);
+    }
+
+    #[test]
+    fn pdas_match_mainnet_accounts() {
+        assert_eq!(state_pda().to_string(), "5zpq7DvB6UdFFvpmBPspGPNfUGoBRRCE2HHg5u3gxcsN");
+        assert_eq!(perp_market_pda(0).to_string(), "8UJgxaiQx5nTrdDgph5FiahMmzduuLTLf5WmsPegYA6W");
+        assert_eq!(spot_market_pda(0).to_string(), "6gMq3mRCKf8aP3ttTyYhuijVZ2LGi14oDsBbkgubfLB3");
+    }
+
+    #[test]
+    fn market_order_params_borsh_layout() {
+        // Short 1 SOL (1e9 base units) on market 1, reduce-only, no lower than $149.25.
+        let expected: [u8; 32] = [
+            0, 1, 1, 0,                   // order_type Market, market_type Perp, direction Short, user_order_id
+            0, 202, 154, 59, 0, 0, 0, 0,  // base_asset_amount 1_000_000_000
+            208, 95, 229, 8, 0, 0, 0, 0,  // price 149_250_000
+            1, 0,                         // market_index
+            1, 0, 0,                      // reduce_only, post_only None, immediate_or_cancel
+            0, 0, 0, 0, 0, 0, 0,          // max_ts .. auction_end_price: all None / Above
+        ];
+        let price = limit_price(&Side::Short, 150.0, 50).unwrap();
+        assert_eq!(market_order_params(1, &Side::Short, 1_000_000_000, price, true), expected);
+        assert_eq!(market_order_params(0, &Side::Long, 5, 1, false)[..5], [0, 1, 0, 0, 5]);
+    }
+
+    #[test]
+    fn limit_price_allows_slippage_against_the_order() {
+        assert_eq!(limit_price(&Side::Long, 150.0, 50).unwrap(), 150_750_000);
+        assert_eq!(limit_price(&Side::Short, 150.0, 50).unwrap(), 149_250_000);
+        assert!(limit_price(&Side::Long, 0.0, 50).is_err());
+        assert!(limit_price(&Side::Long, f64::NAN, 50).is_err());
+    }
+
+    #[tokio::test]
+    async fn place_perp_order_reads_oracles_at_market_offset() {
+        let rpc = Arc::new(StubRpc::default());
+        let (usdc_oracle, sol_oracle) = (Pubkey::new_unique(), Pubkey::new_unique());
+        rpc.seed_account(spot_market_pda(QUOTE_SPOT_MARKET_INDEX), market_account(&usdc_oracle));
+        rpc.seed_account(perp_market_pda(0), market_account(&sol_oracle));
+        let authority = Pubkey::new_unique();
+        rpc.seed_account(user_stats_pda(&authority), vec![0]);
+        rpc.seed_account(user_pda(&authority), vec![0]);
+        let drift = DriftInstructions::new(rpc, Arc::new(PerpMarketRegistry::load(None).unwrap()));
+
+        let tx_b64 = drift.place_perp_order(&authority, 0, &Side::Long, 42, 7, false).await.unwrap();
+        let tx: VersionedTransaction = bincode::deserialize(&B64.decode(tx_b64).unwrap()).unwrap();
+        let keys = tx.message.static_account_keys();
+        let [ix] = tx.message.instructions() else { panic!("expected only the order instruction") };
+        assert_eq!(ix.data[..8], discriminator("place_perp_order"));
+        assert_eq!(ix.data[8..], market_order_params(0, &Side::Long, 42, 7, false)[..]);
+        let accounts: Vec<Pubkey> = ix.accounts.iter().map(|i| keys[*i as usize]).collect();
+        assert_eq!(accounts, vec![
+            state_pda(), user_pda(&authority), authority,
+            usdc_oracle, sol_oracle, spot_market_pda(0), perp_market_pda(0),
+        ]);
+        assert!(tx.message.is_maybe_writable(keys.iter().position(|k| *k == perp_market_pda(0)).unwrap()));
+    }
+
+    #[tokio::test]
+    async fn place_perp_order_creates_missing_user() {
+        let rpc = Arc::new(StubRpc::default());
+        rpc.seed_account(spot_market_pda(QUOTE_SPOT_MARKET_INDEX), market_account(&Pubkey::new_unique()));
+        rpc.seed_account(perp_market_pda(0), market_account(&Pubkey::new_unique()));
+        let drift = DriftInstructions::new(rpc, Arc::new(PerpMarketRegistry::load(None).unwrap()));
+
+        let tx_b64 = drift.place_perp_order(&Pubkey::new_unique(), 0, &Side::Short, 1, 7, true).await.unwrap();
+        let tx: VersionedTransaction = bincode::deserialize(&B64.decode(tx_b64).unwrap()).unwrap();
+        let heads: Vec<&[u8]> = tx.message.instructions().iter().map(|ix| &ix.data[..8]).collect();
+        assert_eq!(heads, vec![
+            &discriminator("initialize_user_stats")[..],
+            &discriminator("initialize_user")[..],
+            &discriminator("place_perp_order")[..],
+        ]);
+    }
+}
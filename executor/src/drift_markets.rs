@@ -0,0 +1,107 @@
+// executor/src/drift_markets.rs
+use crate::jupiter::SOL_MINT;
+use anyhow::{ensure, Context, Result};
+use serde::Deserialize;
+use shared_models::PerpMarkEvent;
+use std::{collections::HashMap, sync::Arc, time::{Duration, Instant}};
+use tokio::sync::Mutex;
+use tracing::info;
+
+/// Drift's base-asset precision for perp markets (1e9 units per token).
+pub const DRIFT_BASE_PRECISION: u64 = 1_000_000_000;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PerpMarket {
+    pub mint: String,
+    pub market_index: u16,
+    pub symbol: String,
+    #[serde(default = "default_base_precision")]
+    pub base_precision: u64,
+}
+
+fn default_base_precision() -> u64 { DRIFT_BASE_PRECISION }
+
+impl PerpMarket {
+    /// Converts a USD notional into the market's base-asset units at `price_usd`.
+    pub fn base_asset_amount(&self, size_usd: f64, price_usd: f64) -> Result<u64> {
+        ensure!(price_usd.is_finite() && price_usd > 0.0, "invalid {} price {}", self.symbol, price_usd);
+        ensure!(size_usd.is_finite() && size_usd >= 0.0, "invalid {} order size ${}", self.symbol, size_usd);
+        Ok((size_usd / price_usd * self.base_precision as f64) as u64)
+    }
+}
+
+/// Maps token mints to the Drift perp market that tracks them.
+pub struct PerpMarketRegistry {
+    markets: HashMap<String, PerpMarket>,
+}
+
+impl PerpMarketRegistry {
+    /// Loads the registry from `DRIFT_PERP_MARKETS_PATH` if set, otherwise falls back to SOL-PERP only.
+    pub fn load(path: Option<&str>) -> Result<Self> {
+        let markets: Vec<PerpMarket> = match path {
+            Some(path) => {
+                let raw = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read Drift perp market file at {}", path))?;
+                serde_json::from_str(&raw).with_context(|| format!("Invalid Drift perp market file at {}", path))?
+            }
+            None => vec![PerpMarket {
+                mint: SOL_MINT.to_string(),
+                market_index: 0,
+                symbol: "SOL-PERP".to_string(),
+                base_precision: DRIFT_BASE_PRECISION,
+            }],
+        };
+        info!("Loaded {} Drift perp markets.", markets.len());
+        Ok(Self { markets: markets.into_iter().map(|m| (m.mint.clone(), m)).collect() })
+    }
+
+    pub fn get(&self, mint: &str) -> Option<&PerpMarket> {
+        self.markets.get(mint)
+    }
+
+    pub fn all(&self) -> impl Iterator<Item = &PerpMarket> {
+        self.markets.values()
+    }
+}
+
+/// Latest Drift oracle price per token, from `events:perp_mark`.
+#[derive(Clone, Default)]
+pub struct PerpMarkBook {
+    inner: Arc<Mutex<HashMap<String, (Instant, f64)>>>,
+}
+
+impl PerpMarkBook {
+    pub async fn update(&self, event: &PerpMarkEvent) {
+        self.inner.lock().await.insert(event.token_address.clone(), (Instant::now(), event.oracle_price_usd));
+    }
+
+    /// The oracle price for `token`, unless it is older than `max_age`.
+    pub async fn oracle_price(&self, token: &str, max_age: Duration) -> Option<f64> {
+        self.inner.lock().await.get(token)
+            .filter(|(at, _)| at.elapsed() <= max_age)
+            .map(|(_, price)| *price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sol_perp() -> PerpMarket {
+        PerpMarket { mint: SOL_MINT.to_string(), market_index: 0, symbol: "SOL-PERP".to_string(), base_precision: DRIFT_BASE_PRECISION }
+    }
+
+    #[test]
+    fn base_asset_amount_converts_usd_at_price() {
+        assert_eq!(sol_perp().base_asset_amount(150.0, 150.0).unwrap(), DRIFT_BASE_PRECISION);
+        assert_eq!(sol_perp().base_asset_amount(75.0, 150.0).unwrap(), DRIFT_BASE_PRECISION / 2);
+    }
+
+    #[test]
+    fn base_asset_amount_rejects_bad_prices() {
+        for price in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(sol_perp().base_asset_amount(100.0, price).is_err(), "price {}", price);
+        }
+        assert!(sol_perp().base_asset_amount(-100.0, 150.0).is_err());
+    }
+}
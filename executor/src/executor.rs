@@ -1,44 +1,103 @@
 // executor/src/executor.rs
-use crate::{config::CONFIG, database::Database, jupiter::JupiterClient, portfolio_monitor, signer_client, strategies};
-use anyhow::{anyhow, Result};
-use shared_models::{MarketEvent, StrategyAction, StrategyAllocation, OrderDetails, EventType, Side};
-use solana_sdk::pubkey::Pubkey;
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use crate::{config::CONFIG, database::Database, drift::DriftInstructions, jupiter::JupiterClient, strategies::{self, GroupResult}};
+use crate::drift_markets::{PerpMarkBook, PerpMarketRegistry};
+use crate::algo::{ExecAlgo, WorkingOrders};
+use crate::balances::BalanceTracker;
+use crate::confirmation::{ConfirmationPolicy, ConfirmationTracker};
+use crate::pretrade::{self, DepthBook, PreTradeLimits, PreTradeRejected, Verdict};
+use crate::retry::{FailureClass, RetryDecision, RetryPolicy, TxError};
+use crate::rpc::{NodeRpc, SolanaRpc, StubRpc};
+use crate::short_venue::{InventorySell, LendingBorrowSell, ShortVenue};
+use crate::signer_client::SignerClient;
+use crate::streams;
+use crate::tips::TipPolicy;
+use crate::venues::{
+    drift_perp::DriftPerpVenue, jupiter_spot::JupiterSpotVenue, mock::MockVenue, spot_short::SpotShortVenue,
+    Confirmation, ExecutionVenue, OrderRouter, SubmitPath, TxSubmitter, VenueOrder, VenueQuote,
+};
+use anyhow::{anyhow, Context, Result};
+use futures_util::StreamExt;
+use shared_models::{MarketEvent, StrategyAction, StrategyAllocation, OrderDetails, OrderLeg, LegPosition, Instrument, LegStatus, EventType, Side};
+use std::{collections::HashMap, sync::Arc, time::Instant};
 use tokio::sync::mpsc::{self, Sender, Receiver};
 use tokio::task::JoinHandle;
 use tracing::{error, info, instrument, warn};
-use redis::AsyncCommands; // P-7: For Redis Streams
-// add to top
-use drift_sdk::{Client as DriftClient, Network as DriftNet, OpenPositionArgs};
-use jito_searcher_client::JitoClient;
+use crate::jito::JitoClient;
 
 pub struct MasterExecutor {
     db: Arc<Database>,
     active_strategies: HashMap<String, (Sender<MarketEvent>, JoinHandle<()>)>, // ID -> (Sender, TaskHandle)
     event_router_senders: HashMap<EventType, Vec<Sender<MarketEvent>>>, // EventType -> List of interested strategy senders
     redis_client: redis::Client, // P-7: Client for Redis Streams
-    jupiter_client: Arc<JupiterClient>,
     sol_usd_price: Arc<tokio::sync::Mutex<f64>>, // P-2: Store live SOL/USD price
     portfolio_paused: Arc<tokio::sync::Mutex<bool>>, // P-6: Flag to pause trading
-    /* existing fields */ 
-    jito_client: Arc<JitoClient>,               // NEW
-    drift:       Arc<DriftClient>,              // NEW
-}
+    router: Arc<OrderRouter>,
+    tracker: Arc<ConfirmationTracker>,
+    retry_policy: Arc<RetryPolicy>,
+    depth_book: DepthBook,
+    perp_marks: PerpMarkBook,
+    pretrade_limits: Arc<PreTradeLimits>,
+    working_orders: WorkingOrders,
+    tips: Arc<TipPolicy>,
+    balances: Arc<BalanceTracker>,
+    signer: Arc<SignerClient>,
 }
 
 impl MasterExecutor {
     pub async fn new(db: Arc<Database>) -> Self {
+        let sol_usd_price = Arc::new(tokio::sync::Mutex::new(0.0)); // P-2: Zero until the first events:sol_price entry; nothing trades before it
+        let jito_client = Arc::new(JitoClient::new(CONFIG.jito_rpc_url.clone()));
+        // Mock venues never touch the network, so their signatures are checked against a stub.
+        let rpc: Arc<dyn SolanaRpc> = if CONFIG.mock_venues {
+            Arc::new(StubRpc::default())
+        } else {
+            Arc::new(NodeRpc::new(CONFIG.solana_rpc_url.clone()))
+        };
+        let jupiter_client = Arc::new(JupiterClient::new(rpc.clone(), sol_usd_price.clone()));
+        let tracker = Arc::new(ConfirmationTracker::new(rpc.clone(), sol_usd_price.clone(), ConfirmationPolicy::from_config()));
+        let perp_markets = Arc::new(PerpMarketRegistry::load(CONFIG.drift_perp_markets_path.as_deref()).unwrap());
+        let signer = Arc::new(SignerClient::from_config());
+        let balances = Arc::new(BalanceTracker::new(rpc.clone(), signer.clone(), sol_usd_price.clone()));
+        let perp_marks = PerpMarkBook::default();
+        let drift = DriftInstructions::new(rpc.clone(), perp_markets.clone());
+        let submitter = Arc::new(TxSubmitter::new(jito_client, signer.clone(), rpc));
+        let tips = Arc::new(TipPolicy::from_config(sol_usd_price.clone()).unwrap());
+
+        // Spot short venues, in preference order
+        let mut short_venues: Vec<Box<dyn ShortVenue>> = vec![Box::new(InventorySell::new(db.clone(), balances.clone(), jupiter_client.clone()))];
+        // Borrow-then-sell and buy-then-repay are separate transactions: only atomic as a bundle.
+        match &CONFIG.lending_adapter_url {
+            Some(url) if CONFIG.jito_bundles => short_venues.push(Box::new(LendingBorrowSell::new(url.clone(), jupiter_client.clone()))),
+            Some(_) => warn!("LENDING_ADAPTER_URL is set but JITO_BUNDLES is off; lending shorts are disabled."),
+            None => {}
+        }
+
+        let mock: Option<Arc<dyn ExecutionVenue>> = CONFIG.mock_venues.then(|| Arc::new(MockVenue::new(1.0)) as Arc<dyn ExecutionVenue>);
+        let router = OrderRouter::new(
+            Arc::new(JupiterSpotVenue::new(jupiter_client.clone(), submitter.clone())),
+            Arc::new(DriftPerpVenue::new(drift, perp_marks.clone(), perp_markets.clone(), submitter.clone())),
+            Arc::new(SpotShortVenue::new(Arc::new(short_venues), jupiter_client.clone(), submitter)),
+            mock,
+            perp_markets,
+        );
+
         Self {
             db,
             active_strategies: HashMap::new(),
             event_router_senders: HashMap::new(),
             redis_client: redis::Client::open(CONFIG.redis_url.clone()).unwrap(),
-            jupiter_client: Arc::new(JupiterClient::new()),
-            sol_usd_price: Arc::new(tokio::sync::Mutex::new(100.0)), // P-2: Default to $100
+            sol_usd_price,
             portfolio_paused: Arc::new(tokio::sync::Mutex::new(false)), // P-6: Not paused by default
-            // inside new()
-            jito_client: Arc::new(JitoClient::new(CONFIG.jito_rpc_url.clone()).await.unwrap()),
-            drift: Arc::new(DriftClient::connect(DriftNet::Mainnet).await.unwrap()),
+            router: Arc::new(router),
+            tracker,
+            retry_policy: Arc::new(RetryPolicy::from_config()),
+            depth_book: DepthBook::default(),
+            perp_marks,
+            pretrade_limits: Arc::new(PreTradeLimits::from_config()),
+            working_orders: WorkingOrders::default(),
+            tips,
+            balances,
+            signer,
         }
     }
 
@@ -49,19 +108,19 @@ impl MasterExecutor {
 
     pub async fn run(&mut self) -> Result<()> {
         info!("Starting Master Executor run loop.");
-        
-        let mut allocation_listener = self.redis_client.get_async_connection().await?.into_pubsub();
-        allocation_listener.subscribe("allocations_channel").await?;
 
-        let mut price_event_listener = self.redis_client.get_async_connection().await?.into_pubsub();
-        price_event_listener.subscribe("events:price").await?;
-
-        let mut social_event_listener = self.redis_client.get_async_connection().await?.into_pubsub();
-        social_event_listener.subscribe("events:social").await?;
+        // The meta allocator publishes allocations; the data consumers XADD market events to streams.
+        let mut allocation_listener = self.redis_client.get_async_pubsub().await?;
+        allocation_listener.subscribe("allocations_channel").await?;
+        let mut allocation_messages = allocation_listener.into_on_message();
+        let mut stream_events = streams::tail(self.redis_client.clone(), &[
+            "events:price", "events:social", "events:funding", "events:perp_mark",
+            "events:depth", "events:sol_price", "events:jito_tip_floor",
+        ]);
 
         loop {
             tokio::select! {
-                Some(msg) = allocation_listener.get_message() => {
+                Some(msg) = allocation_messages.next() => {
                     if let Ok(payload) = msg.get_payload::<String>() {
                         if let Ok(allocations) = serde_json::from_str::<Vec<StrategyAllocation>>(&payload) {
                             self.reconcile_strategies(allocations).await;
@@ -72,26 +131,42 @@ impl MasterExecutor {
                         error!("Failed to get payload from allocation_listener message.");
                     }
                 }
-                Some(msg) = price_event_listener.get_message() => {
-                    if let Ok(payload) = msg.get_payload::<String>() {
-                        if let Ok(event) = serde_json::from_str::<shared_models::PriceTick>(&payload) {
-                            self.dispatch_event(shared_models::MarketEvent::Price(event)).await;
-                        } else {
-                            error!("Failed to deserialize PriceTick: {}", payload);
-                        }
-                    }
+                Some((stream, payload)) = stream_events.recv() => {
+                    self.on_stream_event(&stream, &payload).await;
                 }
-                Some(msg) = social_event_listener.get_message() => {
-                    if let Ok(payload) = msg.get_payload::<String>() {
-                        if let Ok(event) = serde_json::from_str::<shared_models::SocialMention>(&payload) {
-                            self.dispatch_event(shared_models::MarketEvent::Social(event)).await;
-                        } else {
-                            error!("Failed to deserialize SocialMention: {}", payload);
-                        }
-                    }
+            }
+        }
+    }
+
+    /// Handles one stream entry. Producers tag each event with its `type`, so it parses straight
+    /// into a `MarketEvent` whichever stream it came from.
+    async fn on_stream_event(&mut self, stream: &str, payload: &str) {
+        let event = match serde_json::from_str::<MarketEvent>(payload) {
+            Ok(event) => event,
+            Err(e) => {
+                error!(stream, %e, payload, "Failed to deserialize stream event");
+                return;
+            }
+        };
+        match &event {
+            MarketEvent::PerpMark(mark) => self.perp_marks.update(mark).await,
+            MarketEvent::Depth(depth) => self.depth_book.update(depth.clone()).await,
+            // These two feed the executor's own pricing only; no strategy subscribes to them.
+            MarketEvent::JitoTipFloor(floor) => {
+                self.tips.update_floor(floor.clone()).await;
+                return;
+            }
+            MarketEvent::SolPrice(price) => {
+                if price.price_usd.is_finite() && price.price_usd > 0.0 {
+                    *self.sol_usd_price.lock().await = price.price_usd;
+                } else {
+                    warn!(price = price.price_usd, "ignoring invalid SOL/USD price");
                 }
+                return;
             }
+            _ => {}
         }
+        self.dispatch_event(event).await;
     }
 
     async fn reconcile_strategies(&mut self, allocations: Vec<StrategyAllocation>) {
@@ -104,6 +179,10 @@ impl MasterExecutor {
                 handle.abort();
                 info!(strategy = id, "Stopped strategy due to deallocation.");
             }
+            let cancelled = self.working_orders.cancel(id, None, "strategy deallocated");
+            if cancelled > 0 {
+                info!(strategy = id, cancelled, "Cancelled working parent orders of deallocated strategy.");
+            }
             // Remove from event router senders as well
             for (_, senders) in self.event_router_senders.iter_mut() {
                 senders.retain(|s| !s.is_closed()); // Remove closed channels
@@ -122,8 +201,7 @@ impl MasterExecutor {
 
                     let (tx, rx) = mpsc::channel(100); // Bounded channel for backpressure
                     let strategy_id_clone = id.clone();
-                    let db_clone = self.db.clone();
-                    let jupiter_client_clone = self.jupiter_client.clone();
+                    let ctx = self.trade_ctx();
 
                     // Register subscriptions
                     for sub_type in strategy_instance.subscriptions() {
@@ -131,9 +209,7 @@ impl MasterExecutor {
                     }
 
                     let handle = tokio::spawn(async move {
-                        strategy_task(strategy_instance, rx, db_clone, jupiter_client_clone, 
-                                     self.drift.clone(), self.jito_client.clone(), 
-                                     self.sol_usd_price.clone(), strategy_id_clone).await;
+                        strategy_task(strategy_instance, rx, ctx, strategy_id_clone).await;
                     });
                     self.active_strategies.insert(id, (tx, handle));
                 } else {
@@ -158,6 +234,23 @@ impl MasterExecutor {
         }
     }
 
+    fn trade_ctx(&self) -> TradeCtx {
+        TradeCtx {
+            db: self.db.clone(),
+            router: self.router.clone(),
+            tracker: self.tracker.clone(),
+            retry: self.retry_policy.clone(),
+            depth: self.depth_book.clone(),
+            pretrade: self.pretrade_limits.clone(),
+            working: self.working_orders.clone(),
+            paused: self.portfolio_paused.clone(),
+            tips: self.tips.clone(),
+            balances: self.balances.clone(),
+            signer: self.signer.clone(),
+            sol_usd_price: self.sol_usd_price.clone(),
+        }
+    }
+
     fn build_strategy(&self, id: &str) -> Option<Box<dyn strategies::Strategy>> {
         for constructor in inventory::iter::<strategies::StrategyConstructor> {
             if constructor.0 == id {
@@ -168,31 +261,106 @@ impl MasterExecutor {
     }
 }
 
-#[instrument(skip(strategy_instance, rx, db, jupiter_client))]
+/// Everything a strategy task needs to turn an action into on-chain orders.
+#[derive(Clone)]
+struct TradeCtx {
+    db: Arc<Database>,
+    router: Arc<OrderRouter>,
+    tracker: Arc<ConfirmationTracker>,
+    retry: Arc<RetryPolicy>,
+    depth: DepthBook,
+    pretrade: Arc<PreTradeLimits>,
+    working: WorkingOrders,
+    /// The portfolio kill switch; stops sliced orders between children.
+    paused: Arc<tokio::sync::Mutex<bool>>,
+    tips: Arc<TipPolicy>,
+    balances: Arc<BalanceTracker>,
+    /// While its circuit is open, live actions are skipped rather than queued behind it.
+    signer: Arc<SignerClient>,
+    /// Zero until the first SOL/USD price arrives; every order is sized and priced through it.
+    sol_usd_price: Arc<tokio::sync::Mutex<f64>>,
+}
+
+/// Where a leg comes from, recorded on its trade row.
+#[derive(Clone, Copy)]
+enum LegOrigin<'a> {
+    Single,
+    /// (group id, leg index) of a multi-leg group.
+    Group(&'a str, usize),
+    /// Child of a sliced parent order.
+    Child(i64),
+}
+
+/// What a leg left behind once filled; enough to reverse it.
+struct LegFill {
+    trade_id: i64,
+    /// Raw token units bought or sold for spot legs, base-asset units for perp legs.
+    filled_amount: u64,
+    size_usd: f64,
+    /// Set for spot shorts: the short venue that has to cover them.
+    short_venue: Option<String>,
+}
+
+impl LegFill {
+    fn position(&self) -> LegPosition {
+        LegPosition { amount_raw: self.filled_amount, short_venue: self.short_venue.clone() }
+    }
+}
+
+/// A group that did not fill: the failure, and per leg what is still open after unwinding.
+#[derive(Debug)]
+struct GroupAborted {
+    error: anyhow::Error,
+    open: Vec<Option<LegPosition>>,
+}
+
+#[instrument(skip(strategy_instance, rx, ctx))]
 async fn strategy_task(
     mut strategy_instance: Box<dyn strategies::Strategy>,
     mut rx: Receiver<MarketEvent>,
-    db: Arc<Database>,
-    jupiter_client: Arc<JupiterClient>,
-    drift: Arc<DriftClient>,
-    jito_client: Arc<JitoClient>,
-    sol_price: Arc<tokio::sync::Mutex<f64>>,
+    ctx: TradeCtx,
     strategy_id: String,
 ) {
     info!(strategy = strategy_id.as_str(), "Strategy task started.");
+    if !CONFIG.paper_trading_mode {
+        if let Err(e) = ctx.balances.sync(&CONFIG.signer_key(&strategy_id), &ctx.db).await {
+            warn!(strategy=%strategy_id, %e, "initial wallet balance sync failed");
+        }
+    }
     while let Some(event) = rx.recv().await {
         // ─────────────────── strategy_task ───────────────────
+        let signal_at = Instant::now();
         match strategy_instance.on_event(&event).await {
+            Ok(StrategyAction::Execute(_) | StrategyAction::ExecuteGroup(_)) if signer_unavailable(&ctx) => {
+                warn!(strategy=%strategy_id, "signer circuit open, skipping action");
+            }
+            Ok(StrategyAction::Execute(_) | StrategyAction::ExecuteGroup(_)) if *ctx.sol_usd_price.lock().await <= 0.0 => {
+                warn!(strategy=%strategy_id, "no SOL/USD price received yet, skipping action");
+            }
             Ok(StrategyAction::Execute(details)) => {
-                if let Err(e) = execute_trade(
-                        db.clone(),
-                        jupiter_client.clone(),
-                        drift.clone(),
-                        jito_client.clone(),
-                        sol_price.clone(),
-                        details,
-                        &strategy_id,
-                ).await { error!(strategy=%strategy_id, %e, "trade failed"); }
+                if let Err(e) = execute_trade(&ctx, details, &strategy_id, signal_at).await {
+                    error!(strategy=%strategy_id, %e, "trade failed");
+                }
+            }
+            Ok(StrategyAction::ExecuteGroup(legs)) => {
+                match execute_group(&ctx, &legs, &strategy_id, signal_at).await {
+                    Ok(fills) => {
+                        let positions: Vec<LegPosition> = fills.iter().map(LegFill::position).collect();
+                        strategy_instance.on_group_result(&legs, GroupResult::Filled(&positions)).await;
+                    }
+                    Err(aborted) if aborted.open.iter().all(Option::is_none) => {
+                        error!(strategy=%strategy_id, e = %aborted.error, "order group failed");
+                        strategy_instance.on_group_result(&legs, GroupResult::Unwound).await;
+                    }
+                    Err(aborted) => {
+                        error!(strategy=%strategy_id, e = %aborted.error, open = ?aborted.open, "order group failed with legs left open");
+                        strategy_instance.on_group_result(&legs, GroupResult::Stranded(&aborted.open)).await;
+                    }
+                }
+            }
+            Ok(StrategyAction::Withdraw(token)) => {
+                let cancelled = ctx.working.cancel(&strategy_id, Some(&token), "signal withdrawn");
+                info!(strategy=%strategy_id, %token, cancelled, "signal withdrawn");
             }
             Ok(StrategyAction::Hold) => {}
             Err(e) => error!(strategy=%strategy_id, %e, "strategy error"),
@@ -201,64 +369,484 @@ async fn strategy_task(
     info!(strategy = strategy_id.as_str(), "Strategy task finished.");
 }
 
-#[instrument(skip(db, jupiter_client))]
+/// Paper trading never signs, so only live mode waits on the signer.
+fn signer_unavailable(ctx: &TradeCtx) -> bool {
+    !CONFIG.paper_trading_mode && ctx.signer.is_open()
+}
+
 // ─────────────────── execute_trade ───────────────────
+/// Single-order path: longs buy spot; shorts use the token's perp when Drift lists one,
+/// otherwise a spot short venue. Large spot orders are sliced into children in the background.
 #[instrument(skip_all)]
-async fn execute_trade(
-    db: Arc<Database>,
-    jupiter: Arc<JupiterClient>,
-    drift: Arc<DriftClient>,
-    jito:  Arc<JitoClient>,
-    sol_price: Arc<tokio::sync::Mutex<f64>>,
-    details: OrderDetails,
+async fn execute_trade(ctx: &TradeCtx, details: OrderDetails, strategy_id: &str, signal_at: Instant) -> Result<()> {
+    let has_perp = ctx.router.has_perp(&details.token_address);
+    let instrument = if details.side == Side::Short && has_perp { Instrument::Perp } else { Instrument::Spot };
+    let target_usd = details.suggested_size_usd.min(CONFIG.global_max_position_usd);
+    let leg = OrderLeg { instrument, details, closes: None };
+
+    match ExecAlgo::for_size(target_usd).filter(|_| instrument == Instrument::Spot) {
+        Some(algo) => {
+            let parent_id = ctx.db.create_parent_order(&leg.details, strategy_id, algo.name(), target_usd)?;
+            let cancel = ctx.working.register(parent_id, strategy_id, &leg.details.token_address);
+            info!(parent_id, algo = algo.name(), target_usd, "slicing order");
+            let (ctx, strategy_id) = (ctx.clone(), strategy_id.to_string());
+            tokio::spawn(async move {
+                if let Err(e) = run_parent_order(&ctx, leg, &strategy_id, algo, parent_id, target_usd, cancel).await {
+                    error!(parent_id, %e, "parent order failed");
+                }
+                ctx.working.finish(parent_id);
+            });
+        }
+        None => {
+            execute_leg(ctx, &leg, strategy_id, LegOrigin::Single, signal_at).await?;
+        }
+    }
+    Ok(())
+}
+
+// ─────────────────── run_parent_order ───────────────────
+/// Sends children until the target is filled, the signal is withdrawn or the kill switch fires.
+/// Each child is a normal leg with its own quote, pre-trade check and retries. Whatever ends the
+/// parent, including an error, is recorded on its row.
+#[instrument(skip_all, fields(parent_id))]
+async fn run_parent_order(
+    ctx: &TradeCtx,
+    leg: OrderLeg,
     strategy_id: &str,
+    algo: ExecAlgo,
+    parent_id: i64,
+    target_usd: f64,
+    cancel: tokio::sync::watch::Receiver<Option<String>>,
 ) -> Result<()> {
+    let (status, reason) = match send_children(ctx, leg, strategy_id, algo, parent_id, target_usd, cancel).await {
+        Ok(done) => done,
+        Err(e) => ("FAILED", Some(format!("{:#}", e))),
+    };
+    ctx.db.finish_parent_order(parent_id, status, reason.as_deref())?;
+    match reason {
+        Some(reason) if status == "FAILED" => Err(anyhow!(reason)),
+        _ => Ok(()),
+    }
+}
+
+/// The child loop of `run_parent_order`; returns the parent's final status and reason.
+async fn send_children(
+    ctx: &TradeCtx,
+    leg: OrderLeg,
+    strategy_id: &str,
+    algo: ExecAlgo,
+    parent_id: i64,
+    target_usd: f64,
+    mut cancel: tokio::sync::watch::Receiver<Option<String>>,
+) -> Result<(&'static str, Option<String>)> {
+    /// Consecutive child failures tolerated before the parent gives up.
+    const MAX_CHILD_FAILURES: u32 = 2;
+    let mut remaining = target_usd;
+    let mut failures = 0u32;
+
+    while remaining >= ctx.pretrade.min_size_usd {
+        if let Some(reason) = cancel.borrow().clone() {
+            info!(parent_id, %reason, remaining, "parent order cancelled");
+            return Ok(("CANCELED", Some(reason)));
+        }
+        if *ctx.paused.lock().await {
+            warn!(parent_id, remaining, "kill switch active, remaining children cancelled");
+            return Ok(("CANCELED", Some("kill switch".to_string())));
+        }
+
+        let impact = match algo {
+            ExecAlgo::Liquidity { .. } => {
+                let probe = VenueOrder::from_leg(&leg, remaining, &CONFIG.signer_key(strategy_id));
+                let quote = ctx.router.route(&probe).quote(&probe).await.context("probe quote failed")?;
+                quote.swap_quote.map(|q| q.price_impact() * 100.0)
+            }
+            ExecAlgo::Twap { .. } => None,
+        };
+        let child_usd = algo.child_size(target_usd, remaining, impact).max(ctx.pretrade.min_size_usd).min(remaining);
+        let mut child = leg.clone();
+        child.details.suggested_size_usd = child_usd;
+
+        // Each child is a fresh decision, so its signal age starts now.
+        match execute_leg(ctx, &child, strategy_id, LegOrigin::Child(parent_id), Instant::now()).await {
+            Ok(fill) => {
+                failures = 0;
+                remaining -= fill.size_usd;
+                ctx.db.add_parent_fill(parent_id, fill.size_usd)?;
+                info!(parent_id, child_usd = fill.size_usd, remaining, "child filled");
+            }
+            Err(e) => {
+                failures += 1;
+                warn!(parent_id, failures, %e, "child order failed");
+                if failures >= MAX_CHILD_FAILURES {
+                    return Err(e);
+                }
+            }
+        }
+
+        if remaining >= ctx.pretrade.min_size_usd {
+            tokio::select! {
+                _ = tokio::time::sleep(algo.interval()) => {}
+                _ = cancel.changed() => {}
+            }
+        }
+    }
+    info!(parent_id, target_usd, "parent order completed");
+    Ok(("COMPLETED", None))
+}
+
+// ─────────────────── execute_group ───────────────────
+/// Executes legs in order. On the first failure every filled leg is reversed, so the group
+/// either ends fully on or fully off, unless an unwind fails too: that leg stays open and is
+/// reported in `GroupAborted::open`. Each leg's status is kept on its trade row.
+#[instrument(skip_all)]
+async fn execute_group(ctx: &TradeCtx, legs: &[OrderLeg], strategy_id: &str, signal_at: Instant) -> Result<Vec<LegFill>, GroupAborted> {
+    let group_id = format!("{}-{}", strategy_id, chrono::Utc::now().timestamp_millis());
+    let mut statuses = vec![LegStatus::Pending; legs.len()];
+    let mut fills: Vec<LegFill> = Vec::with_capacity(legs.len());
+
+    for (i, leg) in legs.iter().enumerate() {
+        match execute_leg(ctx, leg, strategy_id, LegOrigin::Group(&group_id, i), signal_at).await {
+            Ok(fill) => {
+                statuses[i] = LegStatus::Filled;
+                set_leg_status(ctx, &group_id, i, LegStatus::Filled);
+                fills.push(fill);
+            }
+            Err(e) => {
+                statuses[i] = LegStatus::Failed;
+                set_leg_status(ctx, &group_id, i, LegStatus::Failed);
+                error!(group = %group_id, leg = i, %e, "leg failed, unwinding filled legs");
+                let mut open = vec![None; legs.len()];
+                for (j, fill) in fills.iter().enumerate().rev() {
+                    statuses[j] = match unwind_leg(ctx, &legs[j], fill, strategy_id).await {
+                        Ok(()) => LegStatus::Unwound,
+                        Err(ue) => {
+                            error!(group = %group_id, leg = j, %ue, "UNWIND FAILED – position left open");
+                            if let Err(e) = ctx.db.set_trade_status(fill.trade_id, "UNWIND_FAILED") {
+                                error!(trade_id = fill.trade_id, %e, "failed to record unwind failure");
+                            }
+                            open[j] = Some(fill.position());
+                            LegStatus::UnwindFailed
+                        }
+                    };
+                    set_leg_status(ctx, &group_id, j, statuses[j]);
+                }
+                info!(group = %group_id, ?statuses, "order group aborted");
+                return Err(GroupAborted { error: e, open });
+            }
+        }
+    }
+    info!(group = %group_id, ?statuses, "order group filled");
+    Ok(fills)
+}
+
+/// Best effort: the group's outcome still reaches the strategy when the row cannot be updated.
+fn set_leg_status(ctx: &TradeCtx, group_id: &str, leg_index: usize, status: LegStatus) {
+    if let Err(e) = ctx.db.set_leg_status(group_id, leg_index, status) {
+        error!(group = %group_id, leg = leg_index, status = status.as_str(), %e, "failed to record leg status");
+    }
+}
+
+#[instrument(skip_all, fields(token = %leg.details.token_address, instrument = ?leg.instrument))]
+async fn execute_leg(
+    ctx: &TradeCtx,
+    leg: &OrderLeg,
+    strategy_id: &str,
+    origin: LegOrigin<'_>,
+    signal_at: Instant,
+) -> Result<LegFill> {
+    let details = &leg.details;
     let is_live = !CONFIG.paper_trading_mode;
     let is_short = matches!(details.side, Side::Short);
 
     // ----------- sizing ----------
     let size_usd = details.suggested_size_usd.min(CONFIG.global_max_position_usd);
+    let mut order = VenueOrder::from_leg(leg, size_usd, &CONFIG.signer_key(strategy_id));
+    order.all_or_none = matches!(origin, LegOrigin::Group(..));
+    let venue = ctx.router.route(&order);
+
+    // log attempt; the entry price is filled in once quoted
+    let trade_id = ctx.db.log_trade_attempt(details, strategy_id, 0.0)?;
+    ctx.db.set_instrument(trade_id, &format!("{:?}", leg.instrument))?;
+    match origin {
+        LegOrigin::Single => {}
+        LegOrigin::Group(group_id, leg_index) => ctx.db.set_trade_leg(trade_id, group_id, leg_index)?,
+        LegOrigin::Child(parent_id) => ctx.db.set_trade_parent(trade_id, parent_id)?,
+    }
 
-    // log attempt
-    let quote = jupiter.get_quote(1.0, &details.token_address).await?;   // 1 USD probe
-    let trade_id = db.log_trade_attempt(&details, strategy_id, quote.price_per_token)?;
-
-    if !is_live {
-        simulate_fill(&db, trade_id, size_usd, is_short)?;
-        return Ok(())
-    }
-
-    // ------------- live -------------
-    if  is_short {
-        // Perp short on Drift
-        let margin_acct = drift.get_or_create_user().await?;
-        let args = OpenPositionArgs {
-            market_index: 0,   // SOL-PERP
-            direction: drift_sdk::Direction::Short,
-            base_asset_amount: (size_usd / *sol_price.lock().await * 1e9) as u64,
-            limit_price: None,
-            reduce_only: false,
+    let mut attempt = 0u32;
+    let mut retries: HashMap<FailureClass, u32> = HashMap::new();
+    loop {
+        attempt += 1;
+        if signal_at.elapsed() > ctx.retry.max_signal_age {
+            let reason = format!("signal older than {:?}, abandoned", ctx.retry.max_signal_age);
+            ctx.db.mark_failed(trade_id, &reason)?;
+            return Err(anyhow!(reason));
+        }
+
+        let result: Result<LegFill> = async {
+            // A cover must buy back everything owed, so only opening buys are capped.
+            if is_live && leg.instrument == Instrument::Spot && !is_short && !order.reduce_only {
+                cap_to_buying_power(ctx, &mut order, matches!(origin, LegOrigin::Group(..))).await?;
+            }
+            // Venues may shrink the order, e.g. a spot short selling to flat, unless it is all-or-none.
+            let mut quote = venue.quote(&order).await?;
+            if leg.instrument == Instrument::Spot {
+                quote = pretrade_check(ctx, venue.as_ref(), &mut order, quote, trade_id, matches!(origin, LegOrigin::Group(..))).await?;
+            }
+            ctx.db.set_entry_price(trade_id, quote.price_usd)?;
+            if let Some(swap_quote) = &quote.swap_quote {
+                ctx.db.set_quote(trade_id, swap_quote)?;
+            }
+            let short_venue = if leg.instrument == Instrument::Spot && is_short { quote.route.clone() } else { None };
+            // Covers carry their venue too, so tokens bought back for a borrow never count as inventory.
+            if let Some(name) = short_venue.as_ref().or(order.short_venue.as_ref()) {
+                ctx.db.set_short_venue(trade_id, name)?;
+            }
+
+            if !is_live {
+                simulate_fill(&ctx.db, trade_id, quote.size_usd, is_short)?;
+                return Ok(LegFill { trade_id, filled_amount: quote.amount_raw, size_usd: quote.size_usd, short_venue });
+            }
+
+            // ------------- live -------------
+            order.tip_lamports = ctx.tips.tip_for(quote.size_usd, details.confidence).await?;
+            let (sig, filled_amount, size_usd) = submit_and_confirm(ctx, venue.as_ref(), &order, &quote, trade_id, attempt).await?;
+            info!(venue = venue.name(), %sig, filled_amount, "order confirmed");
+            ctx.db.open_trade(trade_id, &sig)?;
+            if let Err(e) = ctx.balances.sync(&order.signer_key, &ctx.db).await {
+                warn!(%e, "wallet balance sync after fill failed");
+            }
+            Ok(LegFill { trade_id, filled_amount, size_usd, short_venue })
+        }.await;
+
+        let e = match result {
+            Ok(fill) => return Ok(fill),
+            Err(e) => e,
         };
-        let sig = drift.open_position(&margin_acct, &args).await?;
-        db.open_trade(trade_id, &sig.to_string())?;
-    } else {
-        // Spot buy via Jupiter
-        let user_pk = Pubkey::from_str(&signer_client::get_pubkey().await?)?;
-        let swap_b64 = jupiter.get_swap_transaction(&user_pk, &details.token_address, size_usd).await?;
-        let signed_b64 = signer_client::sign_transaction(&swap_b64).await?;
-        let mut tx     = jupiter::deserialize_transaction(&signed_b64)?;
-
-        // ------- Jito tip injection --------
-        let bh = jito.get_recent_blockhash().await?;
-        tx.message.set_recent_blockhash(bh);
-        jito.attach_tip(&mut tx, CONFIG.jito_tip_lamports).await?;
-        jito.send_transaction(&tx).await?;
-
-        db.open_trade(trade_id, &tx.signatures[0].to_string())?;
+        let class = FailureClass::classify(&e);
+        let retried = retries.entry(class).or_default();
+        let decision = ctx.retry.decide(class, *retried, order.slippage_bps);
+        *retried += 1;
+        ctx.db.log_execution_attempt(trade_id, attempt, class.as_str(), &e.to_string(), order.slippage_bps)?;
+        warn!(trade_id, attempt, class = class.as_str(), ?decision, %e, "execution attempt failed");
+
+        match decision {
+            RetryDecision::Rebuild => {}
+            RetryDecision::Requote { slippage_bps } => order.slippage_bps = slippage_bps,
+            RetryDecision::Retry => tokio::time::sleep(ctx.retry.backoff).await,
+            RetryDecision::GiveUp if class == FailureClass::PreTradeRejected => {
+                ctx.db.mark_rejected(trade_id, &e.to_string())?;
+                return Err(e);
+            }
+            RetryDecision::GiveUp => {
+                ctx.db.mark_failed(trade_id, &e.to_string())?;
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// Shrinks a spot buy to the spendable SOL in the order's wallet; rejects it if what is left is
+/// below the pre-trade minimum, or if it is a group leg, which must match its hedge. Unknown
+/// balances leave the order alone.
+async fn cap_to_buying_power(ctx: &TradeCtx, order: &mut VenueOrder, in_group: bool) -> Result<()> {
+    let Some(buying_power) = ctx.balances.buying_power_usd(&order.signer_key).await else { return Ok(()) };
+    if order.size_usd <= buying_power {
+        return Ok(());
+    }
+    if in_group {
+        return Err(PreTradeRejected(format!("group leg of ${:.2} exceeds buying power ${:.2}", order.size_usd, buying_power)).into());
+    }
+    if buying_power < ctx.pretrade.min_size_usd {
+        return Err(PreTradeRejected(format!("buying power ${:.2} below minimum order size", buying_power)).into());
     }
+    warn!(requested = order.size_usd, buying_power, "order capped to wallet buying power");
+    order.size_usd = buying_power;
     Ok(())
 }
 
+/// Runs the pre-trade liquidity check on a spot quote. A downsize re-quotes the smaller order
+/// once and checks again. Group legs are never downsized, since that would break the hedge.
+async fn pretrade_check(
+    ctx: &TradeCtx,
+    venue: &dyn ExecutionVenue,
+    order: &mut VenueOrder,
+    quote: VenueQuote,
+    trade_id: i64,
+    in_group: bool,
+) -> Result<VenueQuote> {
+    let depth = ctx.depth.latest(&order.token_address, ctx.pretrade.max_depth_age).await;
+    let check = pretrade::evaluate(&ctx.pretrade, &quote, depth.as_ref(), &order.side);
+    ctx.db.log_pretrade(trade_id, &check)?;
+    info!(trade_id, verdict = check.verdict.as_str(), ?check, "pre-trade check");
+
+    let size_usd = match check.verdict {
+        Verdict::Accept => return Ok(quote),
+        Verdict::Reject(reason) => return Err(PreTradeRejected(reason).into()),
+        Verdict::Downsize(_) if in_group => {
+            return Err(PreTradeRejected(format!("group leg would need downsizing from ${:.2}", check.size_usd)).into());
+        }
+        Verdict::Downsize(size_usd) => size_usd,
+    };
+
+    order.size_usd = size_usd;
+    let quote = venue.quote(order).await?;
+    let recheck = pretrade::evaluate(&ctx.pretrade, &quote, depth.as_ref(), &order.side);
+    if recheck.verdict != Verdict::Accept {
+        ctx.db.log_pretrade(trade_id, &recheck)?;
+    }
+    match recheck.verdict {
+        Verdict::Accept => Ok(quote),
+        Verdict::Reject(reason) => Err(PreTradeRejected(reason).into()),
+        Verdict::Downsize(_) => Err(PreTradeRejected(format!("still fails after downsizing to ${:.2}", size_usd)).into()),
+    }
+}
+
+/// Builds, submits and waits for confirmation. On success the trade row carries the real fill;
+/// returns (signature, raw amount, USD size). Terminal transaction outcomes come back as `TxError`.
+async fn submit_and_confirm(
+    ctx: &TradeCtx,
+    venue: &dyn ExecutionVenue,
+    order: &VenueOrder,
+    quote: &VenueQuote,
+    trade_id: i64,
+    attempt: u32,
+) -> Result<(String, u64, f64)> {
+    let built = venue.build(order, quote).await?;
+    if let Some(sim) = venue.simulate(&built, quote).await? {
+        ctx.db.set_simulated_units(trade_id, sim.units_consumed)?;
+    }
+    let before = venue.position(order).await?;
+    let submitted = venue.submit(built).await?;
+    let sig = submitted.signature.clone();
+    ctx.db.set_submitted(trade_id, &sig, attempt)?;
+
+    match ctx.tracker.wait(venue, &sig).await? {
+        Confirmation::Confirmed => {
+            // Only a landed attempt pays its tip and priority fee.
+            ctx.db.set_submission_cost(trade_id, submitted.path, submitted.tip_lamports, submitted.priority_fee_lamports)?;
+            let fill = match order.instrument {
+                Instrument::Spot => ctx.tracker.spot_fill(&sig, &order.token_address, submitted.tip_in_tx_lamports).await?,
+                // Landing only placed the order; the fill is what the position moved.
+                Instrument::Perp => match before {
+                    Some(before) => {
+                        let fill = venue.fill_since(order, &before).await?;
+                        if fill.amount_raw == 0 {
+                            return Err(TxError::Unfilled { signature: sig, remaining_raw: quote.amount_raw }.into());
+                        }
+                        Some(fill)
+                    }
+                    None => None,
+                },
+            };
+            Ok(match fill {
+                Some(fill) => {
+                    ctx.db.record_fill(trade_id, fill.amount_raw, fill.price_usd, fill.size_usd)?;
+                    (sig, fill.amount_raw, fill.size_usd)
+                }
+                None => {
+                    ctx.db.set_filled_amount(trade_id, quote.amount_raw)?;
+                    (sig, quote.amount_raw, quote.size_usd)
+                }
+            })
+        }
+        Confirmation::Expired => Err(TxError::Expired(sig).into()),
+        Confirmation::Failed(reason) => Err(TxError::Failed { signature: sig, reason }.into()),
+        // Still unknown: it may yet land, so resubmitting could double-fill. Only a venue that
+        // recalls it for certain turns that into a plain failure.
+        Confirmation::Pending => {
+            match venue.cancel(order, &sig).await {
+                Ok(()) => {
+                    if let Ok(Confirmation::Failed(reason)) = venue.confirm(&sig).await {
+                        return Err(TxError::Failed { signature: sig, reason }.into());
+                    }
+                }
+                Err(e) => warn!(%sig, %e, "could not cancel unconfirmed order"),
+            }
+            Err(TxError::Unconfirmed(sig).into())
+        }
+    }
+}
+
+/// Reverses a filled leg with an opposite, reduce-only order of the same raw size: spot longs
+/// sell back through inventory, spot shorts cover through their venue, perps reduce. Failed
+/// attempts go through the retry policy like any order; an unconfirmed one is never resent.
+async fn unwind_leg(ctx: &TradeCtx, leg: &OrderLeg, fill: &LegFill, strategy_id: &str) -> Result<()> {
+    if CONFIG.paper_trading_mode {
+        ctx.db.set_trade_status(fill.trade_id, "UNWOUND")?;
+        return Ok(());
+    }
+    let mut order = VenueOrder {
+        instrument: leg.instrument,
+        token_address: leg.details.token_address.clone(),
+        side: leg.details.side.opposite(),
+        size_usd: fill.size_usd,
+        amount_raw: Some(fill.filled_amount),
+        reduce_only: true,
+        all_or_none: false,
+        short_venue: fill.short_venue.clone(),
+        slippage_bps: CONFIG.slippage_bps,
+        tip_lamports: CONFIG.jito_tip_lamports,
+        // An unwind must not be left stranded by a Jito outage.
+        submit_path: SubmitPath::JitoThenRpc,
+        signer_key: CONFIG.signer_key(strategy_id),
+    };
+    let venue = ctx.router.route(&order);
+    let mut attempt = 0u32;
+    let mut retries: HashMap<FailureClass, u32> = HashMap::new();
+    loop {
+        attempt += 1;
+        // Unwinds are urgent: tip as for a full-confidence signal, or the static tip rather than not unwinding.
+        order.tip_lamports = ctx.tips.tip_for(fill.size_usd, 1.0).await.unwrap_or_else(|e| {
+            warn!(trade_id = fill.trade_id, %e, "unwind tip falls back to JITO_TIP_LAMPORTS");
+            CONFIG.jito_tip_lamports
+        });
+        let result: Result<(String, Option<u64>)> = async {
+            let quote = venue.quote(&order).await?;
+            let built = venue.build(&order, &quote).await?;
+            let before = venue.position(&order).await?;
+            let sig = venue.submit(built).await?.signature;
+            match ctx.tracker.wait(venue.as_ref(), &sig).await? {
+                Confirmation::Confirmed => match before {
+                    Some(before) => Ok((sig, Some(venue.fill_since(&order, &before).await?.amount_raw))),
+                    None => Ok((sig, None)),
+                },
+                Confirmation::Expired => Err(TxError::Expired(sig).into()),
+                Confirmation::Failed(reason) => Err(TxError::Failed { signature: sig, reason }.into()),
+                Confirmation::Pending => Err(TxError::Unconfirmed(sig).into()),
+            }
+        }.await;
+
+        let e = match result {
+            // A resting order that only partly filled leaves the rest to unwind.
+            Ok((signature, Some(filled))) if filled < order.amount_raw.unwrap_or(0) => {
+                let remaining_raw = order.amount_raw.unwrap_or(0) - filled;
+                order.amount_raw = Some(remaining_raw);
+                TxError::Unfilled { signature, remaining_raw }.into()
+            }
+            Ok((sig, _)) => {
+                ctx.db.set_trade_status(fill.trade_id, "UNWOUND")?;
+                info!(trade_id = fill.trade_id, venue = venue.name(), %sig, "leg unwound");
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+        let class = FailureClass::classify(&e);
+        let retried = retries.entry(class).or_default();
+        let decision = ctx.retry.decide(class, *retried, order.slippage_bps);
+        *retried += 1;
+        ctx.db.log_execution_attempt(fill.trade_id, attempt, &format!("unwind_{}", class.as_str()), &e.to_string(), order.slippage_bps)?;
+        warn!(trade_id = fill.trade_id, attempt, class = class.as_str(), ?decision, %e, "unwind attempt failed");
+
+        match decision {
+            RetryDecision::Rebuild => {}
+            RetryDecision::Requote { slippage_bps } => order.slippage_bps = slippage_bps,
+            RetryDecision::Retry => tokio::time::sleep(ctx.retry.backoff).await,
+            RetryDecision::GiveUp => return Err(e),
+        }
+    }
+}
+
 // helper
 fn simulate_fill(db:&Database, id:i64, size:f64, short:bool) -> Result<()> {
     let pnl = size * (rand::random::<f64>()*0.1 - 0.05) * if short { -1.0 } else { 1.0 };
@@ -267,3 +855,262 @@ fn simulate_fill(db:&Database, id:i64, size:f64, short:bool) -> Result<()> {
     db.update_trade_pnl(id,status,0.0,pnl)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{balances::WalletBalances, config};
+    use std::time::Duration;
+
+    /// One mock per route, so tests can tell which venue an order went to.
+    struct Venues {
+        spot: Arc<MockVenue>,
+        perp: Arc<MockVenue>,
+        spot_short: Arc<MockVenue>,
+    }
+
+    impl Venues {
+        fn new() -> Self {
+            Self { spot: Arc::new(MockVenue::new(2.0)), perp: Arc::new(MockVenue::new(2.0)), spot_short: Arc::new(MockVenue::new(2.0)) }
+        }
+    }
+
+    fn test_ctx(venues: &Venues) -> TradeCtx {
+        config::test_env();
+        let path = std::env::temp_dir().join(format!("executor-test-{}-{}.db", std::process::id(), rand::random::<u64>()));
+        let db = Arc::new(Database::new(path.to_str().unwrap()).unwrap());
+        let rpc: Arc<dyn SolanaRpc> = Arc::new(StubRpc::default());
+        let sol_price = Arc::new(tokio::sync::Mutex::new(100.0));
+        let signer = Arc::new(SignerClient::from_config());
+        let policy = ConfirmationPolicy { poll_interval: Duration::from_millis(1), max_wait: Duration::from_millis(20) };
+        TradeCtx {
+            db,
+            router: Arc::new(OrderRouter::new(
+                venues.spot.clone(),
+                venues.perp.clone(),
+                venues.spot_short.clone(),
+                None,
+                Arc::new(PerpMarketRegistry::load(None).unwrap()),
+            )),
+            tracker: Arc::new(ConfirmationTracker::new(rpc.clone(), sol_price.clone(), policy)),
+            retry: Arc::new(RetryPolicy::from_config()),
+            depth: DepthBook::default(),
+            pretrade: Arc::new(PreTradeLimits::from_config()),
+            working: WorkingOrders::default(),
+            paused: Arc::new(tokio::sync::Mutex::new(false)),
+            tips: Arc::new(TipPolicy::from_config(sol_price.clone()).unwrap()),
+            balances: Arc::new(BalanceTracker::new(rpc, signer.clone(), sol_price.clone())),
+            signer,
+            sol_usd_price: sol_price,
+        }
+    }
+
+    fn leg(instrument: Instrument, token: &str, side: Side) -> OrderLeg {
+        OrderLeg {
+            instrument,
+            details: OrderDetails { token_address: token.to_string(), suggested_size_usd: 100.0, confidence: 1.0, side },
+            closes: None,
+        }
+    }
+
+    fn spot_leg(token: &str, side: Side) -> OrderLeg { leg(Instrument::Spot, token, side) }
+    fn perp_leg(token: &str, side: Side) -> OrderLeg { leg(Instrument::Perp, token, side) }
+
+    fn status_of(ctx: &TradeCtx, token: &str) -> String {
+        ctx.db.get_all_trades().unwrap().into_iter().find(|t| t.token_address == token).unwrap().status
+    }
+
+    #[tokio::test]
+    async fn group_fills_every_leg() {
+        let venues = Venues::new();
+        let ctx = test_ctx(&venues);
+        let legs = [spot_leg("spot", Side::Long), perp_leg("perp", Side::Short)];
+        let fills = execute_group(&ctx, &legs, "test", Instant::now()).await.unwrap();
+        assert_eq!(fills.iter().map(|f| f.filled_amount).collect::<Vec<_>>(), vec![50_000_000, 50_000_000]);
+        assert_eq!((venues.spot.built().len(), venues.perp.built().len()), (1, 1));
+        assert_eq!(status_of(&ctx, "spot"), "OPEN");
+    }
+
+    #[tokio::test]
+    async fn failed_leg_unwinds_filled_spot_long() {
+        let venues = Venues::new();
+        venues.perp.script([Confirmation::Failed("custom program error: 0x1".to_string())]);
+        let ctx = test_ctx(&venues);
+        let legs = [spot_leg("spot", Side::Long), perp_leg("perp", Side::Short)];
+        let Err(aborted) = execute_group(&ctx, &legs, "test", Instant::now()).await else {
+            panic!("a group with a failed leg must not fill");
+        };
+        assert!(aborted.open.iter().all(Option::is_none));
+
+        // Selling a spot long back goes through the spot-short venue's inventory path.
+        let built = venues.spot_short.built();
+        assert_eq!(built.len(), 1);
+        let unwind = &built[0];
+        assert_eq!((unwind.instrument, unwind.token_address.as_str(), &unwind.side), (Instrument::Spot, "spot", &Side::Short));
+        assert!(unwind.reduce_only);
+        assert_eq!(unwind.amount_raw, Some(50_000_000));
+        assert_eq!(status_of(&ctx, "spot"), "UNWOUND");
+        assert_eq!(status_of(&ctx, "perp"), "FAILED");
+    }
+
+    #[tokio::test]
+    async fn spot_short_unwinds_by_covering_through_its_venue() {
+        let venues = Venues::new();
+        venues.perp.script([Confirmation::Failed("custom program error: 0x1".to_string())]);
+        let ctx = test_ctx(&venues);
+        let legs = [spot_leg("spot", Side::Short), perp_leg("perp", Side::Long)];
+        assert!(execute_group(&ctx, &legs, "test", Instant::now()).await.is_err());
+
+        // Opened and covered on the spot-short route, never through plain Jupiter buys.
+        let built = venues.spot_short.built();
+        assert_eq!(built.len(), 2, "the short, then its cover");
+        assert!(venues.spot.built().is_empty());
+        let cover = &built[1];
+        assert_eq!((&cover.side, cover.reduce_only, cover.amount_raw), (&Side::Long, true, Some(50_000_000)));
+        assert_eq!(status_of(&ctx, "spot"), "UNWOUND");
+    }
+
+    #[tokio::test]
+    async fn unwind_without_a_sol_price_uses_the_static_tip() {
+        let venues = Venues::new();
+        let ctx = test_ctx(&venues);
+        let leg = spot_leg("spot", Side::Long);
+        let fill = execute_leg(&ctx, &leg, "test", LegOrigin::Single, Instant::now()).await.unwrap();
+
+        *ctx.sol_usd_price.lock().await = 0.0;
+        unwind_leg(&ctx, &leg, &fill, "test").await.unwrap();
+        let built = venues.spot_short.built();
+        assert_eq!(built.len(), 1);
+        assert_eq!(built[0].tip_lamports, CONFIG.jito_tip_lamports);
+        assert_eq!(status_of(&ctx, "spot"), "UNWOUND");
+    }
+
+    #[tokio::test]
+    async fn failed_unwind_is_recorded_and_reported_open() {
+        let venues = Venues::new();
+        venues.perp.script([Confirmation::Failed("custom program error: 0x1".to_string())]);
+        // Every unwind attempt slips, until the retry policy runs out of re-quotes.
+        venues.spot_short.script(std::iter::repeat_n(Confirmation::Failed("custom program error: 0x1771".to_string()), 20));
+        let ctx = test_ctx(&venues);
+        let legs = [spot_leg("spot", Side::Long), perp_leg("perp", Side::Short)];
+        let Err(aborted) = execute_group(&ctx, &legs, "test", Instant::now()).await else {
+            panic!("a group with a failed leg must not fill");
+        };
+
+        assert!(venues.spot_short.built().len() > 1, "the unwind is retried");
+        assert_eq!(status_of(&ctx, "spot"), "UNWIND_FAILED");
+        assert_eq!(aborted.open.len(), 2);
+        assert_eq!(aborted.open[0].as_ref().map(|p| p.amount_raw), Some(50_000_000));
+        assert!(aborted.open[1].is_none());
+    }
+
+    #[tokio::test]
+    async fn group_leg_over_buying_power_is_rejected_not_capped() {
+        let venues = Venues::new();
+        let ctx = test_ctx(&venues);
+        // 0.55 SOL less the 0.05 SOL reserve, at $100: $50 to spend against $100 legs.
+        ctx.balances.seed(&CONFIG.signer_key("test"), WalletBalances { sol_lamports: 550_000_000, tokens: HashMap::new() }).await;
+
+        let legs = [spot_leg("group", Side::Long), perp_leg("perp", Side::Short)];
+        assert!(execute_group(&ctx, &legs, "test", Instant::now()).await.is_err());
+        assert!(venues.spot.built().is_empty() && venues.perp.built().is_empty());
+
+        let fill = execute_leg(&ctx, &spot_leg("single", Side::Long), "test", LegOrigin::Single, Instant::now()).await.unwrap();
+        assert!((fill.size_usd - 50.0).abs() < 1e-6, "a single leg is capped instead");
+    }
+
+    #[tokio::test]
+    async fn perp_fill_is_what_the_position_moved() {
+        let venues = Venues::new();
+        // A landed order that keepers only partly fill, then one they never fill.
+        venues.perp.script_fills([20_000_000, 0]);
+        let ctx = test_ctx(&venues);
+        let fill = execute_leg(&ctx, &perp_leg("perp", Side::Short), "test", LegOrigin::Single, Instant::now()).await.unwrap();
+        assert_eq!(fill.filled_amount, 20_000_000);
+        assert!((fill.size_usd - 40.0).abs() < 1e-6);
+
+        // The unfilled order is re-quoted with wider slippage and fills in full.
+        let fill = execute_leg(&ctx, &perp_leg("unfilled", Side::Short), "test", LegOrigin::Single, Instant::now()).await.unwrap();
+        assert_eq!(fill.filled_amount, 50_000_000);
+        let built = venues.perp.built();
+        assert_eq!(built.len(), 3);
+        assert!(built[2].slippage_bps > built[1].slippage_bps);
+    }
+
+    #[tokio::test]
+    async fn partly_filled_perp_unwind_retries_the_remainder() {
+        let venues = Venues::new();
+        let ctx = test_ctx(&venues);
+        let leg = perp_leg("perp", Side::Short);
+        let fill = execute_leg(&ctx, &leg, "test", LegOrigin::Single, Instant::now()).await.unwrap();
+
+        venues.perp.script_fills([30_000_000]);
+        unwind_leg(&ctx, &leg, &fill, "test").await.unwrap();
+        let amounts: Vec<_> = venues.perp.built()[1..].iter().map(|o| o.amount_raw).collect();
+        assert_eq!(amounts, vec![Some(50_000_000), Some(20_000_000)]);
+        assert_eq!(status_of(&ctx, "perp"), "UNWOUND");
+    }
+
+    #[tokio::test]
+    async fn unconfirmed_order_is_cancelled_and_failed() {
+        let venues = Venues::new();
+        venues.perp.script([Confirmation::Pending]);
+        let ctx = test_ctx(&venues);
+        let Err(err) = execute_leg(&ctx, &perp_leg("perp", Side::Short), "test", LegOrigin::Single, Instant::now()).await else {
+            panic!("an unconfirmed order must not count as filled");
+        };
+        assert!(matches!(err.downcast_ref::<TxError>(), Some(TxError::Failed { reason, .. }) if reason == "cancelled"));
+        assert_eq!(venues.perp.built().len(), 1, "a cancelled order is not resubmitted");
+        assert_eq!(status_of(&ctx, "perp"), "FAILED");
+    }
+
+    /// Runs a sliced spot order the way `execute_trade` does, on its own task.
+    async fn run_sliced(ctx: &TradeCtx, token: &str, algo: ExecAlgo, target_usd: f64, cancel_first: bool) -> (Result<()>, i64) {
+        let mut leg = spot_leg(token, Side::Long);
+        leg.details.suggested_size_usd = target_usd;
+        let parent_id = ctx.db.create_parent_order(&leg.details, "test", algo.name(), target_usd).unwrap();
+        let cancel = ctx.working.register(parent_id, "test", token);
+        if cancel_first {
+            ctx.working.cancel("test", Some(token), "signal withdrawn");
+        }
+        let ctx = ctx.clone();
+        let result = tokio::spawn(async move { run_parent_order(&ctx, leg, "test", algo, parent_id, target_usd, cancel).await }).await.unwrap();
+        (result, parent_id)
+    }
+
+    #[tokio::test]
+    async fn twap_parent_order_fills_in_equal_children() {
+        let venues = Venues::new();
+        let ctx = test_ctx(&venues);
+        let (result, parent_id) = run_sliced(&ctx, "twap", ExecAlgo::Twap { slices: 4, interval: Duration::ZERO }, 400.0, false).await;
+        result.unwrap();
+        let sizes: Vec<f64> = venues.spot.built().iter().map(|o| o.size_usd).collect();
+        assert_eq!(sizes, vec![100.0; 4]);
+        assert_eq!(ctx.db.parent_order(parent_id).unwrap(), ("COMPLETED".to_string(), 400.0, 4));
+    }
+
+    #[tokio::test]
+    async fn cancelled_parent_order_sends_no_children() {
+        let venues = Venues::new();
+        let ctx = test_ctx(&venues);
+        let (result, parent_id) = run_sliced(&ctx, "cancelled", ExecAlgo::Twap { slices: 4, interval: Duration::ZERO }, 400.0, true).await;
+        result.unwrap();
+        assert!(venues.spot.built().is_empty());
+        assert_eq!(ctx.db.parent_order(parent_id).unwrap(), ("CANCELED".to_string(), 0.0, 0));
+    }
+
+    #[tokio::test]
+    async fn parent_order_fails_after_consecutive_child_failures() {
+        let venues = Venues::new();
+        venues.spot.script([
+            Confirmation::Failed("custom program error: 0x1".to_string()),
+            Confirmation::Failed("custom program error: 0x1".to_string()),
+        ]);
+        let ctx = test_ctx(&venues);
+        let (result, parent_id) = run_sliced(&ctx, "failing", ExecAlgo::Twap { slices: 4, interval: Duration::ZERO }, 400.0, false).await;
+        assert!(result.is_err());
+        assert_eq!(venues.spot.built().len(), 2);
+        let (status, filled_usd, children) = ctx.db.parent_order(parent_id).unwrap();
+        assert_eq!((status.as_str(), filled_usd, children), ("FAILED", 0.0, 0));
+    }
+}
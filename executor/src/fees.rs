@@ -0,0 +1,225 @@
+// executor/src/fees.rs
+use crate::{config::CONFIG, rpc::SolanaRpc, static_keys::insert_key};
+use anyhow::Result;
+use solana_sdk::{
+    compute_budget::{self, ComputeBudgetInstruction},
+    instruction::CompiledInstruction,
+    message::{MessageHeader, VersionedMessage},
+    pubkey::Pubkey,
+};
+use std::sync::Arc;
+use tracing::warn;
+
+/// ComputeBudget instruction discriminators.
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// Compute-unit limit and price for transactions sent over plain RPC, where there is no tip
+/// and the priority fee is what gets them scheduled.
+pub struct FeePolicy {
+    /// Used only when the transaction does not already set its own limit.
+    pub cu_limit: u32,
+    /// Percentile of recent prioritization fees on the touched accounts to pay.
+    pub percentile: u8,
+    pub min_micro_lamports: u64,
+    pub max_micro_lamports: u64,
+    rpc: Arc<dyn SolanaRpc>,
+}
+
+/// What the policy set on one transaction.
+#[derive(Debug, Clone, Copy)]
+pub struct ComputeBudget {
+    pub cu_limit: u32,
+    pub cu_price_micro_lamports: u64,
+}
+
+impl ComputeBudget {
+    /// Priority fee in lamports if the whole limit is charged.
+    pub fn priority_fee_lamports(&self) -> u64 {
+        (self.cu_limit as u128 * self.cu_price_micro_lamports as u128 / 1_000_000) as u64
+    }
+}
+
+impl FeePolicy {
+    pub fn from_config(rpc: Arc<dyn SolanaRpc>) -> Self {
+        Self {
+            cu_limit: CONFIG.cu_limit,
+            percentile: CONFIG.priority_fee_percentile,
+            min_micro_lamports: CONFIG.priority_fee_min_micro_lamports,
+            max_micro_lamports: CONFIG.priority_fee_max_micro_lamports,
+            rpc,
+        }
+    }
+
+    /// Price per compute unit from recent fees paid to write-lock the same accounts.
+    pub async fn cu_price(&self, writable: &[Pubkey]) -> u64 {
+        let mut fees = match self.rpc.recent_prioritization_fees(writable).await {
+            Ok(fees) => fees,
+            Err(e) => {
+                warn!(%e, "prioritization fee lookup failed, using the minimum");
+                vec![]
+            }
+        };
+        if fees.is_empty() {
+            return self.min_micro_lamports;
+        }
+        fees.sort_unstable();
+        let index = ((fees.len() - 1) * self.percentile.min(100) as usize) / 100;
+        fees[index].clamp(self.min_micro_lamports, self.max_micro_lamports)
+    }
+
+    /// Sets the compute-unit price on `message`, and the limit if it has none, returning what was set.
+    pub async fn apply(&self, message: &mut VersionedMessage) -> Result<ComputeBudget> {
+        let writable: Vec<Pubkey> = message.static_account_keys().iter().enumerate()
+            .filter(|(i, _)| message.is_maybe_writable(*i))
+            .map(|(_, key)| *key)
+            .collect();
+        let cu_price = self.cu_price(&writable).await;
+        let cu_limit = match message {
+            VersionedMessage::Legacy(m) => set_compute_budget(&mut m.header, &mut m.account_keys, &mut m.instructions, self.cu_limit, cu_price)?,
+            VersionedMessage::V0(m) => set_compute_budget(&mut m.header, &mut m.account_keys, &mut m.instructions, self.cu_limit, cu_price)?,
+        };
+        Ok(ComputeBudget { cu_limit, cu_price_micro_lamports: cu_price })
+    }
+}
+
+/// Replaces any compute-unit price instruction with ours and adds a limit if missing.
+/// Returns the limit in effect; fails if there is no room for another static key.
+fn set_compute_budget(
+    header: &mut MessageHeader,
+    keys: &mut Vec<Pubkey>,
+    instructions: &mut Vec<CompiledInstruction>,
+    default_limit: u32,
+    cu_price: u64,
+) -> Result<u32> {
+    let program_index = match keys.iter().position(|k| *k == compute_budget::id()) {
+        Some(i) => u8::try_from(i)?,
+        None => {
+            let index = insert_key(keys, instructions, keys.len(), compute_budget::id())?;
+            header.num_readonly_unsigned_accounts += 1;
+            index
+        }
+    };
+    let is_budget = |ix: &CompiledInstruction, kind: u8| ix.program_id_index == program_index && ix.data.first() == Some(&kind);
+
+    let existing_limit = instructions.iter()
+        .find(|ix| is_budget(ix, SET_COMPUTE_UNIT_LIMIT))
+        .and_then(|ix| ix.data.get(1..5))
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    instructions.retain(|ix| !is_budget(ix, SET_COMPUTE_UNIT_PRICE));
+    instructions.insert(0, CompiledInstruction {
+        program_id_index: program_index,
+        accounts: vec![],
+        data: ComputeBudgetInstruction::set_compute_unit_price(cu_price).data,
+    });
+    Ok(match existing_limit {
+        Some(limit) => limit,
+        None => {
+            instructions.insert(0, CompiledInstruction {
+                program_id_index: program_index,
+                accounts: vec![],
+                data: ComputeBudgetInstruction::set_compute_unit_limit(default_limit).data,
+            });
+            default_limit
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::message::{v0, v0::MessageAddressTableLookup};
+
+    /// What an account index refers to: a static key, or the n-th address loaded from lookups.
+    #[derive(Debug, PartialEq)]
+    enum Account {
+        Static(Pubkey),
+        Loaded(usize),
+    }
+
+    fn resolve(message: &v0::Message, index: u8) -> Account {
+        match message.account_keys.get(index as usize) {
+            Some(key) => Account::Static(*key),
+            None => Account::Loaded(index as usize - message.account_keys.len()),
+        }
+    }
+
+    fn accounts_of(message: &v0::Message, program: &Pubkey) -> Vec<Account> {
+        let ix = message.instructions.iter().find(|ix| message.account_keys[ix.program_id_index as usize] == *program).unwrap();
+        ix.accounts.iter().map(|i| resolve(message, *i)).collect()
+    }
+
+    /// Payer and program as static keys, plus one writable and one readonly address from a lookup table.
+    fn swap_message(extra: Vec<CompiledInstruction>, extra_keys: Vec<Pubkey>) -> (v0::Message, Pubkey) {
+        let (payer, program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut account_keys = vec![payer, program];
+        account_keys.extend(extra_keys);
+        let mut instructions = extra;
+        instructions.push(CompiledInstruction { program_id_index: 1, accounts: vec![0, account_keys.len() as u8, account_keys.len() as u8 + 1], data: vec![9] });
+        let message = v0::Message {
+            header: MessageHeader { num_required_signatures: 1, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 1 },
+            account_keys,
+            recent_blockhash: Default::default(),
+            instructions,
+            address_table_lookups: vec![MessageAddressTableLookup { account_key: Pubkey::new_unique(), writable_indexes: vec![0], readonly_indexes: vec![1] }],
+        };
+        (message, program)
+    }
+
+    #[test]
+    fn inserting_the_program_keeps_instruction_accounts() {
+        let (mut message, program) = swap_message(vec![], vec![]);
+        let before = accounts_of(&message, &program);
+        let m = &mut message;
+        let limit = set_compute_budget(&mut m.header, &mut m.account_keys, &mut m.instructions, 200_000, 5_000).unwrap();
+
+        assert_eq!(limit, 200_000);
+        assert_eq!(accounts_of(&message, &program), before);
+        assert_eq!(before, vec![Account::Static(message.account_keys[0]), Account::Loaded(0), Account::Loaded(1)]);
+        assert_eq!(message.account_keys.last(), Some(&compute_budget::id()));
+        assert_eq!(message.header.num_readonly_unsigned_accounts, 2);
+        let budget: Vec<&[u8]> = message.instructions[..2].iter().map(|ix| ix.data.as_slice()).collect();
+        assert_eq!(budget, vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000).data.as_slice(),
+            ComputeBudgetInstruction::set_compute_unit_price(5_000).data.as_slice(),
+        ]);
+        assert!(message.instructions[..2].iter().all(|ix| message.account_keys[ix.program_id_index as usize] == compute_budget::id()));
+    }
+
+    #[test]
+    fn existing_limit_is_kept_and_price_replaced() {
+        let existing = vec![
+            CompiledInstruction { program_id_index: 2, accounts: vec![], data: ComputeBudgetInstruction::set_compute_unit_limit(400_000).data },
+            CompiledInstruction { program_id_index: 2, accounts: vec![], data: ComputeBudgetInstruction::set_compute_unit_price(1).data },
+        ];
+        let (mut message, program) = swap_message(existing, vec![compute_budget::id()]);
+        let before = accounts_of(&message, &program);
+        let keys = message.account_keys.len();
+        let m = &mut message;
+        let limit = set_compute_budget(&mut m.header, &mut m.account_keys, &mut m.instructions, 200_000, 5_000).unwrap();
+
+        assert_eq!(limit, 400_000);
+        assert_eq!(message.account_keys.len(), keys, "no key added");
+        assert_eq!(accounts_of(&message, &program), before);
+        let prices: Vec<&CompiledInstruction> = message.instructions.iter().filter(|ix| ix.data.first() == Some(&SET_COMPUTE_UNIT_PRICE)).collect();
+        assert_eq!(prices.len(), 1);
+        assert_eq!(prices[0].data, ComputeBudgetInstruction::set_compute_unit_price(5_000).data);
+    }
+
+    #[test]
+    fn full_static_key_table_is_an_error() {
+        let (mut message, _) = swap_message(vec![], (0..254).map(|_| Pubkey::new_unique()).collect());
+        let m = &mut message;
+        assert!(set_compute_budget(&mut m.header, &mut m.account_keys, &mut m.instructions, 200_000, 5_000).is_err());
+    }
+
+    #[test]
+    fn lookup_index_that_would_overflow_is_an_error() {
+        let (mut message, _) = swap_message(vec![], vec![]);
+        message.instructions[0].accounts[2] = u8::MAX;
+        let before = message.clone();
+        let m = &mut message;
+        assert!(set_compute_budget(&mut m.header, &mut m.account_keys, &mut m.instructions, 200_000, 5_000).is_err());
+        assert_eq!(message, before, "nothing is shifted");
+    }
+}
@@ -0,0 +1,138 @@
+// executor/src/jito.rs
+use crate::{static_keys::insert_key, tips};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use reqwest::Client;
+use serde_json::{json, Value};
+use solana_sdk::{
+    instruction::CompiledInstruction,
+    message::{MessageHeader, VersionedMessage},
+    pubkey::Pubkey,
+    system_instruction, system_program,
+    transaction::VersionedTransaction,
+};
+
+/// Jito block engine over its JSON-RPC HTTP API.
+pub struct JitoClient {
+    client: Client,
+    url: String,
+}
+
+impl JitoClient {
+    pub fn new(url: String) -> Self {
+        Self { client: Client::new(), url: url.trim_end_matches('/').to_string() }
+    }
+
+    async fn call(&self, path: &str, method: &str, params: Value) -> Result<Value> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response: Value = self.client.post(format!("{}{}", self.url, path)).json(&body).send().await?
+            .error_for_status()?
+            .json().await?;
+        if let Some(error) = response.get("error") {
+            bail!("Jito {} failed: {}", method, error);
+        }
+        response.get("result").cloned().ok_or_else(|| anyhow!("Jito {} returned no result", method))
+    }
+
+    /// Sends signed transactions as one bundle, returning its id.
+    pub async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String> {
+        let encoded = transactions.iter()
+            .map(|tx| Ok(B64.encode(bincode::serialize(tx)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let result = self.call("/api/v1/bundles", "sendBundle", json!([encoded, { "encoding": "base64" }])).await?;
+        result.as_str().map(str::to_string).context("Jito sendBundle result is not a bundle id")
+    }
+
+    pub async fn send_transaction(&self, tx: &VersionedTransaction) -> Result<String> {
+        let encoded = B64.encode(bincode::serialize(tx)?);
+        let result = self.call("/api/v1/transactions", "sendTransaction", json!([encoded, { "encoding": "base64" }])).await?;
+        result.as_str().map(str::to_string).context("Jito sendTransaction result is not a signature")
+    }
+}
+
+/// Appends a SOL transfer of `tip_lamports` from the fee payer to a Jito tip account.
+/// The message changes, so this must happen before signing.
+pub fn attach_tip(tx: &mut VersionedTransaction, tip_lamports: u64) -> Result<()> {
+    match &mut tx.message {
+        VersionedMessage::Legacy(m) => append_tip(&mut m.header, &mut m.account_keys, &mut m.instructions, tip_lamports),
+        VersionedMessage::V0(m) => append_tip(&mut m.header, &mut m.account_keys, &mut m.instructions, tip_lamports),
+    }
+}
+
+fn append_tip(header: &mut MessageHeader, keys: &mut Vec<Pubkey>, instructions: &mut Vec<CompiledInstruction>, tip_lamports: u64) -> Result<()> {
+    let payer = *keys.first().ok_or_else(|| anyhow!("message has no fee payer"))?;
+    let tip_account = tips::tip_account();
+    // The tip account goes last among the writable non-signers, the system program last overall.
+    let writable_end = keys.len() - header.num_readonly_unsigned_accounts as usize;
+    let tip_index = insert_key(keys, instructions, writable_end, tip_account)?;
+    let program_index = match keys.iter().position(|k| *k == system_program::id()) {
+        Some(i) => u8::try_from(i)?,
+        None => {
+            let index = insert_key(keys, instructions, keys.len(), system_program::id())?;
+            header.num_readonly_unsigned_accounts += 1;
+            index
+        }
+    };
+    let ix = system_instruction::transfer(&payer, &tip_account, tip_lamports);
+    instructions.push(CompiledInstruction { program_id_index: program_index, accounts: vec![0, tip_index], data: ix.data });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{hash::Hash, message::{v0, v0::MessageAddressTableLookup}, system_instruction::SystemInstruction};
+
+    /// Payer, a writable account and a program as static keys, plus one address from a lookup table.
+    fn swap_tx() -> VersionedTransaction {
+        let (payer, pool, program) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let message = v0::Message {
+            header: MessageHeader { num_required_signatures: 1, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 1 },
+            account_keys: vec![payer, pool, program],
+            recent_blockhash: Hash::default(),
+            instructions: vec![CompiledInstruction { program_id_index: 2, accounts: vec![0, 1, 3], data: vec![9] }],
+            address_table_lookups: vec![MessageAddressTableLookup { account_key: Pubkey::new_unique(), writable_indexes: vec![], readonly_indexes: vec![0] }],
+        };
+        VersionedTransaction { signatures: vec![Default::default()], message: VersionedMessage::V0(message) }
+    }
+
+    fn v0(tx: &VersionedTransaction) -> &v0::Message {
+        let VersionedMessage::V0(m) = &tx.message else { unreachable!() };
+        m
+    }
+
+    #[test]
+    fn tip_is_appended_without_moving_existing_accounts() {
+        let mut tx = swap_tx();
+        let before = v0(&tx).clone();
+        attach_tip(&mut tx, 10_000).unwrap();
+        let m = v0(&tx);
+
+        // The swap still points at payer, pool, program and the looked-up address (first index past the static keys).
+        let swap = &m.instructions[0];
+        assert_eq!(m.account_keys[swap.program_id_index as usize], before.account_keys[2]);
+        assert_eq!(m.account_keys[swap.accounts[0] as usize], before.account_keys[0]);
+        assert_eq!(m.account_keys[swap.accounts[1] as usize], before.account_keys[1]);
+        assert_eq!(swap.accounts[2] as usize, m.account_keys.len());
+
+        // The tip account is writable, the system program readonly.
+        assert_eq!(m.header.num_readonly_unsigned_accounts, 2);
+        let tip = &m.instructions[1];
+        assert_eq!(m.account_keys[tip.program_id_index as usize], system_program::id());
+        assert_eq!(m.account_keys[tip.accounts[0] as usize], before.account_keys[0]);
+        assert!(m.is_maybe_writable(tip.accounts[1] as usize));
+        assert!(!m.is_maybe_writable(tip.program_id_index as usize));
+        assert_eq!(bincode::deserialize::<SystemInstruction>(&tip.data).unwrap(), SystemInstruction::Transfer { lamports: 10_000 });
+    }
+
+    #[test]
+    fn tip_is_refused_when_a_lookup_index_would_overflow() {
+        let mut tx = swap_tx();
+        let VersionedMessage::V0(m) = &mut tx.message else { unreachable!() };
+        m.instructions[0].accounts[2] = u8::MAX;
+        let before = v0(&tx).clone();
+        assert!(attach_tip(&mut tx, 10_000).is_err());
+        assert_eq!(v0(&tx).instructions, before.instructions, "nothing is shifted");
+        assert_eq!(v0(&tx).account_keys, before.account_keys);
+    }
+}
@@ -1,108 +1,318 @@
 // executor/src/jupiter.rs
-use crate::config::CONFIG;
-use anyhow::{anyhow, Context, Result};
-use reqwest::Client;
-use serde::Deserialize;
+use crate::{config::CONFIG, rpc::SolanaRpc};
+use anyhow::{anyhow, ensure, Context, Result};
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use reqwest::{Client, Response, StatusCode};
+use serde::{Deserialize, Serialize};
 use solana_sdk::{pubkey::Pubkey, transaction::VersionedTransaction};
-use std::time::Duration;
+use std::{collections::HashMap, fmt, str::FromStr, sync::Arc, time::Duration};
+use tokio::sync::Mutex;
 use tracing::info;
 
-#[derive(Debug, Deserialize)]
+pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+const SOL_DECIMALS: u8 = 9;
+
+/// Jupiter v6 `/quote` response. Unknown fields are kept so the quote can be posted back to `/swap` intact.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct JupiterQuote {
+    pub input_mint: String,
+    pub in_amount: String,
+    pub output_mint: String,
     pub out_amount: String,
-    #[serde(rename = "marketInfos")]
-    pub market_infos: Vec<MarketInfo>,
+    /// Minimum output after slippage (ExactIn).
+    pub other_amount_threshold: String,
+    pub swap_mode: String,
+    pub slippage_bps: u16,
+    pub price_impact_pct: String,
+    pub route_plan: Vec<RoutePlanStep>,
+    #[serde(default)]
+    pub context_slot: Option<u64>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct MarketInfo {
-    pub lp_fee: LpFee,
-    pub liquidity: f64,
+pub struct RoutePlanStep {
+    pub swap_info: SwapInfo,
+    pub percent: u8,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct LpFee {
-    pub amount: String,
-    pub mint: String,
-    pub pct: f64,
+pub struct SwapInfo {
+    pub amm_key: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub in_amount: String,
+    pub out_amount: String,
+    pub fee_amount: String,
+    pub fee_mint: String,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct JupiterQuoteResponse {
-    pub data: Vec<JupiterQuote>,
+impl JupiterQuote {
+    pub fn in_amount(&self) -> Result<u64> { Ok(self.in_amount.parse()?) }
+    pub fn out_amount(&self) -> Result<u64> { Ok(self.out_amount.parse()?) }
+    pub fn min_out_amount(&self) -> Result<u64> { Ok(self.other_amount_threshold.parse()?) }
+    /// Price impact as a fraction (Jupiter reports e.g. "0.0123" for 1.23%).
+    pub fn price_impact(&self) -> f64 { self.price_impact_pct.parse().unwrap_or(0.0) }
+
+    /// AMM labels along the route, e.g. "Raydium>Orca".
+    pub fn route_label(&self) -> String {
+        self.route_plan.iter()
+            .map(|step| step.swap_info.label.clone().unwrap_or_else(|| step.swap_info.amm_key.clone()))
+            .collect::<Vec<_>>()
+            .join(">")
+    }
+
+    /// Rejects quotes that are not ExactIn, or whose minimum output is empty or looser than the
+    /// slippage we asked for. Price impact is judged by the pre-trade check, which can downsize instead.
+    pub fn validate(&self) -> Result<()> {
+        // In ExactOut quotes `otherAmountThreshold` is the maximum input, which nothing here checks.
+        ensure!(self.swap_mode == "ExactIn", "swap mode {} is not supported, only ExactIn", self.swap_mode);
+        let out = self.out_amount()?;
+        let min_out = self.min_out_amount()?;
+        let floor = out as u128 * (10_000 - self.slippage_bps.min(10_000) as u128) / 10_000;
+        if min_out == 0 || (min_out as u128) < floor {
+            return Err(anyhow!("minimum output {} is below {} allowed by {} bps slippage", min_out, floor, self.slippage_bps));
+        }
+        Ok(())
+    }
+}
+
+/// A non-2xx answer from the Jupiter API, kept typed so the retry policy can tell a missing
+/// route from rate limiting or an outage.
+#[derive(Debug)]
+pub struct JupiterHttpError {
+    pub endpoint: &'static str,
+    pub status: StatusCode,
+    pub body: String,
+}
+
+impl JupiterHttpError {
+    /// Rate limited or a server-side failure: the same request may succeed shortly.
+    pub fn is_retryable(&self) -> bool {
+        self.status == StatusCode::TOO_MANY_REQUESTS || self.status.is_server_error()
+    }
+
+    /// Jupiter answers an unroutable pair with a 4xx carrying one of these codes.
+    pub fn is_no_route(&self) -> bool {
+        self.status.is_client_error()
+            && ["COULD_NOT_FIND_ANY_ROUTE", "NO_ROUTES_FOUND", "No routes found"].iter().any(|code| self.body.contains(code))
+    }
+
+    async fn check(endpoint: &'static str, response: Response) -> Result<Response, Self> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        Err(Self { endpoint, status, body: response.text().await.unwrap_or_default() })
+    }
+}
+
+impl fmt::Display for JupiterHttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Jupiter {} returned {}: {}", self.endpoint, self.status, self.body)
+    }
 }
 
+impl std::error::Error for JupiterHttpError {}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SwapResponse {
     pub swap_transaction: String,
 }
 
-pub struct QuoteResult {
-    pub out_amount: u64,
-    pub price_per_token: f64, // Price of the token per USD
-}
-
 pub struct JupiterClient {
     client: Client,
+    rpc: Arc<dyn SolanaRpc>,
+    sol_price: Arc<Mutex<f64>>,
+    decimals: Mutex<HashMap<String, u8>>,
 }
 
 impl JupiterClient {
-    pub fn new() -> Self {
+    pub fn new(rpc: Arc<dyn SolanaRpc>, sol_price: Arc<Mutex<f64>>) -> Self {
         Self {
             client: Client::builder().timeout(Duration::from_secs(15)).build().unwrap(),
+            rpc,
+            sol_price,
+            decimals: Mutex::new(HashMap::new()),
         }
     }
 
-    pub async fn get_quote(&self, amount_sol_to_swap: f64, output_mint: &str) -> Result<QuoteResult> {
-        let amount_lamports = (amount_sol_to_swap * 1_000_000_000.0) as u64; // Convert SOL to Lamports
+    /// ExactIn quote for `amount_raw` base units of `input_mint`.
+    pub async fn get_quote(&self, input_mint: &str, output_mint: &str, amount_raw: u64, slippage_bps: u16) -> Result<JupiterQuote> {
         let url = format!(
-            "{}/quote?inputMint=So11111111111111111111111111111111111111112&outputMint={}&amount={}&slippageBps={}",
-            CONFIG.jupiter_api_url, output_mint, amount_lamports, CONFIG.slippage_bps
+            "{}/quote?inputMint={}&outputMint={}&amount={}&slippageBps={}",
+            CONFIG.jupiter_api_url, input_mint, output_mint, amount_raw, slippage_bps
         );
+        let response = JupiterHttpError::check("quote", self.client.get(&url).send().await?).await
+            .with_context(|| format!("Jupiter quote {} -> {}", input_mint, output_mint))?;
+        let quote: JupiterQuote = response.json().await.context("Unexpected Jupiter quote shape")?;
+        info!("Jupiter quote {} {} -> {} {} (impact {:.4}%)", quote.in_amount, input_mint, quote.out_amount, output_mint, quote.price_impact() * 100.0);
+        Ok(quote)
+    }
 
-        let response: JupiterQuoteResponse = self.client.get(&url).send().await?.json().await?;
-        let best_route = response.data.first().ok_or_else(|| anyhow!("No route found by Jupiter for {}", output_mint))?;
-        
-        let out_amount: u64 = best_route.out_amount.parse()?;
-        
-        // Calculate price_per_token based on the swap of 1 SOL
-        let price_per_token = (amount_sol_to_swap / (out_amount as f64 / 1_000_000_000.0)).recip(); // (1 SOL / tokens_received) -> Price per Token
-        info!("Jupiter quote for {} SOL -> {}. Price per token: {:.8} USD", amount_sol_to_swap, output_mint, price_per_token);
-
-        Ok(QuoteResult { out_amount, price_per_token })
+    /// Decimals of `mint`, read once from chain and cached.
+    pub async fn token_decimals(&self, mint: &str) -> Result<u8> {
+        if mint == SOL_MINT { return Ok(SOL_DECIMALS); }
+        if let Some(&d) = self.decimals.lock().await.get(mint) { return Ok(d); }
+        let d = self.rpc.token_decimals(&Pubkey::from_str(mint)?).await?;
+        self.decimals.lock().await.insert(mint.to_string(), d);
+        Ok(d)
     }
 
-    pub async fn get_swap_transaction(&self, user_pubkey: &Pubkey, output_mint: &str, amount_usd_to_swap: f64) -> Result<String> {
-        // Assume 1 SOL = $100 for a rough conversion for Jupiter API call
-        // In a real system, you'd get the live SOL/USD price
-        let amount_sol = amount_usd_to_swap / 100.0; 
-        let amount_lamports = (amount_sol * 1_000_000_000.0) as u64;
+    pub async fn usd_to_lamports(&self, amount_usd: f64) -> Result<u64> {
+        let sol_usd = *self.sol_price.lock().await;
+        ensure!(sol_usd > 0.0, "no SOL/USD price yet");
+        Ok((amount_usd / sol_usd * 1e9) as u64)
+    }
 
-        let quote_url = format!(
-            "{}/quote?inputMint=So11111111111111111111111111111111111111112&outputMint={}&amount={}&slippageBps={}",
-            CONFIG.jupiter_api_url, output_mint, amount_lamports, CONFIG.slippage_bps
-        );
-        let quote_response: serde_json::Value = self.client.get(&quote_url).send().await?.json().await?;
-        
-        let swap_payload = serde_json::json!({
-            "quoteResponse": quote_response,
-            "userPublicKey": user_pubkey.to_string(),
-            "wrapAndUnwrapSol": true,
-        });
+    /// USD price of the non-SOL side of a SOL-paired quote, using the live SOL oracle.
+    pub async fn price_usd(&self, quote: &JupiterQuote) -> Result<f64> {
+        let sol_usd = *self.sol_price.lock().await;
+        ensure!(sol_usd > 0.0, "no SOL/USD price yet");
+        let (sol_raw, token_raw, token_mint) = if quote.input_mint == SOL_MINT {
+            (quote.in_amount()?, quote.out_amount()?, &quote.output_mint)
+        } else if quote.output_mint == SOL_MINT {
+            (quote.out_amount()?, quote.in_amount()?, &quote.input_mint)
+        } else {
+            return Err(anyhow!("quote {} -> {} is not SOL-paired", quote.input_mint, quote.output_mint));
+        };
+        let decimals = self.token_decimals(token_mint).await?;
+        let tokens = token_raw as f64 / 10f64.powi(decimals as i32);
+        if tokens == 0.0 { return Err(anyhow!("quote for {} returns zero tokens", token_mint)); }
+        Ok(sol_raw as f64 / 1e9 * sol_usd / tokens)
+    }
 
+    /// Builds the swap for exactly this quote, so the executed route is the one that was priced and logged.
+    pub async fn swap_transaction(&self, user_pubkey: &Pubkey, quote: &JupiterQuote) -> Result<String> {
+        let swap_payload = swap_request(user_pubkey, quote);
         let swap_url = format!("{}/swap", CONFIG.jupiter_api_url);
-        let response: SwapResponse = self.client.post(swap_url).json(&swap_payload).send().await?.json().await?;
-        info!("Generated Jupiter swap transaction for {} USD.", amount_usd_to_swap);
+        let response = JupiterHttpError::check("swap", self.client.post(swap_url).json(&swap_payload).send().await?).await?;
+        let response: SwapResponse = response.json().await.context("Unexpected Jupiter swap shape")?;
+        info!("Generated Jupiter swap transaction {} {} -> {} {}.", quote.in_amount, quote.input_mint, quote.out_amount, quote.output_mint);
         Ok(response.swap_transaction)
     }
 }
 
+/// `/swap` body: the quote exactly as `/quote` returned it, unknown fields included.
+fn swap_request(user_pubkey: &Pubkey, quote: &JupiterQuote) -> serde_json::Value {
+    serde_json::json!({
+        "quoteResponse": quote,
+        "userPublicKey": user_pubkey.to_string(),
+        "wrapAndUnwrapSol": true,
+    })
+}
+
 pub fn deserialize_transaction(tx_b64: &str) -> Result<VersionedTransaction> {
-    let tx_bytes = base64::decode(tx_b64)?;
+    let tx_bytes = B64.decode(tx_b64)?;
     bincode::deserialize(&tx_bytes).context("Failed to deserialize transaction")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::StubRpc;
+    use serde_json::json;
+
+    /// 1 SOL → USDC as Jupiter v6 `/quote` returns it, split over two pools at 50 bps slippage.
+    fn v6_quote() -> serde_json::Value {
+        json!({
+            "inputMint": "So11111111111111111111111111111111111111112",
+            "inAmount": "1000000000",
+            "outputMint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+            "outAmount": "147260318",
+            "otherAmountThreshold": "146524016",
+            "swapMode": "ExactIn",
+            "slippageBps": 50,
+            "platformFee": null,
+            "priceImpactPct": "0.0012",
+            "routePlan": [
+                {
+                    "swapInfo": {
+                        "ammKey": "Czfq3xZZDmsdGdUyrNLtRhGc47cXcZtLG4crryfu44zE",
+                        "label": "Whirlpool",
+                        "inputMint": "So11111111111111111111111111111111111111112",
+                        "outputMint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                        "inAmount": "600000000",
+                        "outAmount": "88362510",
+                        "feeAmount": "240000",
+                        "feeMint": "So11111111111111111111111111111111111111112"
+                    },
+                    "percent": 60
+                },
+                {
+                    "swapInfo": {
+                        "ammKey": "8sLbNZoA1cfnvMJLPfp98ZLAnFSYCFApfJKMbiXNLwxj",
+                        "label": "Raydium CLMM",
+                        "inputMint": "So11111111111111111111111111111111111111112",
+                        "outputMint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                        "inAmount": "400000000",
+                        "outAmount": "58897808",
+                        "feeAmount": "100000",
+                        "feeMint": "So11111111111111111111111111111111111111112"
+                    },
+                    "percent": 40
+                }
+            ],
+            "contextSlot": 299113841,
+            "timeTaken": 0.003
+        })
+    }
+
+    fn quote_with(field: &str, value: serde_json::Value) -> JupiterQuote {
+        let mut raw = v6_quote();
+        raw[field] = value;
+        serde_json::from_value(raw).unwrap()
+    }
+
+    #[test]
+    fn v6_quote_parses_and_validates() {
+        let quote: JupiterQuote = serde_json::from_value(v6_quote()).unwrap();
+        assert_eq!(quote.out_amount().unwrap(), 147_260_318);
+        assert_eq!(quote.min_out_amount().unwrap(), 146_524_016);
+        assert!((quote.price_impact() - 0.0012).abs() < 1e-12);
+        assert_eq!(quote.route_label(), "Whirlpool>Raydium CLMM");
+        assert_eq!(quote.route_plan.iter().map(|step| step.percent).collect::<Vec<_>>(), vec![60, 40]);
+        assert_eq!(quote.context_slot, Some(299_113_841));
+        quote.validate().unwrap();
+    }
+
+    #[test]
+    fn swap_request_posts_the_quote_back_unchanged() {
+        let quote: JupiterQuote = serde_json::from_value(v6_quote()).unwrap();
+        assert!(quote.extra.contains_key("platformFee") && quote.extra.contains_key("timeTaken"));
+        let user = Pubkey::new_unique();
+        let body = swap_request(&user, &quote);
+        assert_eq!(body["quoteResponse"], v6_quote());
+        assert_eq!(body["userPublicKey"], user.to_string());
+    }
+
+    #[test]
+    fn exact_out_and_loose_quotes_are_rejected() {
+        let err = quote_with("swapMode", json!("ExactOut")).validate().unwrap_err();
+        assert_eq!(err.to_string(), "swap mode ExactOut is not supported, only ExactIn");
+        assert!(quote_with("otherAmountThreshold", json!("0")).validate().is_err());
+        // 50 bps below 147260318 is 146524016.
+        assert!(quote_with("otherAmountThreshold", json!("146524016")).validate().is_ok());
+        assert!(quote_with("otherAmountThreshold", json!("146524015")).validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn usd_to_lamports_needs_a_sol_price() {
+        let sol_price = Arc::new(Mutex::new(0.0));
+        let jupiter = JupiterClient::new(Arc::new(StubRpc::default()), sol_price.clone());
+        assert!(jupiter.usd_to_lamports(10.0).await.is_err());
+        *sol_price.lock().await = 200.0;
+        assert_eq!(jupiter.usd_to_lamports(10.0).await.unwrap(), 50_000_000);
+    }
+}
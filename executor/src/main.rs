@@ -1,11 +1,27 @@
 // executor/src/main.rs
+mod algo;
+mod balances;
 mod config;
+mod confirmation;
 mod database;
+mod drift;
+mod drift_markets;
 mod executor;
+mod fees;
+mod jito;
 mod jupiter;
 mod portfolio_monitor; // P-6: New module for portfolio monitoring
+mod pretrade;
+mod retry;
+mod rpc;
+mod short_venue;
 mod signer_client;
+mod simulation;
+mod static_keys;
 mod strategies;
+mod streams;
+mod tips;
+mod venues;
 
 use crate::config::CONFIG;
 use anyhow::Result;
@@ -1,5 +1,4 @@
 use crate::{config::CONFIG, database::Database};
-use anyhow::Result;
 use std::{sync::Arc, time::Duration};
 use tracing::{error, info, warn};
 use redis::AsyncCommands;
@@ -20,41 +19,20 @@ pub async fn run_monitor(db: Arc<Database>, paused_flag: Arc<tokio::sync::Mutex<
 
         info!("PnL {:.2} USD | Peak {:.2} | DD {:.2}%", pnl,hwm,dd);
 
-        let mut conn = match client.get_async_connection().await {
+        let mut conn = match client.get_multiplexed_async_connection().await {
             Ok(c)=>c, Err(e)=>{ warn!("Redis err {e}"); continue }
         };
 
         if dd > CONFIG.portfolio_stop_loss_percent {
             if !*paused_flag.lock().await {
-                conn.publish("kill_switch_channel","PAUSE").await.ok();
+                conn.publish::<_, _, ()>("kill_switch_channel","PAUSE").await.ok();
                 *paused_flag.lock().await = true;
                 error!("🚨 Trading paused – draw-down {:.1}% > {:.1}%", dd,CONFIG.portfolio_stop_loss_percent);
             }
         } else if *paused_flag.lock().await && dd < CONFIG.portfolio_stop_loss_percent*0.8 {
-            conn.publish("kill_switch_channel","RESUME").await.ok();
+            conn.publish::<_, _, ()>("kill_switch_channel","RESUME").await.ok();
             *paused_flag.lock().await = false;
             info!("✅ Trading resumed – draw-down {:.1}%", dd);
         }
     }
-}
-                    // P-6: Publish to kill switch channel
-                    if let Err(e) = conn.publish("kill_switch_channel", "PAUSE").await {
-                        error!("Failed to publish PAUSE to kill_switch_channel: {}", e);
-                    }
-                } else {
-                    // If drawdown is recovered significantly, resume trading
-                    if drawdown_from_peak < CONFIG.portfolio_stop_loss_percent * 0.8 { // Resume if recovered significantly
-                        info!("✅ Portfolio recovered. Drawdown {:.2}% < Threshold {:.2}%. Resuming trading.",
-                            drawdown_from_peak, CONFIG.portfolio_stop_loss_percent * 0.8);
-                        if let Err(e) = conn.publish("kill_switch_channel", "RESUME").await {
-                            error!("Failed to publish RESUME to kill_switch_channel: {}", e);
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                error!("Portfolio Monitor: Failed to get total PnL from DB: {}", e);
-            }
-        }
-    }
 }
@@ -0,0 +1,222 @@
+// executor/src/pretrade.rs
+use crate::{config::CONFIG, venues::VenueQuote};
+use shared_models::{DepthEvent, Side};
+use std::{collections::HashMap, fmt, sync::Arc, time::{Duration, Instant}};
+use tokio::sync::Mutex;
+
+/// Latest `DepthEvent` per token, with when it arrived.
+#[derive(Clone, Default)]
+pub struct DepthBook {
+    inner: Arc<Mutex<HashMap<String, (Instant, DepthEvent)>>>,
+}
+
+impl DepthBook {
+    pub async fn update(&self, event: DepthEvent) {
+        self.inner.lock().await.insert(event.token_address.clone(), (Instant::now(), event));
+    }
+
+    /// The latest snapshot for `token`, unless it is older than `max_age`.
+    pub async fn latest(&self, token: &str, max_age: Duration) -> Option<DepthEvent> {
+        self.inner.lock().await.get(token)
+            .filter(|(at, _)| at.elapsed() <= max_age)
+            .map(|(_, event)| event.clone())
+    }
+}
+
+pub struct PreTradeLimits {
+    pub max_price_impact_pct: f64,
+    /// The book on the side the order takes must show at least this multiple of its size.
+    pub min_liquidity_multiple: f64,
+    pub max_spread_bps: f64,
+    /// Orders downsized below this are rejected instead.
+    pub min_size_usd: f64,
+    pub max_depth_age: Duration,
+}
+
+impl PreTradeLimits {
+    pub fn from_config() -> Self {
+        Self {
+            max_price_impact_pct: CONFIG.max_price_impact_pct,
+            min_liquidity_multiple: CONFIG.pretrade_min_liquidity_multiple,
+            max_spread_bps: CONFIG.pretrade_max_spread_bps,
+            min_size_usd: CONFIG.pretrade_min_size_usd,
+            max_depth_age: Duration::from_secs(CONFIG.depth_max_age_secs),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Accept,
+    /// Trade, but only this many USD.
+    Downsize(f64),
+    Reject(String),
+}
+
+impl Verdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::Accept => "ACCEPT",
+            Verdict::Downsize(_) => "DOWNSIZE",
+            Verdict::Reject(_) => "REJECT",
+        }
+    }
+}
+
+/// The verdict and the numbers it was based on, logged on the trade row.
+#[derive(Debug, Clone)]
+pub struct PreTradeCheck {
+    pub verdict: Verdict,
+    pub size_usd: f64,
+    pub price_impact_pct: f64,
+    /// Book size on the side the order takes; `None` without a fresh `DepthEvent` showing any.
+    pub liquidity_usd: Option<f64>,
+    /// `None` without a fresh `DepthEvent`.
+    pub spread_bps: Option<f64>,
+}
+
+/// A pre-trade check refused the order; never retried.
+#[derive(Debug)]
+pub struct PreTradeRejected(pub String);
+
+impl fmt::Display for PreTradeRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pre-trade check rejected order: {}", self.0)
+    }
+}
+
+impl std::error::Error for PreTradeRejected {}
+
+/// Checks a spot quote against impact, liquidity and spread limits.
+///
+/// Liquidity is what the latest `DepthEvent` shows on the side the order takes: the asks for a
+/// buy, the bids for a sell. Jupiter v6 quotes no longer report pool liquidity, and the quote's
+/// impact is already limited on its own. Impact and liquidity breaches shrink the order to the
+/// largest size that would pass; a wide spread cannot be traded around and rejects outright.
+pub fn evaluate(limits: &PreTradeLimits, quote: &VenueQuote, depth: Option<&DepthEvent>, side: &Side) -> PreTradeCheck {
+    let size_usd = quote.size_usd;
+    let price_impact_pct = quote.swap_quote.as_ref().map_or(0.0, |q| q.price_impact() * 100.0);
+    // An empty side says nothing about liquidity, so it does not cap the order.
+    let liquidity_usd = depth
+        .map(|d| match side { Side::Long => d.ask_size_usd, Side::Short => d.bid_size_usd })
+        .filter(|size| *size > 0.0);
+    let spread_bps = depth.and_then(|d| {
+        let mid = (d.bid_price + d.ask_price) / 2.0;
+        (mid > 0.0).then(|| (d.ask_price - d.bid_price) / mid * 10_000.0)
+    });
+    let check = |verdict| PreTradeCheck { verdict, size_usd, price_impact_pct, liquidity_usd, spread_bps };
+
+    if let Some(spread) = spread_bps {
+        if spread > limits.max_spread_bps {
+            return check(Verdict::Reject(format!("spread {:.0} bps exceeds {:.0} bps", spread, limits.max_spread_bps)));
+        }
+    }
+
+    let mut max_size = size_usd;
+    if price_impact_pct > limits.max_price_impact_pct {
+        max_size = max_size.min(size_usd * limits.max_price_impact_pct / price_impact_pct);
+    }
+    if let Some(liquidity) = liquidity_usd {
+        max_size = max_size.min(liquidity / limits.min_liquidity_multiple);
+    }
+
+    if max_size >= size_usd {
+        check(Verdict::Accept)
+    } else if max_size < limits.min_size_usd {
+        check(Verdict::Reject(format!(
+            "only ${:.2} of ${:.2} passes (impact {:.2}%, liquidity {}), below ${:.2} minimum",
+            max_size,
+            size_usd,
+            price_impact_pct,
+            liquidity_usd.map_or("n/a".to_string(), |l| format!("${:.0}", l)),
+            limits.min_size_usd,
+        )))
+    } else {
+        check(Verdict::Downsize(max_size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(min_liquidity_multiple: f64) -> PreTradeLimits {
+        PreTradeLimits {
+            max_price_impact_pct: 1.0,
+            min_liquidity_multiple,
+            max_spread_bps: 50.0,
+            min_size_usd: 5.0,
+            max_depth_age: Duration::from_secs(30),
+        }
+    }
+
+    fn quote(size_usd: f64, impact_pct: f64) -> VenueQuote {
+        let swap_quote = serde_json::from_value(serde_json::json!({
+            "inputMint": "So11111111111111111111111111111111111111112",
+            "inAmount": "1000000000",
+            "outputMint": "token",
+            "outAmount": "1000000",
+            "otherAmountThreshold": "995000",
+            "swapMode": "ExactIn",
+            "slippageBps": 50,
+            "priceImpactPct": (impact_pct / 100.0).to_string(),
+            "routePlan": [],
+        })).unwrap();
+        VenueQuote { price_usd: 1.0, size_usd, amount_raw: 1_000_000, route: None, swap_quote: Some(swap_quote) }
+    }
+
+    fn depth(bid_price: f64, ask_price: f64, bid_size_usd: f64, ask_size_usd: f64) -> DepthEvent {
+        DepthEvent { token_address: "token".to_string(), bid_price, ask_price, bid_size_usd, ask_size_usd }
+    }
+
+    #[test]
+    fn evaluate_verdicts() {
+        struct Case {
+            name: &'static str,
+            size_usd: f64,
+            impact_pct: f64,
+            liquidity_multiple: f64,
+            depth: Option<DepthEvent>,
+            side: Side,
+            expected: Verdict,
+        }
+        let case = |name, size_usd, impact_pct, depth, side, expected| Case { name, size_usd, impact_pct, liquidity_multiple: 10.0, depth, side, expected };
+        let cases = [
+            case("no impact, no book", 100.0, 0.0, None, Side::Long, Verdict::Accept),
+            case("impact at the limit", 100.0, 1.0, None, Side::Long, Verdict::Accept),
+            case("impact over the limit downsizes", 100.0, 2.0, None, Side::Long, Verdict::Downsize(50.0)),
+            case("downsize exactly to the minimum", 100.0, 20.0, None, Side::Long, Verdict::Downsize(5.0)),
+            case("downsize below the minimum", 100.0, 40.0, None, Side::Long, Verdict::Reject(String::new())),
+            case("high impact on a deep book only downsizes for impact", 100.0, 2.0, Some(depth(99.9, 100.1, 1e6, 1e6)), Side::Long, Verdict::Downsize(50.0)),
+            case("narrow spread", 100.0, 0.0, Some(depth(99.9, 100.1, 1e6, 1e6)), Side::Long, Verdict::Accept),
+            case("wide spread rejects", 100.0, 0.0, Some(depth(99.0, 101.0, 1e6, 1e6)), Side::Long, Verdict::Reject(String::new())),
+            case("asks at the multiple", 100.0, 0.0, Some(depth(99.9, 100.1, 1e6, 1_000.0)), Side::Long, Verdict::Accept),
+            case("buy capped by the asks", 100.0, 0.0, Some(depth(99.9, 100.1, 1e6, 400.0)), Side::Long, Verdict::Downsize(40.0)),
+            case("sell capped by the bids", 100.0, 0.0, Some(depth(99.9, 100.1, 400.0, 1e6)), Side::Short, Verdict::Downsize(40.0)),
+            case("sell ignores the asks", 100.0, 0.0, Some(depth(99.9, 100.1, 1e6, 400.0)), Side::Short, Verdict::Accept),
+            case("thin book with no impact still downsizes", 100.0, 0.0, Some(depth(99.9, 100.1, 1e6, 600.0)), Side::Long, Verdict::Downsize(60.0)),
+            Case { liquidity_multiple: 1.0, ..case("multiple of one caps at the book", 100.0, 0.0, Some(depth(99.9, 100.1, 1e6, 40.0)), Side::Long, Verdict::Downsize(40.0)) },
+            case("empty book side is not a cap", 100.0, 0.0, Some(depth(99.9, 100.1, 1e6, 0.0)), Side::Long, Verdict::Accept),
+            case("book below the minimum", 100.0, 0.0, Some(depth(99.9, 100.1, 1e6, 40.0)), Side::Long, Verdict::Reject(String::new())),
+        ];
+        for c in cases {
+            let check = evaluate(&limits(c.liquidity_multiple), &quote(c.size_usd, c.impact_pct), c.depth.as_ref(), &c.side);
+            match (&check.verdict, &c.expected) {
+                (Verdict::Downsize(got), Verdict::Downsize(want)) => assert!((got - want).abs() < 1e-6, "{}: downsized to {}", c.name, got),
+                (got, want) => assert_eq!(got.as_str(), want.as_str(), "{}: {:?}", c.name, check),
+            }
+        }
+    }
+
+    #[test]
+    fn evaluate_reports_its_inputs() {
+        let check = evaluate(&limits(10.0), &quote(100.0, 2.0), Some(&depth(99.9, 100.1, 3_000.0, 5_000.0)), &Side::Long);
+        assert!((check.price_impact_pct - 2.0).abs() < 1e-9);
+        assert_eq!(check.liquidity_usd, Some(5_000.0), "a buy takes the asks");
+        assert!((check.spread_bps.unwrap() - 20.0).abs() < 1e-6);
+        let check = evaluate(&limits(10.0), &quote(100.0, 2.0), Some(&depth(99.9, 100.1, 3_000.0, 5_000.0)), &Side::Short);
+        assert_eq!(check.liquidity_usd, Some(3_000.0), "a sell takes the bids");
+        let check = evaluate(&limits(10.0), &quote(100.0, 2.0), None, &Side::Long);
+        assert_eq!((check.liquidity_usd, check.spread_bps), (None, None), "impact alone implies no liquidity");
+    }
+}
@@ -0,0 +1,267 @@
+// executor/src/retry.rs
+use crate::{config::CONFIG, jupiter::JupiterHttpError, pretrade::PreTradeRejected, signer_client::SignerError, simulation::SimulationError};
+use std::{fmt, time::Duration};
+
+/// Terminal outcomes of a submitted transaction, kept typed so the retry policy can tell them apart.
+#[derive(Debug)]
+pub enum TxError {
+    /// The blockhash expired before the transaction landed; it can never land now.
+    Expired(String),
+    /// Landed and failed on-chain.
+    Failed { signature: String, reason: String },
+    /// Still unknown at timeout. It may yet land, so it must not be resubmitted.
+    Unconfirmed(String),
+    /// Landed as a resting order that left the book with `remaining_raw` unfilled: the price ran
+    /// past its limit.
+    Unfilled { signature: String, remaining_raw: u64 },
+}
+
+impl fmt::Display for TxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxError::Expired(sig) => write!(f, "transaction {} expired without landing", sig),
+            TxError::Failed { signature, reason } => write!(f, "transaction {} failed: {}", signature, reason),
+            TxError::Unconfirmed(sig) => write!(f, "transaction {} unconfirmed at timeout", sig),
+            TxError::Unfilled { signature, remaining_raw } => write!(f, "order {} left {} unfilled", signature, remaining_raw),
+        }
+    }
+}
+
+impl std::error::Error for TxError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FailureClass {
+    BlockhashExpired,
+    SlippageExceeded,
+    /// The signer could not be reached or its circuit is open. Nothing was signed, so nothing was sent.
+    SignerTimeout,
+    /// The signer answered and refused; retrying will not help.
+    SignerRejected,
+    RouteNotFound,
+    /// The venue's API rate limited us or failed server-side.
+    VenueUnavailable,
+    Unconfirmed,
+    PreTradeRejected,
+    SimulationFailed,
+    Other,
+}
+
+impl FailureClass {
+    pub fn classify(err: &anyhow::Error) -> Self {
+        if let Some(tx_err) = err.downcast_ref::<TxError>() {
+            return match tx_err {
+                TxError::Expired(_) => FailureClass::BlockhashExpired,
+                TxError::Unconfirmed(_) => FailureClass::Unconfirmed,
+                TxError::Unfilled { .. } => FailureClass::SlippageExceeded,
+                // Jupiter's SlippageToleranceExceeded is custom program error 6001 (0x1771).
+                TxError::Failed { reason, .. } if reason.contains("0x1771") || reason.contains("SlippageToleranceExceeded") => {
+                    FailureClass::SlippageExceeded
+                }
+                TxError::Failed { .. } => FailureClass::Other,
+            };
+        }
+        if let Some(sim_err) = err.downcast_ref::<SimulationError>() {
+            return match sim_err {
+                // A fresh quote may clear either; both mean the price moved.
+                SimulationError::BelowMinimum { .. } => FailureClass::SlippageExceeded,
+                SimulationError::Failed { reason, .. } if reason.contains("0x1771") || reason.contains("SlippageToleranceExceeded") => {
+                    FailureClass::SlippageExceeded
+                }
+                SimulationError::Failed { .. } => FailureClass::SimulationFailed,
+            };
+        }
+        if let Some(signer_err) = err.downcast_ref::<SignerError>() {
+            return match signer_err {
+                SignerError::Unavailable(_) | SignerError::CircuitOpen { .. } => FailureClass::SignerTimeout,
+                _ => FailureClass::SignerRejected,
+            };
+        }
+        if let Some(http_err) = err.downcast_ref::<JupiterHttpError>() {
+            return if http_err.is_retryable() {
+                FailureClass::VenueUnavailable
+            } else if http_err.is_no_route() {
+                FailureClass::RouteNotFound
+            } else {
+                FailureClass::Other
+            };
+        }
+        if err.downcast_ref::<PreTradeRejected>().is_some() {
+            return FailureClass::PreTradeRejected;
+        }
+        // Any other transport error (Jupiter /swap, Jito, RPC send) may have come after the
+        // transaction went out, so it falls through to `Other` and is never resent blindly.
+        let msg = err.to_string();
+        if msg.contains("No route found") || msg.contains("COULD_NOT_FIND_ANY_ROUTE") {
+            FailureClass::RouteNotFound
+        } else if msg.contains("BlockhashNotFound") || msg.contains("Blockhash not found") {
+            FailureClass::BlockhashExpired
+        } else {
+            FailureClass::Other
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureClass::BlockhashExpired => "blockhash_expired",
+            FailureClass::SlippageExceeded => "slippage_exceeded",
+            FailureClass::SignerTimeout => "signer_timeout",
+            FailureClass::SignerRejected => "signer_rejected",
+            FailureClass::RouteNotFound => "route_not_found",
+            FailureClass::VenueUnavailable => "venue_unavailable",
+            FailureClass::Unconfirmed => "unconfirmed",
+            FailureClass::PreTradeRejected => "pretrade_rejected",
+            FailureClass::SimulationFailed => "simulation_failed",
+            FailureClass::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// Build a fresh transaction with a new blockhash.
+    Rebuild,
+    /// Quote again with a wider slippage tolerance.
+    Requote { slippage_bps: u16 },
+    /// Same request again after a backoff.
+    Retry,
+    GiveUp,
+}
+
+pub struct RetryPolicy {
+    pub max_rebuilds: u32,
+    pub max_requotes: u32,
+    pub max_signer_retries: u32,
+    pub max_venue_retries: u32,
+    pub slippage_step_bps: u16,
+    pub max_slippage_bps: u16,
+    pub backoff: Duration,
+    /// Orders older than this since the strategy signalled are abandoned.
+    pub max_signal_age: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config() -> Self {
+        Self {
+            max_rebuilds: CONFIG.retry_max_rebuilds,
+            max_requotes: CONFIG.retry_max_requotes,
+            max_signer_retries: CONFIG.retry_max_signer_retries,
+            max_venue_retries: CONFIG.retry_max_venue_retries,
+            slippage_step_bps: CONFIG.retry_slippage_step_bps,
+            max_slippage_bps: CONFIG.retry_max_slippage_bps,
+            backoff: Duration::from_millis(CONFIG.retry_backoff_ms),
+            max_signal_age: Duration::from_secs(CONFIG.max_signal_age_secs),
+        }
+    }
+
+    /// `retries` is how many times this class has already been retried for the order.
+    pub fn decide(&self, class: FailureClass, retries: u32, slippage_bps: u16) -> RetryDecision {
+        match class {
+            FailureClass::BlockhashExpired if retries < self.max_rebuilds => RetryDecision::Rebuild,
+            FailureClass::SlippageExceeded if retries < self.max_requotes && slippage_bps < self.max_slippage_bps => {
+                RetryDecision::Requote { slippage_bps: (slippage_bps + self.slippage_step_bps).min(self.max_slippage_bps) }
+            }
+            FailureClass::SignerTimeout if retries < self.max_signer_retries => RetryDecision::Retry,
+            FailureClass::VenueUnavailable if retries < self.max_venue_retries => RetryDecision::Retry,
+            _ => RetryDecision::GiveUp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    fn jupiter(status: u16, body: &str) -> anyhow::Error {
+        anyhow::Error::new(JupiterHttpError { endpoint: "quote", status: StatusCode::from_u16(status).unwrap(), body: body.to_string() })
+            .context("Jupiter quote A -> B")
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_rebuilds: 2,
+            max_requotes: 2,
+            max_signer_retries: 3,
+            max_venue_retries: 1,
+            slippage_step_bps: 50,
+            max_slippage_bps: 200,
+            backoff: Duration::ZERO,
+            max_signal_age: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn classify_maps_every_failure() {
+        let failed = |reason: &str| anyhow::Error::new(TxError::Failed { signature: "sig".to_string(), reason: reason.to_string() });
+        let cases: Vec<(anyhow::Error, FailureClass)> = vec![
+            (TxError::Expired("sig".to_string()).into(), FailureClass::BlockhashExpired),
+            (TxError::Unconfirmed("sig".to_string()).into(), FailureClass::Unconfirmed),
+            (TxError::Unfilled { signature: "sig".to_string(), remaining_raw: 1 }.into(), FailureClass::SlippageExceeded),
+            (failed("custom program error: 0x1771"), FailureClass::SlippageExceeded),
+            (failed("SlippageToleranceExceeded"), FailureClass::SlippageExceeded),
+            (failed("custom program error: 0x1"), FailureClass::Other),
+            (SimulationError::BelowMinimum { mint: "m".to_string(), simulated: 1, minimum: 2 }.into(), FailureClass::SlippageExceeded),
+            (SimulationError::Failed { reason: "0x1771".to_string(), units_consumed: None }.into(), FailureClass::SlippageExceeded),
+            (SimulationError::Failed { reason: "insufficient funds".to_string(), units_consumed: None }.into(), FailureClass::SimulationFailed),
+            (SignerError::Unavailable("timeout".to_string()).into(), FailureClass::SignerTimeout),
+            (SignerError::PolicyRejected(vec![]).into(), FailureClass::SignerRejected),
+            (SignerError::CircuitOpen { retry_in: Duration::from_secs(1) }.into(), FailureClass::SignerTimeout),
+            (PreTradeRejected("too thin".to_string()).into(), FailureClass::PreTradeRejected),
+            (jupiter(429, "Too Many Requests"), FailureClass::VenueUnavailable),
+            (jupiter(503, ""), FailureClass::VenueUnavailable),
+            (jupiter(400, r#"{"errorCode":"COULD_NOT_FIND_ANY_ROUTE"}"#), FailureClass::RouteNotFound),
+            (jupiter(400, r#"{"errorCode":"TOKEN_NOT_TRADABLE"}"#), FailureClass::Other),
+            (anyhow::anyhow!("RPC response error: Blockhash not found"), FailureClass::BlockhashExpired),
+            (anyhow::anyhow!("something else"), FailureClass::Other),
+        ];
+        for (err, expected) in cases {
+            assert_eq!(FailureClass::classify(&err), expected, "{:#}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn transport_errors_are_not_retried() {
+        // Nothing listens on port 1: a connect error, as from a Jito or RPC send that timed out.
+        let err = reqwest::get("http://127.0.0.1:1").await.unwrap_err();
+        assert!(err.is_connect());
+        let err = anyhow::Error::new(err).context("sendTransaction");
+        assert_eq!(FailureClass::classify(&err), FailureClass::Other);
+        assert_eq!(policy().decide(FailureClass::classify(&err), 0, 50), RetryDecision::GiveUp);
+    }
+
+    #[test]
+    fn decide_retries_each_class_up_to_its_limit() {
+        let policy = policy();
+        assert_eq!(policy.decide(FailureClass::BlockhashExpired, 0, 50), RetryDecision::Rebuild);
+        assert_eq!(policy.decide(FailureClass::BlockhashExpired, 1, 50), RetryDecision::Rebuild);
+        assert_eq!(policy.decide(FailureClass::BlockhashExpired, 2, 50), RetryDecision::GiveUp);
+        assert_eq!(policy.decide(FailureClass::SignerTimeout, 2, 50), RetryDecision::Retry);
+        assert_eq!(policy.decide(FailureClass::SignerTimeout, 3, 50), RetryDecision::GiveUp);
+        assert_eq!(policy.decide(FailureClass::VenueUnavailable, 0, 50), RetryDecision::Retry);
+        assert_eq!(policy.decide(FailureClass::VenueUnavailable, 1, 50), RetryDecision::GiveUp);
+    }
+
+    #[test]
+    fn decide_widens_slippage_up_to_the_cap() {
+        let policy = policy();
+        assert_eq!(policy.decide(FailureClass::SlippageExceeded, 0, 50), RetryDecision::Requote { slippage_bps: 100 });
+        assert_eq!(policy.decide(FailureClass::SlippageExceeded, 1, 180), RetryDecision::Requote { slippage_bps: 200 });
+        assert_eq!(policy.decide(FailureClass::SlippageExceeded, 1, 200), RetryDecision::GiveUp);
+        assert_eq!(policy.decide(FailureClass::SlippageExceeded, 2, 50), RetryDecision::GiveUp);
+    }
+
+    #[test]
+    fn decide_never_retries_terminal_classes() {
+        let policy = policy();
+        for class in [
+            FailureClass::SignerRejected,
+            FailureClass::RouteNotFound,
+            FailureClass::Unconfirmed,
+            FailureClass::PreTradeRejected,
+            FailureClass::SimulationFailed,
+            FailureClass::Other,
+        ] {
+            assert_eq!(policy.decide(class, 0, 50), RetryDecision::GiveUp, "{:?}", class);
+        }
+    }
+}
@@ -0,0 +1,394 @@
+// executor/src/rpc.rs
+use crate::simulation::{SimulatedAccount, Simulation};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use solana_account_decoder::{UiAccountData, UiAccountEncoding};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig, RpcTransactionConfig},
+    rpc_request::{RpcError, RpcRequest, TokenAccountsFilter},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, hash::Hash, pubkey::Pubkey, signature::Signature, transaction::VersionedTransaction};
+use solana_transaction_status::{option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use std::{collections::HashMap, str::FromStr, sync::{atomic::{AtomicBool, Ordering}, Mutex}};
+
+pub const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+/// One SPL token account owned by the wallet.
+#[derive(Debug, Clone)]
+pub struct TokenHolding {
+    pub mint: String,
+    pub amount_raw: u64,
+}
+
+/// Balance changes of a landed transaction, seen from the fee payer (our wallet).
+#[derive(Debug, Clone, Default)]
+pub struct TxMeta {
+    pub err: Option<String>,
+    pub fee_lamports: u64,
+    /// Post minus pre lamports of the fee payer, fee and tips included.
+    pub sol_delta_lamports: i64,
+    /// Post minus pre raw token balance per mint, for accounts owned by the fee payer.
+    pub token_deltas: HashMap<String, i128>,
+    pub token_decimals: HashMap<String, u8>,
+}
+
+/// The slice of Solana RPC the executor depends on, so a local stub can stand in for a node.
+#[async_trait]
+pub trait SolanaRpc: Send + Sync {
+    /// `None` while the cluster has not seen the signature; `Some(Err)` if it landed and failed.
+    async fn signature_status(&self, signature: &Signature) -> Result<Option<Result<(), String>>>;
+    async fn transaction_meta(&self, signature: &Signature) -> Result<Option<TxMeta>>;
+    async fn is_blockhash_valid(&self, blockhash: &Hash) -> Result<bool>;
+    async fn token_decimals(&self, mint: &Pubkey) -> Result<u8>;
+    async fn latest_blockhash(&self) -> Result<Hash>;
+    /// Sends a signed transaction as-is, without preflight.
+    async fn send_transaction(&self, tx: &VersionedTransaction) -> Result<Signature>;
+    /// Recent per-slot prioritization fees (micro-lamports per CU) for transactions locking `accounts`.
+    async fn recent_prioritization_fees(&self, accounts: &[Pubkey]) -> Result<Vec<u64>>;
+    /// Simulates without signature checks against a fresh blockhash, returning the post state of `accounts`.
+    async fn simulate_transaction(&self, tx: &VersionedTransaction, accounts: &[Pubkey]) -> Result<Simulation>;
+    /// Raw balance of an SPL token account; zero if it does not exist yet.
+    async fn token_balance(&self, account: &Pubkey) -> Result<u64>;
+    async fn lamports(&self, account: &Pubkey) -> Result<u64>;
+    /// All token accounts of `owner` under both token programs.
+    async fn token_accounts(&self, owner: &Pubkey) -> Result<Vec<TokenHolding>>;
+    /// Raw data of `account`; `None` if it does not exist.
+    async fn account_data(&self, account: &Pubkey) -> Result<Option<Vec<u8>>>;
+    /// Program owning `account`, e.g. which token program a mint belongs to; `None` if it does not exist.
+    async fn account_owner(&self, account: &Pubkey) -> Result<Option<Pubkey>>;
+}
+
+/// `SolanaRpc` over a real node via `solana-client`.
+pub struct NodeRpc {
+    client: RpcClient,
+}
+
+impl NodeRpc {
+    pub fn new(url: String) -> Self {
+        Self { client: RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()) }
+    }
+}
+
+#[async_trait]
+impl SolanaRpc for NodeRpc {
+    async fn signature_status(&self, signature: &Signature) -> Result<Option<Result<(), String>>> {
+        Ok(self.client.get_signature_status(signature).await?
+            .map(|status| status.map_err(|e| e.to_string())))
+    }
+
+    async fn transaction_meta(&self, signature: &Signature) -> Result<Option<TxMeta>> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        // `get_transaction_with_config` cannot tell a null result (not indexed yet) from a failed request.
+        let params = serde_json::json!([signature.to_string(), config]);
+        let tx: Option<EncodedConfirmedTransactionWithStatusMeta> = self.client.send(RpcRequest::GetTransaction, params).await?;
+        let Some(tx) = tx else { return Ok(None) };
+        let meta = tx.transaction.meta.ok_or_else(|| anyhow!("transaction {} has no meta", signature))?;
+
+        // Account 0 is always the fee payer, which is our wallet.
+        let sol_delta_lamports = meta.post_balances.first().copied().unwrap_or(0) as i64
+            - meta.pre_balances.first().copied().unwrap_or(0) as i64;
+        let owner = match tx.transaction.transaction.decode() {
+            Some(decoded) => decoded.message.static_account_keys().first().map(|k| k.to_string()),
+            None => None,
+        };
+
+        let mut token_deltas: HashMap<String, i128> = HashMap::new();
+        let mut token_decimals = HashMap::new();
+        let balances = |b: OptionSerializer<Vec<solana_transaction_status::UiTransactionTokenBalance>>| -> Vec<_> {
+            match b { OptionSerializer::Some(v) => v, _ => vec![] }
+        };
+        for (sign, list) in [(-1i128, balances(meta.pre_token_balances)), (1i128, balances(meta.post_token_balances))] {
+            for balance in list {
+                let balance_owner: Option<String> = balance.owner.into();
+                if balance_owner != owner { continue; }
+                let amount: i128 = balance.ui_token_amount.amount.parse().unwrap_or(0);
+                *token_deltas.entry(balance.mint.clone()).or_default() += sign * amount;
+                token_decimals.insert(balance.mint, balance.ui_token_amount.decimals);
+            }
+        }
+
+        Ok(Some(TxMeta {
+            err: meta.err.map(|e| e.to_string()),
+            fee_lamports: meta.fee,
+            sol_delta_lamports,
+            token_deltas,
+            token_decimals,
+        }))
+    }
+
+    async fn is_blockhash_valid(&self, blockhash: &Hash) -> Result<bool> {
+        Ok(self.client.is_blockhash_valid(blockhash, CommitmentConfig::processed()).await?)
+    }
+
+    async fn token_decimals(&self, mint: &Pubkey) -> Result<u8> {
+        Ok(self.client.get_token_supply(mint).await?.decimals)
+    }
+
+    async fn latest_blockhash(&self) -> Result<Hash> {
+        Ok(self.client.get_latest_blockhash().await?)
+    }
+
+    async fn send_transaction(&self, tx: &VersionedTransaction) -> Result<Signature> {
+        let config = RpcSendTransactionConfig { skip_preflight: true, ..Default::default() };
+        Ok(self.client.send_transaction_with_config(tx, config).await?)
+    }
+
+    async fn recent_prioritization_fees(&self, accounts: &[Pubkey]) -> Result<Vec<u64>> {
+        Ok(self.client.get_recent_prioritization_fees(accounts).await?
+            .into_iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect())
+    }
+
+    async fn simulate_transaction(&self, tx: &VersionedTransaction, accounts: &[Pubkey]) -> Result<Simulation> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(CommitmentConfig::processed()),
+            accounts: (!accounts.is_empty()).then(|| RpcSimulateTransactionAccountsConfig {
+                encoding: Some(UiAccountEncoding::JsonParsed),
+                addresses: accounts.iter().map(|a| a.to_string()).collect(),
+            }),
+            ..Default::default()
+        };
+        let result = self.client.simulate_transaction_with_config(tx, config).await?.value;
+        let accounts = result.accounts.unwrap_or_default().into_iter()
+            .map(|account| account.map(|a| SimulatedAccount {
+                lamports: a.lamports,
+                token_amount: match a.data {
+                    UiAccountData::Json(parsed) => parsed.parsed["info"]["tokenAmount"]["amount"].as_str().and_then(|s| s.parse().ok()),
+                    _ => None,
+                },
+            }))
+            .collect();
+        Ok(Simulation {
+            err: result.err.map(|e| e.to_string()),
+            logs: result.logs.unwrap_or_default(),
+            units_consumed: result.units_consumed,
+            accounts,
+        })
+    }
+
+    async fn token_balance(&self, account: &Pubkey) -> Result<u64> {
+        match self.client.get_token_account_balance(account).await {
+            Ok(balance) => Ok(balance.amount.parse()?),
+            Err(e) if is_account_not_found(&e) => Ok(0), // account not created yet
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn lamports(&self, account: &Pubkey) -> Result<u64> {
+        Ok(self.client.get_balance(account).await?)
+    }
+
+    async fn token_accounts(&self, owner: &Pubkey) -> Result<Vec<TokenHolding>> {
+        let mut holdings = Vec::new();
+        for program in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID] {
+            let filter = TokenAccountsFilter::ProgramId(Pubkey::from_str(program)?);
+            for keyed in self.client.get_token_accounts_by_owner(owner, filter).await? {
+                let UiAccountData::Json(parsed) = keyed.account.data else { continue };
+                let info = &parsed.parsed["info"];
+                let (Some(mint), Some(amount)) = (info["mint"].as_str(), info["tokenAmount"]["amount"].as_str()) else { continue };
+                holdings.push(TokenHolding {
+                    mint: mint.to_string(),
+                    amount_raw: amount.parse()?,
+                });
+            }
+        }
+        Ok(holdings)
+    }
+
+    async fn account_data(&self, account: &Pubkey) -> Result<Option<Vec<u8>>> {
+        let response = self.client.get_account_with_commitment(account, CommitmentConfig::confirmed()).await?;
+        Ok(response.value.map(|a| a.data))
+    }
+
+    async fn account_owner(&self, account: &Pubkey) -> Result<Option<Pubkey>> {
+        let response = self.client.get_account_with_commitment(account, CommitmentConfig::confirmed()).await?;
+        Ok(response.value.map(|a| a.owner))
+    }
+}
+
+/// The node's answer for a token account that does not exist, as opposed to a failed request.
+fn is_account_not_found(err: &ClientError) -> bool {
+    matches!(err.kind(), ClientErrorKind::RpcError(RpcError::RpcResponseError { message, .. }) if message.contains("could not find account"))
+}
+
+/// How `StubRpc` settles the transactions sent through it.
+#[derive(Debug, Clone, Default)]
+pub enum StubOutcome {
+    #[default]
+    Confirm,
+    /// Lands and fails on-chain with this error.
+    #[cfg(test)]
+    Fail(String),
+    /// Never reaches the cluster.
+    #[cfg(test)]
+    Drop,
+}
+
+/// In-memory `SolanaRpc` for offline runs: sent transactions settle with the configured outcome,
+/// seeded signatures with the meta they were seeded with.
+#[derive(Default)]
+pub struct StubRpc {
+    metas: Mutex<HashMap<Signature, TxMeta>>,
+    outcome: Mutex<StubOutcome>,
+    blockhashes_expired: AtomicBool,
+    owners: Mutex<HashMap<Pubkey, Pubkey>>,
+    simulation: Mutex<Option<Simulation>>,
+    accounts: Mutex<HashMap<Pubkey, Vec<u8>>>,
+}
+
+#[cfg(test)]
+impl StubRpc {
+    pub fn seed(&self, signature: Signature, meta: TxMeta) {
+        self.metas.lock().unwrap().insert(signature, meta);
+    }
+
+    /// How transactions sent from now on settle.
+    pub fn set_outcome(&self, outcome: StubOutcome) {
+        *self.outcome.lock().unwrap() = outcome;
+    }
+
+    /// Reports every blockhash as expired, so dropped transactions read as `Expired`.
+    pub fn expire_blockhashes(&self, expired: bool) {
+        self.blockhashes_expired.store(expired, Ordering::Relaxed);
+    }
+
+    /// Makes `account_owner` return `owner` for `account`.
+    pub fn seed_owner(&self, account: Pubkey, owner: Pubkey) {
+        self.owners.lock().unwrap().insert(account, owner);
+    }
+
+    /// What `simulate_transaction` returns from now on.
+    pub fn set_simulation(&self, simulation: Simulation) {
+        *self.simulation.lock().unwrap() = Some(simulation);
+    }
+
+    /// Makes `account_data` return `data` for `account`.
+    pub fn seed_account(&self, account: Pubkey, data: Vec<u8>) {
+        self.accounts.lock().unwrap().insert(account, data);
+    }
+}
+
+#[async_trait]
+impl SolanaRpc for StubRpc {
+    async fn signature_status(&self, signature: &Signature) -> Result<Option<Result<(), String>>> {
+        Ok(self.metas.lock().unwrap().get(signature).map(|meta| match &meta.err {
+            Some(err) => Err(err.clone()),
+            None => Ok(()),
+        }))
+    }
+
+    async fn transaction_meta(&self, signature: &Signature) -> Result<Option<TxMeta>> {
+        Ok(self.metas.lock().unwrap().get(signature).cloned())
+    }
+
+    async fn is_blockhash_valid(&self, _blockhash: &Hash) -> Result<bool> {
+        Ok(!self.blockhashes_expired.load(Ordering::Relaxed))
+    }
+
+    async fn token_decimals(&self, _mint: &Pubkey) -> Result<u8> {
+        Ok(6) // pump.fun-style memecoins
+    }
+
+    async fn latest_blockhash(&self) -> Result<Hash> {
+        Ok(Hash::new_unique())
+    }
+
+    async fn send_transaction(&self, tx: &VersionedTransaction) -> Result<Signature> {
+        let signature = tx.signatures.first().copied().unwrap_or_default();
+        let err = match self.outcome.lock().unwrap().clone() {
+            StubOutcome::Confirm => None,
+            #[cfg(test)]
+            StubOutcome::Fail(err) => Some(err),
+            #[cfg(test)]
+            StubOutcome::Drop => return Ok(signature),
+        };
+        self.metas.lock().unwrap().entry(signature).or_insert(TxMeta { err, ..Default::default() });
+        Ok(signature)
+    }
+
+    async fn recent_prioritization_fees(&self, _accounts: &[Pubkey]) -> Result<Vec<u64>> {
+        Ok(vec![])
+    }
+
+    /// Returns the simulation set with `set_simulation`. Otherwise it always succeeds but reports no
+    /// account state, so output checks fail against it; mock venues do not simulate.
+    async fn simulate_transaction(&self, _tx: &VersionedTransaction, accounts: &[Pubkey]) -> Result<Simulation> {
+        if let Some(simulation) = self.simulation.lock().unwrap().clone() {
+            return Ok(simulation);
+        }
+        Ok(Simulation { units_consumed: Some(0), accounts: vec![None; accounts.len()], ..Default::default() })
+    }
+
+    async fn token_balance(&self, _account: &Pubkey) -> Result<u64> {
+        Ok(0)
+    }
+
+    /// Offline runs have an effectively unlimited wallet.
+    async fn lamports(&self, _account: &Pubkey) -> Result<u64> {
+        Ok(1_000 * 1_000_000_000)
+    }
+
+    async fn token_accounts(&self, _owner: &Pubkey) -> Result<Vec<TokenHolding>> {
+        Ok(vec![])
+    }
+
+    async fn account_data(&self, account: &Pubkey) -> Result<Option<Vec<u8>>> {
+        Ok(self.accounts.lock().unwrap().get(account).cloned())
+    }
+
+    async fn account_owner(&self, account: &Pubkey) -> Result<Option<Pubkey>> {
+        Ok(self.owners.lock().unwrap().get(account).copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{message::{v0, VersionedMessage}, signature::Keypair, signer::Signer};
+
+    fn signed_tx() -> VersionedTransaction {
+        let payer = Keypair::new();
+        let message = v0::Message::try_compile(&payer.pubkey(), &[], &[], Hash::new_unique()).unwrap();
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap()
+    }
+
+    #[test]
+    fn token_program_ids_are_pubkeys() {
+        for id in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID] {
+            assert!(Pubkey::from_str(id).is_ok(), "{} is not a pubkey", id);
+        }
+    }
+
+    #[tokio::test]
+    async fn sent_transactions_settle_with_configured_outcome() {
+        let rpc = StubRpc::default();
+        let confirmed = rpc.send_transaction(&signed_tx()).await.unwrap();
+        rpc.set_outcome(StubOutcome::Fail("custom program error: 0x1771".to_string()));
+        let failed = rpc.send_transaction(&signed_tx()).await.unwrap();
+        rpc.set_outcome(StubOutcome::Drop);
+        let dropped = rpc.send_transaction(&signed_tx()).await.unwrap();
+
+        assert_eq!(rpc.signature_status(&confirmed).await.unwrap(), Some(Ok(())));
+        assert_eq!(rpc.signature_status(&failed).await.unwrap(), Some(Err("custom program error: 0x1771".to_string())));
+        assert_eq!(rpc.signature_status(&dropped).await.unwrap(), None);
+        assert!(rpc.transaction_meta(&dropped).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn blockhash_expiry_is_configurable() {
+        let rpc = StubRpc::default();
+        assert!(rpc.is_blockhash_valid(&Hash::new_unique()).await.unwrap());
+        rpc.expire_blockhashes(true);
+        assert!(!rpc.is_blockhash_valid(&Hash::new_unique()).await.unwrap());
+    }
+}
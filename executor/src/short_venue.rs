@@ -0,0 +1,229 @@
+// executor/src/short_venue.rs
+use crate::{balances::BalanceTracker, config::CONFIG, database::Database, jupiter::{JupiterClient, JupiterQuote}};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use solana_sdk::pubkey::Pubkey;
+use std::{sync::Arc, time::Duration};
+use tracing::info;
+
+/// Unsigned transactions to sign and send in order.
+pub struct ShortPlan {
+    pub transactions_b64: Vec<String>,
+    /// Which of the transactions is the Jupiter swap.
+    pub swap_index: usize,
+}
+
+/// A way to get short exposure on a token that has no Drift perp.
+#[async_trait]
+pub trait ShortVenue: Send + Sync {
+    /// Recorded on the trade row as `short_venue`.
+    fn name(&self) -> &'static str;
+    /// Raw token units this venue can sell right now for the wallet of `signer_key`, capped at
+    /// `wanted_raw`. Zero means unavailable.
+    async fn capacity(&self, signer_key: &str, token: &str, wanted_raw: u64) -> Result<u64>;
+    /// Sells through `sell_quote` (token -> SOL), acquiring the tokens first if the venue has to.
+    async fn open(&self, user: &Pubkey, token: &str, sell_quote: &JupiterQuote) -> Result<ShortPlan>;
+    /// Buys back through `buy_quote` (SOL -> token) and settles the `amount_raw` the venue is owed.
+    async fn cover(&self, user: &Pubkey, token: &str, amount_raw: u64, buy_quote: &JupiterQuote) -> Result<ShortPlan>;
+}
+
+/* ---------- inventory ---------- */
+
+/// Sells tokens the portfolio already holds. When the position is smaller than the signal,
+/// this sells to flat instead of going net short.
+pub struct InventorySell {
+    db: Arc<Database>,
+    balances: Arc<BalanceTracker>,
+    jupiter: Arc<JupiterClient>,
+}
+
+impl InventorySell {
+    pub fn new(db: Arc<Database>, balances: Arc<BalanceTracker>, jupiter: Arc<JupiterClient>) -> Self {
+        Self { db, balances, jupiter }
+    }
+}
+
+#[async_trait]
+impl ShortVenue for InventorySell {
+    fn name(&self) -> &'static str { "inventory" }
+
+    /// Only what the strategies trading from `signer_key` hold, never more than its wallet
+    /// shows, and nothing until that wallet's balances have been fetched.
+    async fn capacity(&self, signer_key: &str, token: &str, wanted_raw: u64) -> Result<u64> {
+        let Some(held) = self.balances.token_balance(signer_key, token).await else {
+            return Ok(0);
+        };
+        let net: i64 = self.db.get_spot_inventory_by_strategy(token)?.into_iter()
+            .filter(|(strategy_id, _)| CONFIG.signer_key(strategy_id) == signer_key)
+            .map(|(_, net)| net)
+            .sum();
+        Ok((net.max(0) as u64).min(held).min(wanted_raw))
+    }
+
+    async fn open(&self, user: &Pubkey, _token: &str, sell_quote: &JupiterQuote) -> Result<ShortPlan> {
+        let tx = self.jupiter.swap_transaction(user, sell_quote).await?;
+        Ok(ShortPlan { transactions_b64: vec![tx], swap_index: 0 })
+    }
+
+    async fn cover(&self, user: &Pubkey, _token: &str, _amount_raw: u64, buy_quote: &JupiterQuote) -> Result<ShortPlan> {
+        let tx = self.jupiter.swap_transaction(user, buy_quote).await?;
+        Ok(ShortPlan { transactions_b64: vec![tx], swap_index: 0 })
+    }
+}
+
+/* ---------- lending borrow-and-sell ---------- */
+
+/// Borrows the token through a lending-protocol adapter service, then sells it via Jupiter.
+/// The adapter builds unsigned borrow/repay transactions; the signer still signs everything.
+pub struct LendingBorrowSell {
+    client: Client,
+    adapter_url: String,
+    jupiter: Arc<JupiterClient>,
+}
+
+impl LendingBorrowSell {
+    pub fn new(adapter_url: String, jupiter: Arc<JupiterClient>) -> Self {
+        Self {
+            client: Client::builder().timeout(Duration::from_secs(10)).build().unwrap(),
+            adapter_url,
+            jupiter,
+        }
+    }
+
+    async fn adapter_tx(&self, action: &str, user: &Pubkey, token: &str, amount_raw: u64) -> Result<String> {
+        let url = format!("{}/{}", self.adapter_url, action);
+        let body = serde_json::json!({ "mint": token, "amount": amount_raw.to_string(), "owner": user.to_string() });
+        let response: serde_json::Value = self.client.post(&url).json(&body).send().await?.error_for_status()?.json().await?;
+        response["transaction"].as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow!("Lending adapter {} response has no transaction", action))
+    }
+}
+
+#[async_trait]
+impl ShortVenue for LendingBorrowSell {
+    fn name(&self) -> &'static str { "lending_borrow" }
+
+    async fn capacity(&self, _signer_key: &str, token: &str, wanted_raw: u64) -> Result<u64> {
+        let url = format!("{}/available?mint={}", self.adapter_url, token);
+        let response: serde_json::Value = self.client.get(&url).send().await?.error_for_status()?.json().await?;
+        let available: u64 = response["available"].as_str().unwrap_or("0").parse()?;
+        Ok(available.min(wanted_raw))
+    }
+
+    async fn open(&self, user: &Pubkey, token: &str, sell_quote: &JupiterQuote) -> Result<ShortPlan> {
+        let amount_raw = sell_quote.in_amount()?;
+        let borrow = self.adapter_tx("borrow", user, token, amount_raw).await?;
+        let sell = self.jupiter.swap_transaction(user, sell_quote).await?;
+        Ok(ShortPlan { transactions_b64: vec![borrow, sell], swap_index: 1 })
+    }
+
+    async fn cover(&self, user: &Pubkey, token: &str, amount_raw: u64, buy_quote: &JupiterQuote) -> Result<ShortPlan> {
+        let bought = buy_quote.min_out_amount()?;
+        if bought < amount_raw {
+            return Err(anyhow!("cover buys at least {} of {} but {} is owed", bought, token, amount_raw));
+        }
+        let buy = self.jupiter.swap_transaction(user, buy_quote).await?;
+        let repay = self.adapter_tx("repay", user, token, amount_raw).await?;
+        Ok(ShortPlan { transactions_b64: vec![buy, repay], swap_index: 0 })
+    }
+}
+
+/* ---------- selection ---------- */
+
+/// Picks the first venue, in preference order, that can take any of the order, or with
+/// `all_or_none` the first that can take all of it.
+pub async fn select<'a>(
+    venues: &'a [Box<dyn ShortVenue>],
+    signer_key: &str,
+    token: &str,
+    wanted_raw: u64,
+    all_or_none: bool,
+) -> Result<(&'a dyn ShortVenue, u64)> {
+    for venue in venues {
+        let capacity = venue.capacity(signer_key, token, wanted_raw).await?;
+        if all_or_none && capacity < wanted_raw {
+            info!(venue = venue.name(), token, capacity, wanted_raw, "short venue cannot take the whole order, skipping");
+            continue;
+        }
+        if capacity > 0 {
+            if capacity < wanted_raw {
+                info!(venue = venue.name(), token, capacity, wanted_raw, "short venue can only partially fill, trading to its capacity");
+            }
+            return Ok((venue.as_ref(), capacity));
+        }
+    }
+    Err(anyhow!("no short venue available for {} of {}", wanted_raw, token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{balances::WalletBalances, config, rpc::{SolanaRpc, StubRpc}, signer_client::SignerClient, venues::mock::MockShortVenue};
+    use shared_models::{OrderDetails, Side};
+    use std::collections::HashMap;
+
+    fn venues(capacities: &[(&'static str, u64)]) -> Vec<Box<dyn ShortVenue>> {
+        capacities.iter().map(|&(name, capacity_raw)| Box::new(MockShortVenue { name, capacity_raw }) as Box<dyn ShortVenue>).collect()
+    }
+
+    #[tokio::test]
+    async fn first_venue_with_capacity_wins() {
+        let venues = venues(&[("inventory", 0), ("lending_borrow", 500), ("other", 1_000)]);
+        let (venue, amount) = select(&venues, "default", "TOKEN", 1_000, false).await.unwrap();
+        assert_eq!((venue.name(), amount), ("lending_borrow", 500));
+    }
+
+    #[tokio::test]
+    async fn all_or_none_skips_partial_venues() {
+        let venues = venues(&[("inventory", 400), ("lending_borrow", 1_000)]);
+        let (venue, amount) = select(&venues, "default", "TOKEN", 1_000, true).await.unwrap();
+        assert_eq!((venue.name(), amount), ("lending_borrow", 1_000));
+
+        let venues = self::venues(&[("inventory", 400), ("lending_borrow", 999)]);
+        assert!(select(&venues, "default", "TOKEN", 1_000, true).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn no_capacity_anywhere_fails() {
+        let venues = venues(&[("inventory", 0), ("lending_borrow", 0)]);
+        assert!(select(&venues, "default", "TOKEN", 1_000, false).await.is_err());
+    }
+
+    /// An open spot trade of `amount_raw` by `strategy_id`, as a confirmed fill leaves it.
+    fn trade(db: &Database, strategy_id: &str, side: Side, amount_raw: u64) {
+        let details = OrderDetails { token_address: "token".to_string(), suggested_size_usd: 10.0, confidence: 1.0, side };
+        let id = db.log_trade_attempt(&details, strategy_id, 1.0).unwrap();
+        db.set_instrument(id, "Spot").unwrap();
+        db.set_filled_amount(id, amount_raw).unwrap();
+        db.open_trade(id, "sig").unwrap();
+    }
+
+    #[tokio::test]
+    async fn inventory_is_limited_to_the_keys_own_strategies_and_wallet() {
+        config::test_env();
+        let path = std::env::temp_dir().join(format!("short-venue-test-{}-{}.db", std::process::id(), rand::random::<u64>()));
+        let db = Arc::new(Database::new(path.to_str().unwrap()).unwrap());
+        let rpc: Arc<dyn SolanaRpc> = Arc::new(StubRpc::default());
+        let sol_price = Arc::new(tokio::sync::Mutex::new(100.0));
+        let balances = Arc::new(BalanceTracker::new(rpc.clone(), Arc::new(SignerClient::from_config()), sol_price.clone()));
+        let venue = InventorySell::new(db.clone(), balances.clone(), Arc::new(JupiterClient::new(rpc, sol_price)));
+
+        // Both keys hold the mint; meanrev has also sold part of its position.
+        trade(&db, "momentum_v1", Side::Long, 1_000);
+        trade(&db, "meanrev_v1", Side::Long, 5_000);
+        trade(&db, "meanrev_v1", Side::Short, 2_000);
+        assert_eq!(venue.capacity("momentum", "token", 10_000).await.unwrap(), 0, "unknown until momentum's wallet is fetched");
+
+        let wallet = |held: u64| WalletBalances { sol_lamports: 0, tokens: HashMap::from([("token".to_string(), held)]) };
+        balances.seed("momentum", wallet(1_000)).await;
+        balances.seed("meanrev", wallet(3_000)).await;
+        assert_eq!(venue.capacity("momentum", "token", 10_000).await.unwrap(), 1_000, "not meanrev's tokens");
+        assert_eq!(venue.capacity("meanrev", "token", 10_000).await.unwrap(), 3_000, "meanrev's sells net only its own longs");
+        assert_eq!(venue.capacity("meanrev", "token", 500).await.unwrap(), 500);
+
+        balances.seed("momentum", wallet(400)).await;
+        assert_eq!(venue.capacity("momentum", "token", 10_000).await.unwrap(), 400, "capped by the wallet");
+    }
+}
@@ -1,32 +1,263 @@
 // executor/src/signer_client.rs
 use crate::config::CONFIG;
-use anyhow::{anyhow, Context, Result};
-use reqwest::Client;
-use shared_models::{SignRequest, SignResponse};
-use std::time::Duration;
-use tracing::error;
-
-pub async fn get_pubkey() -> Result<String> {
-    let client = Client::new();
-    let url = format!("{}/pubkey", CONFIG.signer_url);
-    let response = client.get(&url).timeout(Duration::from_secs(5)).send().await?
-        .json::<serde_json::Value>().await?;
-    
-    response["pubkey"].as_str()
-        .map(|s| s.to_string())
-        .ok_or_else(|| anyhow!("Pubkey not found in signer response"))
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use sha2::Sha256;
+use shared_models::{SignRequest, SignResponse, SignerErrorBody};
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::{error, warn};
+
+/// Why the signer did not sign, mapped from its status and error body.
+#[derive(Debug)]
+pub enum SignerError {
+    /// The signing policy refused the transaction; resending it will not help.
+    PolicyRejected(Vec<String>),
+    /// A signature-rate or spend budget is exhausted.
+    RateLimited(String),
+    /// Our HMAC credentials were refused.
+    Unauthorized(String),
+    /// Transport failure, timeout or a 5xx.
+    Unavailable(String),
+    /// Too many consecutive failures; requests are not being sent until the cooldown ends.
+    CircuitOpen { retry_in: Duration },
+}
+
+impl fmt::Display for SignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerError::PolicyRejected(reasons) => write!(f, "signer policy rejected the transaction: {}", reasons.join("; ")),
+            SignerError::RateLimited(reason) => write!(f, "signer rate limit: {}", reason),
+            SignerError::Unauthorized(reason) => write!(f, "signer refused our credentials: {}", reason),
+            SignerError::Unavailable(reason) => write!(f, "signer unavailable: {}", reason),
+            SignerError::CircuitOpen { retry_in } => write!(f, "signer circuit open, retrying in {:?}", retry_in),
+        }
+    }
+}
+
+impl std::error::Error for SignerError {}
+
+/// Consecutive-failure circuit breaker. Once open, calls fail fast until the cooldown ends; then
+/// a single call goes through as a probe (half-open) while the rest keep failing fast. The probe
+/// succeeding closes the breaker, failing reopens it.
+struct Breaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// When the half-open probe was let through. A probe that never reports back (its caller
+    /// was cancelled) is given up on after a cooldown, so another can go.
+    probe_started: Option<Instant>,
+}
+
+impl Breaker {
+    fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self { failure_threshold, cooldown, consecutive_failures: 0, open_until: None, probe_started: None }
+    }
+
+    /// How long calls keep failing fast from `now`, or `None` when one may be sent.
+    fn blocked_for(&self, now: Instant) -> Option<Duration> {
+        match (self.open_until, self.probe_started) {
+            (Some(until), _) if now < until => Some(until - now),
+            (Some(_), Some(started)) if now < started + self.cooldown => Some(started + self.cooldown - now),
+            _ => None,
+        }
+    }
+
+    fn admit(&mut self, now: Instant) -> Result<(), SignerError> {
+        if let Some(retry_in) = self.blocked_for(now) {
+            return Err(SignerError::CircuitOpen { retry_in });
+        }
+        if self.open_until.is_some() {
+            self.probe_started = Some(now);
+        }
+        Ok(())
+    }
+
+    /// Records the outcome of an admitted call; `failure` is why the signer was unavailable.
+    fn record(&mut self, failure: Option<&str>, now: Instant) {
+        match failure {
+            Some(reason) => {
+                self.consecutive_failures += 1;
+                if self.probe_started.take().is_some() {
+                    self.open_until = Some(now + self.cooldown);
+                    warn!(%reason, cooldown = ?self.cooldown, "signer probe failed, circuit reopened");
+                } else if self.consecutive_failures >= self.failure_threshold {
+                    self.open_until = Some(now + self.cooldown);
+                    error!(failures = self.consecutive_failures, %reason, cooldown = ?self.cooldown, "signer circuit opened");
+                }
+            }
+            None => {
+                if self.open_until.take().is_some() {
+                    warn!("signer circuit closed");
+                }
+                self.probe_started = None;
+                self.consecutive_failures = 0;
+            }
+        }
+    }
+}
+
+/// Client for the isolated signer: one pooled HTTP client, each key's pubkey fetched once, HMAC
+/// request signing and typed errors behind a circuit breaker.
+pub struct SignerClient {
+    http: Client,
+    pubkeys: Mutex<HashMap<String, Pubkey>>,
+    breaker: Mutex<Breaker>,
+}
+
+impl SignerClient {
+    pub fn from_config() -> Self {
+        Self {
+            http: Client::builder().timeout(Duration::from_secs(5)).build().expect("reqwest client"),
+            pubkeys: Mutex::new(HashMap::new()),
+            breaker: Mutex::new(Breaker::new(CONFIG.signer_breaker_failures, Duration::from_secs(CONFIG.signer_breaker_cooldown_secs))),
+        }
+    }
+
+    /// Whether calls are currently failing fast. Strategies skip live orders while it is.
+    pub fn is_open(&self) -> bool {
+        self.breaker.lock().unwrap().blocked_for(Instant::now()).is_some()
+    }
+
+    /// The wallet of the signer's key named `key`.
+    pub async fn pubkey(&self, key: &str) -> Result<Pubkey> {
+        if let Some(pubkey) = self.pubkeys.lock().unwrap().get(key) {
+            return Ok(*pubkey);
+        }
+        let response: serde_json::Value = self.call(Method::GET, &format!("/pubkey/{}", key), vec![]).await?.json().await
+            .map_err(|e| SignerError::Unavailable(e.to_string()))?;
+        let pubkey = Pubkey::from_str(response["pubkey"].as_str().context("Pubkey not found in signer response")?)?;
+        self.pubkeys.lock().unwrap().insert(key.to_string(), pubkey);
+        Ok(pubkey)
+    }
+
+    /// Has the signer sign `tx_b64` with its key named `key`.
+    pub async fn sign_transaction(&self, tx_b64: &str, key: &str) -> Result<String> {
+        let request = SignRequest { transaction_b64: tx_b64.to_string(), key: Some(key.to_string()) };
+        let body = serde_json::to_vec(&request).context("Failed to encode sign request")?;
+        let response: SignResponse = self.call(Method::POST, "/sign", body).await?.json().await
+            .map_err(|e| SignerError::Unavailable(e.to_string()))?;
+        Ok(response.signed_transaction_b64)
+    }
+
+    /// Sends one authenticated request through the breaker, turning non-2xx answers into `SignerError`.
+    async fn call(&self, method: Method, path: &str, body: Vec<u8>) -> Result<Response, SignerError> {
+        self.breaker.lock().unwrap().admit(Instant::now())?;
+        let result = match self.authenticated(method, path, body).send().await {
+            Err(e) => Err(SignerError::Unavailable(e.to_string())),
+            Ok(response) if response.status().is_success() => Ok(response),
+            Ok(response) => Err(Self::error_from(response).await),
+        };
+        // Only unavailability counts towards the breaker: a refusal means the signer is working.
+        let failure = match &result {
+            Err(SignerError::Unavailable(reason)) => Some(reason.as_str()),
+            _ => None,
+        };
+        self.breaker.lock().unwrap().record(failure, Instant::now());
+        result
+    }
+
+    async fn error_from(response: Response) -> SignerError {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        let body: Option<SignerErrorBody> = serde_json::from_str(&text).ok();
+        let reasons = body.map(|b| b.reasons).unwrap_or_else(|| vec![text]);
+        match status {
+            StatusCode::FORBIDDEN => SignerError::PolicyRejected(reasons),
+            StatusCode::TOO_MANY_REQUESTS => SignerError::RateLimited(reasons.join("; ")),
+            StatusCode::UNAUTHORIZED => SignerError::Unauthorized(reasons.join("; ")),
+            status if status.is_server_error() => SignerError::Unavailable(format!("{}: {}", status, reasons.join("; "))),
+            // Anything else is a request the signer will keep refusing.
+            status => SignerError::PolicyRejected(vec![format!("{}: {}", status, reasons.join("; "))]),
+        }
+    }
+
+    /// Adds the signer's HMAC headers: key id, timestamp, a random nonce and the MAC over
+    /// `METHOD\nPATH\nTIMESTAMP\nNONCE\nBODY`.
+    fn authenticated(&self, method: Method, path: &str, body: Vec<u8>) -> RequestBuilder {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0).to_string();
+        let nonce = hex::encode(rand::random::<[u8; 16]>());
+        let mut mac = Hmac::<Sha256>::new_from_slice(CONFIG.signer_hmac_secret.as_bytes()).expect("HMAC accepts any key length");
+        mac.update(format!("{}\n{}\n{}\n{}\n", method, path, timestamp, nonce).as_bytes());
+        mac.update(&body);
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        self.http.request(method, format!("{}{}", CONFIG.signer_url, path))
+            .header("x-signer-key-id", &CONFIG.signer_key_id)
+            .header("x-signer-timestamp", timestamp)
+            .header("x-signer-nonce", nonce)
+            .header("x-signer-signature", signature)
+            .header("content-type", "application/json")
+            .body(body)
+    }
 }
 
-pub async fn sign_transaction(tx_b64: &str) -> Result<String> {
-    let client = Client::new();
-    let url = format!("{}/sign", CONFIG.signer_url);
-    let request = SignRequest { transaction_b64: tx_b64.to_string() };
-    
-    let response: SignResponse = client.post(&url)
-        .json(&request)
-        .timeout(Duration::from_secs(5))
-        .send().await?
-        .json().await?;
-    
-    Ok(response.signed_transaction_b64)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_secs(30);
+
+    fn tripped(now: Instant) -> Breaker {
+        let mut breaker = Breaker::new(3, COOLDOWN);
+        for _ in 0..3 {
+            breaker.admit(now).unwrap();
+            breaker.record(Some("timeout"), now);
+        }
+        breaker
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let now = Instant::now();
+        let mut breaker = Breaker::new(3, COOLDOWN);
+        for _ in 0..2 {
+            breaker.admit(now).unwrap();
+            breaker.record(Some("timeout"), now);
+        }
+        breaker.admit(now).unwrap();
+        breaker.record(None, now);
+        assert_eq!(breaker.consecutive_failures, 0, "a success resets the count");
+
+        let mut breaker = tripped(now);
+        assert!(matches!(breaker.admit(now + Duration::from_secs(10)), Err(SignerError::CircuitOpen { retry_in }) if retry_in == Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn lets_a_single_probe_through_after_the_cooldown() {
+        let now = Instant::now();
+        let mut breaker = tripped(now);
+        let later = now + COOLDOWN;
+        assert!(breaker.blocked_for(later).is_none());
+        breaker.admit(later).unwrap();
+        assert!(matches!(breaker.admit(later), Err(SignerError::CircuitOpen { .. })), "only one probe at a time");
+        assert!(breaker.blocked_for(later + Duration::from_secs(1)).is_some());
+
+        // A probe that never reports back is given up on after another cooldown.
+        assert!(breaker.admit(later + COOLDOWN).is_ok());
+    }
+
+    #[test]
+    fn probe_failure_reopens_and_success_closes() {
+        let now = Instant::now();
+        let mut breaker = tripped(now);
+        let later = now + COOLDOWN;
+        breaker.admit(later).unwrap();
+        breaker.record(Some("connection refused"), later);
+        assert!(matches!(breaker.admit(later + Duration::from_secs(1)), Err(SignerError::CircuitOpen { .. })));
+
+        let again = later + COOLDOWN;
+        breaker.admit(again).unwrap();
+        breaker.record(None, again);
+        assert!(breaker.open_until.is_none() && breaker.probe_started.is_none());
+        assert!(breaker.admit(again).is_ok() && breaker.admit(again).is_ok(), "closed again: calls are not limited to one");
+    }
 }
@@ -0,0 +1,206 @@
+// executor/src/simulation.rs
+use crate::{config::CONFIG, jupiter::{self, JupiterQuote, SOL_MINT}, rpc::SolanaRpc};
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::{fmt, str::FromStr, sync::Arc};
+use tracing::{debug, warn};
+
+const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+
+/// Result of simulating one transaction against current state.
+#[derive(Debug, Clone, Default)]
+pub struct Simulation {
+    pub err: Option<String>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    /// Post-simulation state of the accounts asked for, in request order; `None` if absent.
+    pub accounts: Vec<Option<SimulatedAccount>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SimulatedAccount {
+    pub lamports: u64,
+    /// Raw amount when the account is an SPL token account.
+    pub token_amount: Option<u64>,
+}
+
+/// Why a pre-flight simulation refused a transaction.
+#[derive(Debug)]
+pub enum SimulationError {
+    /// The program failed; `reason` is the transaction error plus the last log lines.
+    Failed { reason: String, units_consumed: Option<u64> },
+    /// Simulated output is below the quote's minimum.
+    BelowMinimum { mint: String, simulated: u64, minimum: u64 },
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::Failed { reason, units_consumed } => {
+                write!(f, "simulation failed after {} CU: {}", units_consumed.unwrap_or(0), reason)
+            }
+            SimulationError::BelowMinimum { mint, simulated, minimum } => {
+                write!(f, "simulated output {} of {} is below quote minimum {}", simulated, mint, minimum)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+/// Associated token account of `owner` for `mint` under `token_program`, the program owning the
+/// mint: classic SPL Token or Token-2022.
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    let ata_program = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID).unwrap();
+    Pubkey::find_program_address(&[owner.as_ref(), token_program.as_ref(), mint.as_ref()], &ata_program).0
+}
+
+/// Simulates unsigned transactions before they are signed, so failures cost neither tips nor fees.
+pub struct Preflight {
+    rpc: Arc<dyn SolanaRpc>,
+}
+
+impl Preflight {
+    pub fn new(rpc: Arc<dyn SolanaRpc>) -> Self {
+        Self { rpc }
+    }
+
+    /// Simulates `unsigned_b64`. With `quote`, also checks the fee payer receives at least the
+    /// quote's minimum output. Native SOL output is checked net of a fee allowance.
+    pub async fn check(&self, unsigned_b64: &str, quote: Option<&JupiterQuote>) -> Result<Simulation> {
+        let tx = jupiter::deserialize_transaction(unsigned_b64)?;
+        let payer = *tx.message.static_account_keys().first().ok_or_else(|| anyhow!("transaction has no fee payer"))?;
+
+        let watch = match quote {
+            Some(q) if q.output_mint == SOL_MINT => Some((payer, None)),
+            Some(q) => {
+                let mint = Pubkey::from_str(&q.output_mint)?;
+                let token_program = self.rpc.account_owner(&mint).await?.ok_or_else(|| anyhow!("mint {} not found", mint))?;
+                Some((associated_token_address(&payer, &mint, &token_program), Some(q)))
+            }
+            None => None,
+        };
+        let (before, addresses) = match &watch {
+            Some((account, Some(_))) => (self.rpc.token_balance(account).await?, vec![*account]),
+            Some((account, None)) => (self.rpc.lamports(account).await?, vec![*account]),
+            None => (0, vec![]),
+        };
+
+        let sim = self.rpc.simulate_transaction(&tx, &addresses).await?;
+        debug!(units = ?sim.units_consumed, err = ?sim.err, "pre-flight simulation");
+        if let Some(err) = &sim.err {
+            let tail = sim.logs.iter().rev().take(3).rev().cloned().collect::<Vec<_>>().join(" | ");
+            warn!(%err, logs = %tail, "pre-flight simulation failed");
+            return Err(SimulationError::Failed { reason: format!("{} [{}]", err, tail), units_consumed: sim.units_consumed }.into());
+        }
+
+        if let Some(q) = quote {
+            let post = sim.accounts.first().cloned().flatten().unwrap_or_default();
+            let (after, allowance) = if q.output_mint == SOL_MINT {
+                (post.lamports, CONFIG.sim_sol_fee_allowance_lamports)
+            } else {
+                (post.token_amount.unwrap_or(0), 0)
+            };
+            let simulated = (after + allowance).saturating_sub(before);
+            let minimum = q.min_out_amount()?;
+            if simulated < minimum {
+                return Err(SimulationError::BelowMinimum { mint: q.output_mint.clone(), simulated, minimum }.into());
+            }
+        }
+        Ok(sim)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config, rpc::{StubRpc, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID}};
+    use base64::{engine::general_purpose::STANDARD as B64, Engine};
+    use solana_sdk::{hash::Hash, message::{v0, VersionedMessage}, signature::Signature, transaction::VersionedTransaction};
+
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+    fn pubkey(s: &str) -> Pubkey { Pubkey::from_str(s).unwrap() }
+
+    fn unsigned_tx(payer: &Pubkey) -> String {
+        let message = v0::Message::try_compile(payer, &[], &[], Hash::new_unique()).unwrap();
+        let tx = VersionedTransaction { signatures: vec![Signature::default()], message: VersionedMessage::V0(message) };
+        B64.encode(bincode::serialize(&tx).unwrap())
+    }
+
+    fn quote(output_mint: &str, minimum: u64) -> JupiterQuote {
+        serde_json::from_value(serde_json::json!({
+            "inputMint": SOL_MINT, "inAmount": "1000000000", "outputMint": output_mint, "outAmount": minimum.to_string(),
+            "otherAmountThreshold": minimum.to_string(), "swapMode": "ExactIn", "slippageBps": 50,
+            "priceImpactPct": "0", "routePlan": [],
+        })).unwrap()
+    }
+
+    fn simulated(account: SimulatedAccount) -> Simulation {
+        Simulation { units_consumed: Some(50_000), accounts: vec![Some(account)], ..Default::default() }
+    }
+
+    #[test]
+    fn ata_matches_known_addresses_for_both_token_programs() {
+        let (owner, mint) = (pubkey("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"), pubkey(USDC));
+        assert_eq!(associated_token_address(&owner, &mint, &pubkey(TOKEN_PROGRAM_ID)), pubkey("FGETo8T8wMcN2wCjav8VK6eh3dLk63evNDPxzLSJra8B"));
+        assert_eq!(associated_token_address(&owner, &mint, &pubkey(TOKEN_2022_PROGRAM_ID)), pubkey("GdjpegrtGwU3pgtzPivYVViSA8rmGL248qBVKzsrU3DD"));
+    }
+
+    #[tokio::test]
+    async fn failed_simulation_is_refused_with_its_logs() {
+        config::test_env();
+        let rpc = Arc::new(StubRpc::default());
+        rpc.set_simulation(Simulation {
+            err: Some("InstructionError(2, Custom(6001))".to_string()),
+            logs: vec!["Program log: slippage".to_string()],
+            units_consumed: Some(80_000),
+            ..Default::default()
+        });
+        let err = Preflight::new(rpc).check(&unsigned_tx(&Pubkey::new_unique()), None).await.unwrap_err();
+        let Some(SimulationError::Failed { reason, units_consumed }) = err.downcast_ref::<SimulationError>() else { panic!("{err:#}") };
+        assert!(reason.contains("Custom(6001)") && reason.contains("Program log: slippage"));
+        assert_eq!(*units_consumed, Some(80_000));
+    }
+
+    #[tokio::test]
+    async fn token_output_below_minimum_is_refused() {
+        config::test_env();
+        let rpc = Arc::new(StubRpc::default());
+        rpc.seed_owner(pubkey(USDC), pubkey(TOKEN_2022_PROGRAM_ID));
+        let preflight = Preflight::new(rpc.clone());
+        let tx = unsigned_tx(&Pubkey::new_unique());
+
+        rpc.set_simulation(simulated(SimulatedAccount { lamports: 0, token_amount: Some(999) }));
+        let err = preflight.check(&tx, Some(&quote(USDC, 1_000))).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<SimulationError>(), Some(SimulationError::BelowMinimum { simulated: 999, minimum: 1_000, .. })));
+
+        rpc.set_simulation(simulated(SimulatedAccount { lamports: 0, token_amount: Some(1_000) }));
+        assert!(preflight.check(&tx, Some(&quote(USDC, 1_000))).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn unknown_output_mint_is_an_error() {
+        config::test_env();
+        let preflight = Preflight::new(Arc::new(StubRpc::default()));
+        assert!(preflight.check(&unsigned_tx(&Pubkey::new_unique()), Some(&quote(USDC, 1))).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn sol_output_is_checked_net_of_the_fee_allowance() {
+        config::test_env();
+        let rpc = Arc::new(StubRpc::default());
+        let preflight = Preflight::new(rpc.clone());
+        let tx = unsigned_tx(&Pubkey::new_unique());
+        let before = rpc.lamports(&Pubkey::new_unique()).await.unwrap();
+        let allowance = CONFIG.sim_sol_fee_allowance_lamports;
+
+        // Received 1 SOL but paid fees out of it: within the allowance.
+        rpc.set_simulation(simulated(SimulatedAccount { lamports: before + 1_000_000_000 - allowance, token_amount: None }));
+        assert!(preflight.check(&tx, Some(&quote(SOL_MINT, 1_000_000_000))).await.is_ok());
+
+        rpc.set_simulation(simulated(SimulatedAccount { lamports: before + 1_000_000_000 - allowance - 1, token_amount: None }));
+        let err = preflight.check(&tx, Some(&quote(SOL_MINT, 1_000_000_000))).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<SimulationError>(), Some(SimulationError::BelowMinimum { .. })));
+    }
+}
@@ -0,0 +1,19 @@
+// executor/src/static_keys.rs
+use anyhow::{Context, Result};
+use solana_sdk::{instruction::CompiledInstruction, pubkey::Pubkey};
+
+/// Inserts a static key at `at`, shifting every later index, including address-lookup ones, by one.
+/// Fails, leaving the message untouched, when an index would pass 255.
+pub fn insert_key(keys: &mut Vec<Pubkey>, instructions: &mut [CompiledInstruction], at: usize, key: Pubkey) -> Result<u8> {
+    let index = u8::try_from(at)?;
+    u8::try_from(keys.len() + 1).context("no room for another static key")?;
+    let shift = |i: u8| if i >= index { i.checked_add(1).context("no room for another account index") } else { Ok(i) };
+    let mut shifted = instructions.to_vec();
+    for ix in shifted.iter_mut() {
+        ix.program_id_index = shift(ix.program_id_index)?;
+        ix.accounts = ix.accounts.iter().map(|a| shift(*a)).collect::<Result<_>>()?;
+    }
+    instructions.clone_from_slice(&shifted);
+    keys.insert(at, key);
+    Ok(index)
+}
@@ -1,4 +1,4 @@
-use crate::{register_strategy, strategies::Strategy};
+use crate::strategies::Strategy;
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::Deserialize;
@@ -10,7 +10,6 @@ use shared_models::{EventType, MarketEvent, StrategyAction, OrderDetails, Side};
 #[derive(Default, Deserialize)]
 struct DevWalletDrain {
     dev_balance_threshold_pct: f64,
-    #[serde(skip)] monitored_dev_wallets: HashSet<String>, // In a real system, this would be populated from on-chain data
 }
 
 #[async_trait]
@@ -1,9 +1,4 @@
-use crate::{register_strategy, strategies::S                    return Ok(StrategyAction::Execute(OrderDetails {
-                        token_address: tick.token_address.clone(),
-                        suggested_size_usd: 1000.0,
-                        confidence: 0.9,
-                        side: Side::Long,
-                    }));gy};
+use crate::strategies::Strategy;
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::Deserialize;
@@ -39,7 +34,7 @@ impl Strategy for KoreanTimeBurst {
 
             // KST 09:00-11:00 corresponds to UTC 00:00-02:00 if no DST difference, or 01:00-03:00 if UTC+9
             // Simplified check: if it's "Korean business hours" in UTC (for simulator)
-            let is_korean_trading_hour = hour >= 0 && hour < 3; // Approx 9 AM - 12 PM KST in UTC
+            let is_korean_trading_hour = hour < 3; // Approx 9 AM - 12 PM KST in UTC
 
             if is_korean_trading_hour {
                 // This would need historical average volume for the specific token.
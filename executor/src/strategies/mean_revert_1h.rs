@@ -1,14 +1,4 @@
-use crate::{register_strategy, strategies::Str                          return Ok(StrategyAction::Execute(OrderDetails {
-                         token_address: tick.token_address.clone(),
-                         suggested_size_usd: 300.0,
-                         confidence: 0.65,
-                         side: Side::Short,
-                     }));          return Ok(StrategyAction::Execute(OrderDetails {
-                        token_address: tick.token_address.clone(),
-                        suggested_size_usd: 400.0,
-                        confidence: 0.6,
-                        side: Side::Long,
-                    }));};
+use crate::strategies::Strategy;
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::Deserialize;
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use serde_json::Value;
-use shared_models::{EventType, MarketEvent, StrategyAction, Side};   // Side imported
+use shared_models::{EventType, LegPosition, MarketEvent, OrderLeg, StrategyAction};
 use std::collections::HashSet;
 
 #[async_trait]
@@ -10,6 +10,20 @@ pub trait Strategy: Send + Sync + 'static { // Added 'static bound
     fn subscriptions(&self) -> HashSet<EventType>;
     async fn init(&mut self, params: &Value) -> Result<()>;
     async fn on_event(&mut self, event: &MarketEvent) -> Result<StrategyAction>;
+    /// Called once an `ExecuteGroup` from this strategy has run, with what its legs hold now.
+    async fn on_group_result(&mut self, _legs: &[OrderLeg], _result: GroupResult<'_>) {}
+}
+
+/// How an order group ended.
+#[derive(Debug, Clone, Copy)]
+pub enum GroupResult<'a> {
+    /// Every leg filled; what each holds, in leg order.
+    Filled(&'a [LegPosition]),
+    /// A leg failed and every filled leg was unwound: nothing changed.
+    Unwound,
+    /// A leg failed and some filled legs could not be unwound. Per leg, in leg order, what is
+    /// still open; those positions are unhedged.
+    Stranded(&'a [Option<LegPosition>]),
 }
 
 // Strategy constructor for dynamic loading
@@ -1,70 +1,361 @@
-use crate::{register_strategy, strategies::Strategy};
+use crate::strategies::{GroupResult, Strategy};
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::Value;
 use std::collections::{HashSet, HashMap};
 use tracing::info;
-use shared_models::{EventType, MarketEvent, StrategyAction, OrderDetails, Side};
+use shared_models::{EventType, MarketEvent, StrategyAction, OrderDetails, OrderLeg, Instrument, LegPosition, Side};
+
+/// Latest view of one token's spot and perp markets.
+#[derive(Default, Clone)]
+struct BasisBook {
+    spot_price: Option<f64>,
+    mark_price: Option<f64>,
+    funding_rate_pct: Option<f64>, // per funding period, positive = longs pay shorts
+}
+
+/// A held hedge: which way the perp leg faces, how large it is and what each leg holds.
+/// A leg is `None` once it is flat; a hedge missing a leg is unhedged and gets closed.
+#[derive(Clone)]
+struct OpenHedge {
+    perp_side: Side,
+    size_usd: f64,
+    spot: Option<LegPosition>,
+    perp: Option<LegPosition>,
+}
+
+impl OpenHedge {
+    fn is_complete(&self) -> bool { self.spot.is_some() && self.perp.is_some() }
+    fn is_flat(&self) -> bool { self.spot.is_none() && self.perp.is_none() }
+}
 
 #[derive(Default, Deserialize)]
 struct PerpBasisArb {
-    basis_threshold_pct: f64,
-    #[serde(skip)] spot_prices: HashMap<String, f64>,
-    #[serde(skip)] perp_funding_rates: HashMap<String, f64>, // Simplified to direct funding rate for token
+    entry_basis_pct: f64,
+    exit_basis_pct: f64,
+    round_trip_fee_bps: f64,   // entry + exit cost across both legs
+    holding_hours: f64,        // horizon over which funding carry is expected to accrue
+    funding_periods_per_year: f64,
+    max_size_usd: f64,
+    #[serde(skip)] books: HashMap<String, BasisBook>,
+    #[serde(skip)] open_hedges: HashMap<String, OpenHedge>,
+}
+
+impl PerpBasisArb {
+    /// Expected edge, as a fraction of notional, of holding a hedge with the perp on `perp_side`:
+    /// basis convergence plus funding received over the holding horizon.
+    fn expected_edge(&self, basis: f64, funding_rate_pct: f64, perp_side: &Side) -> f64 {
+        let periods_held = self.holding_hours / (24.0 * 365.0) * self.funding_periods_per_year;
+        let funding_received = match perp_side {
+            Side::Short => funding_rate_pct / 100.0,
+            Side::Long => -funding_rate_pct / 100.0,
+        };
+        basis.abs() + funding_received * periods_held
+    }
+
+    fn legs(token: &str, perp_side: Side, size_usd: f64, confidence: f64) -> Vec<OrderLeg> {
+        let leg = |instrument, side| OrderLeg {
+            instrument,
+            details: OrderDetails {
+                token_address: token.to_string(),
+                suggested_size_usd: size_usd,
+                confidence,
+                side,
+            },
+            closes: None,
+        };
+        // Spot leg first: it is the one most likely to fail, and failing first leaves nothing to unwind.
+        vec![leg(Instrument::Spot, perp_side.opposite()), leg(Instrument::Perp, perp_side)]
+    }
+
+    /// Closes exactly what is still open: the perp reduce-only, a spot short through its venue.
+    fn exit_legs(token: &str, hedge: &OpenHedge) -> Vec<OrderLeg> {
+        let mut legs = Self::legs(token, hedge.perp_side.opposite(), hedge.size_usd, 0.9);
+        legs[0].closes = hedge.spot.clone();
+        legs[1].closes = hedge.perp.clone();
+        legs.retain(|leg| leg.closes.is_some());
+        legs
+    }
 }
 
 #[async_trait]
 impl Strategy for PerpBasisArb {
     fn id(&self) -> &'static str { "perp_basis_arb" }
-    // This strategy needs both Price (spot) and a new 'PerpData' EventType (for funding/perp price)
-    // For simulation, we will use Price events for both spot and a simulated perp component.
-    fn subscriptions(&self) -> HashSet<EventType> { [EventType::Price].iter().cloned().collect() }
+    fn subscriptions(&self) -> HashSet<EventType> {
+        [EventType::Price, EventType::PerpMark, EventType::Funding].iter().cloned().collect()
+    }
 
     async fn init(&mut self, params: &Value) -> Result<()> {
-        #[derive(Deserialize)] struct P { basis_threshold_pct: f64 }
+        #[derive(Deserialize)] struct P {
+            entry_basis_pct: f64,
+            exit_basis_pct: f64,
+            #[serde(default = "default_fee_bps")] round_trip_fee_bps: f64,
+            #[serde(default = "default_holding_hours")] holding_hours: f64,
+            #[serde(default = "default_periods")] funding_periods_per_year: f64,
+            #[serde(default = "default_max_size")] max_size_usd: f64,
+        }
+        fn default_fee_bps() -> f64 { 40.0 }
+        fn default_holding_hours() -> f64 { 24.0 }
+        fn default_periods() -> f64 { 24.0 * 365.0 } // Drift funds hourly
+        fn default_max_size() -> f64 { 800.0 }
+
         let p: P = serde_json::from_value(params.clone())?;
-        self.basis_threshold_pct = p.basis_threshold_pct;
-        info!(strategy = self.id(), "Initialized with basis_threshold_pct: {}", self.basis_threshold_pct);
+        self.entry_basis_pct = p.entry_basis_pct;
+        self.exit_basis_pct = p.exit_basis_pct;
+        self.round_trip_fee_bps = p.round_trip_fee_bps;
+        self.holding_hours = p.holding_hours;
+        self.funding_periods_per_year = p.funding_periods_per_year;
+        self.max_size_usd = p.max_size_usd;
+        info!(strategy = self.id(), "Initialized with entry_basis_pct: {}, exit_basis_pct: {}, round_trip_fee_bps: {}, holding_hours: {}",
+            self.entry_basis_pct, self.exit_basis_pct, self.round_trip_fee_bps, self.holding_hours);
         Ok(())
     }
 
     async fn on_event(&mut self, event: &MarketEvent) -> Result<StrategyAction> {
-        if let MarketEvent::Price(tick) = event {
-            self.spot_prices.insert(tick.token_address.clone(), tick.price_usd);
-            // Simulate funding rate: higher volume means more volatile funding
-            let simulated_funding_rate = tick.volume_usd_1m / 1_000_000.0 * 0.001; // Tiny percentage
-            self.perp_funding_rates.insert(tick.token_address.clone(), simulated_funding_rate);
-
-            if let (Some(&spot_price), Some(&funding_rate)) = (self.spot_prices.get(&tick.token_address), self.perp_funding_rates.get(&tick.token_address)) {
-                // Simplified: Basis is just the simulated funding rate
-                // A real basis would be (perp_price - spot_price) / spot_price
-                let basis = funding_rate; 
-
-                if basis.abs() > self.basis_threshold_pct / 100.0 {
-                    if basis > 0.0 { // Positive basis: perp is more expensive, short perp & long spot
-                        info!(id = self.id(), token = %tick.token_address, "SHORT PERP/LONG SPOT signal: Basis {:.4}% is above threshold. (Simulated)", basis * 100.0);
-                        return Ok(StrategyAction::Execute(OrderDetails { // Short leg
-                            token_address: tick.token_address.clone(),
-                            suggested_size_usd: 800.0,
-                            confidence: 0.9,
-                            side: Side::Short,
-                        }));
-                        // A real strategy would also execute the long spot leg here
-                    } else { // Negative basis: perp is cheaper, long perp & short spot
-                         info!(id = self.id(), token = %tick.token_address, "LONG PERP/SHORT SPOT signal: Basis {:.4}% is below threshold. (Simulated)", basis * 100.0);
-                         return Ok(StrategyAction::Execute(OrderDetails { // Long leg
-                             token_address: tick.token_address.clone(),
-                             suggested_size_usd: 800.0,
-                             confidence: 0.9,
-                             side: Side::Long,
-                         }));
-                         // A real strategy would also execute the short spot leg here
-                    }
+        let token = event.token().to_string();
+        let book = self.books.entry(token.clone()).or_default();
+        match event {
+            MarketEvent::Price(tick) => book.spot_price = Some(tick.price_usd),
+            MarketEvent::PerpMark(mark) => book.mark_price = Some(mark.mark_price_usd),
+            MarketEvent::Funding(funding) => book.funding_rate_pct = Some(funding.funding_rate_pct),
+            _ => return Ok(StrategyAction::Hold),
+        }
+        let book = book.clone();
+        // Without a funding reading the carry is unknown, and the edge would rest on basis alone.
+        let (Some(spot), Some(mark), Some(funding_rate_pct)) = (book.spot_price, book.mark_price, book.funding_rate_pct)
+            else { return Ok(StrategyAction::Hold) };
+        if spot <= 0.0 { return Ok(StrategyAction::Hold); }
+
+        let basis = (mark - spot) / spot;
+        let annualised_funding_pct = funding_rate_pct * self.funding_periods_per_year;
+        let fees = self.round_trip_fee_bps / 10_000.0;
+
+        if let Some(hedge) = self.open_hedges.get(&token).cloned() {
+            // Exit when the basis has converged, the carry has turned against us or a leg is missing.
+            let edge = self.expected_edge(basis, funding_rate_pct, &hedge.perp_side);
+            if !hedge.is_complete() || basis.abs() * 100.0 < self.exit_basis_pct || edge < 0.0 {
+                info!(id = self.id(), token = %token, "EXIT hedge: basis {:.4}%, annualised funding {:.2}%, edge {:.4}%",
+                    basis * 100.0, annualised_funding_pct, edge * 100.0);
+                return Ok(StrategyAction::ExecuteGroup(Self::exit_legs(&token, &hedge)));
+            }
+            return Ok(StrategyAction::Hold);
+        }
+
+        if basis.abs() * 100.0 <= self.entry_basis_pct { return Ok(StrategyAction::Hold); }
+
+        // Positive basis: perp is more expensive, short perp & long spot.
+        // Negative basis: perp is cheaper, long perp & short spot.
+        let perp_side = if basis > 0.0 { Side::Short } else { Side::Long };
+        let edge = self.expected_edge(basis, funding_rate_pct, &perp_side);
+        if edge <= fees { return Ok(StrategyAction::Hold); }
+
+        // Scale in with how much of the expected edge survives fees.
+        let net_ratio = ((edge - fees) / edge).clamp(0.0, 1.0);
+        let size_usd = self.max_size_usd * net_ratio;
+        info!(id = self.id(), token = %token, "{:?} PERP/{:?} SPOT signal: basis {:.4}%, annualised funding {:.2}%, edge {:.4}% vs fees {:.4}%, size ${:.0}",
+            perp_side, perp_side.opposite(), basis * 100.0, annualised_funding_pct, edge * 100.0, fees * 100.0, size_usd);
+        Ok(StrategyAction::ExecuteGroup(Self::legs(&token, perp_side, size_usd, net_ratio)))
+    }
+
+    /// The hedge is held once any entry leg is left open and released once every leg is flat;
+    /// an exit that was unwound leaves it open to be retried on a later event.
+    async fn on_group_result(&mut self, legs: &[OrderLeg], result: GroupResult<'_>) {
+        let Some(first) = legs.first() else { return };
+        let token = first.details.token_address.clone();
+        let exit = first.closes.is_some();
+        let open: Vec<Option<LegPosition>> = match result {
+            GroupResult::Filled(positions) => positions.iter().cloned().map(Some).collect(),
+            GroupResult::Unwound => {
+                info!(id = self.id(), token = %token, exit, "hedge group unwound, position unchanged");
+                return;
+            }
+            GroupResult::Stranded(open) => open.to_vec(),
+        };
+        let opened = |instrument| legs.iter().zip(&open)
+            .find(|(leg, _)| leg.instrument == instrument)
+            .and_then(|(_, position)| position.clone());
+
+        if exit {
+            // A close left standing made its leg flat; the other legs were unwound or never filled.
+            let Some(hedge) = self.open_hedges.get_mut(&token) else { return };
+            for (leg, position) in legs.iter().zip(&open) {
+                let filled = matches!(result, GroupResult::Filled(_)) || position.is_some();
+                if !filled { continue; }
+                match leg.instrument {
+                    Instrument::Spot => hedge.spot = None,
+                    Instrument::Perp => hedge.perp = None,
                 }
             }
+            if hedge.is_flat() {
+                self.open_hedges.remove(&token);
+                info!(id = self.id(), token = %token, "hedge closed");
+            } else {
+                info!(id = self.id(), token = %token, "hedge partially closed");
+            }
+            return;
         }
-        Ok(StrategyAction::Hold)
+
+        let Some(perp_leg) = legs.iter().find(|leg| leg.instrument == Instrument::Perp) else { return };
+        let hedge = OpenHedge {
+            perp_side: perp_leg.details.side.clone(),
+            size_usd: perp_leg.details.suggested_size_usd,
+            spot: opened(Instrument::Spot),
+            perp: opened(Instrument::Perp),
+        };
+        if hedge.is_flat() { return; }
+        info!(id = self.id(), token = %token, complete = hedge.is_complete(), "hedge opened");
+        self.open_hedges.insert(token, hedge);
     }
 }
 register_strategy!(PerpBasisArb, "perp_basis_arb");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_models::{FundingEvent, PerpMarkEvent, PriceTick};
+
+    const TOKEN: &str = "So11111111111111111111111111111111111111112";
+
+    async fn strategy() -> PerpBasisArb {
+        let mut s = PerpBasisArb::default();
+        s.init(&serde_json::json!({
+            "entry_basis_pct": 0.5, "exit_basis_pct": 0.1, "round_trip_fee_bps": 40.0,
+            "holding_hours": 24.0, "funding_periods_per_year": 8760.0, "max_size_usd": 1000.0,
+        })).await.unwrap();
+        s
+    }
+
+    fn price(p: f64) -> MarketEvent {
+        MarketEvent::Price(PriceTick { token_address: TOKEN.into(), price_usd: p, volume_usd_1m: 0.0 })
+    }
+    fn mark(p: f64) -> MarketEvent {
+        MarketEvent::PerpMark(PerpMarkEvent { token_address: TOKEN.into(), mark_price_usd: p, oracle_price_usd: p })
+    }
+    fn funding(rate_pct: f64) -> MarketEvent {
+        MarketEvent::Funding(FundingEvent { token_address: TOKEN.into(), funding_rate_pct: rate_pct, next_funding_time_sec: 0 })
+    }
+
+    async fn feed(s: &mut PerpBasisArb, events: &[MarketEvent]) -> StrategyAction {
+        let mut action = StrategyAction::Hold;
+        for event in events { action = s.on_event(event).await.unwrap(); }
+        action
+    }
+
+    fn group(action: StrategyAction) -> Vec<OrderLeg> {
+        match action {
+            StrategyAction::ExecuteGroup(legs) => legs,
+            other => panic!("expected a group, got {other:?}"),
+        }
+    }
+
+    fn position(amount_raw: u64) -> LegPosition { LegPosition { amount_raw, short_venue: None } }
+
+    #[test]
+    fn edge_adds_funding_received_over_the_horizon() {
+        let s = PerpBasisArb { holding_hours: 24.0, funding_periods_per_year: 8760.0, ..Default::default() };
+        // 24 hourly periods at 0.01% each: 0.24% carry for the short, paid by the long.
+        assert!((s.expected_edge(0.01, 0.01, &Side::Short) - 0.0124).abs() < 1e-12);
+        assert!((s.expected_edge(-0.01, 0.01, &Side::Long) - 0.0076).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn waits_for_a_funding_reading_before_entering() {
+        let mut s = strategy().await;
+        assert!(matches!(feed(&mut s, &[price(100.0), mark(101.0)]).await, StrategyAction::Hold));
+        assert!(matches!(feed(&mut s, &[funding(0.0)]).await, StrategyAction::ExecuteGroup(_)));
+    }
+
+    #[tokio::test]
+    async fn positive_basis_shorts_the_perp_sized_by_net_ratio() {
+        let mut s = strategy().await;
+        let legs = group(feed(&mut s, &[funding(0.0), price(100.0), mark(101.0)]).await);
+
+        assert_eq!(legs.len(), 2);
+        assert_eq!((legs[0].instrument, &legs[0].details.side), (Instrument::Spot, &Side::Long));
+        assert_eq!((legs[1].instrument, &legs[1].details.side), (Instrument::Perp, &Side::Short));
+        // Edge 1% against 0.4% fees keeps 60% of the edge, so 60% of max size.
+        assert!((legs[1].details.suggested_size_usd - 600.0).abs() < 1e-6);
+        assert!((legs[1].details.confidence - 0.6).abs() < 1e-9);
+        assert!(legs.iter().all(|leg| leg.closes.is_none()));
+    }
+
+    #[tokio::test]
+    async fn negative_basis_longs_the_perp_and_shorts_spot() {
+        let mut s = strategy().await;
+        let legs = group(feed(&mut s, &[funding(0.0), price(100.0), mark(99.0)]).await);
+        assert_eq!(&legs[0].details.side, &Side::Short);
+        assert_eq!(&legs[1].details.side, &Side::Long);
+    }
+
+    #[tokio::test]
+    async fn holds_below_entry_basis_or_when_carry_eats_the_edge() {
+        let mut s = strategy().await;
+        assert!(matches!(feed(&mut s, &[funding(0.0), price(100.0), mark(100.4)]).await, StrategyAction::Hold));
+        // Shorting the perp at 1% basis, but paying 0.03% an hour for 24 hours: edge 0.28% < 0.4% fees.
+        assert!(matches!(feed(&mut s, &[funding(-0.03), mark(101.0)]).await, StrategyAction::Hold));
+    }
+
+    #[tokio::test]
+    async fn filled_entry_opens_the_hedge_and_converged_basis_closes_it() {
+        let mut s = strategy().await;
+        let entry = group(feed(&mut s, &[funding(0.0), price(100.0), mark(101.0)]).await);
+        s.on_group_result(&entry, GroupResult::Filled(&[position(5), position(7)])).await;
+        // Still wide: hold, no second entry.
+        assert!(matches!(feed(&mut s, &[mark(100.9)]).await, StrategyAction::Hold));
+
+        let exit = group(feed(&mut s, &[mark(100.05)]).await);
+        assert_eq!(exit.len(), 2);
+        assert_eq!(exit[0].closes, Some(position(5)));
+        assert_eq!(exit[1].closes, Some(position(7)));
+        assert_eq!(&exit[1].details.side, &Side::Long);
+
+        s.on_group_result(&exit, GroupResult::Filled(&[position(5), position(7)])).await;
+        assert!(s.open_hedges.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unwound_groups_leave_the_position_unchanged() {
+        let mut s = strategy().await;
+        let entry = group(feed(&mut s, &[funding(0.0), price(100.0), mark(101.0)]).await);
+        s.on_group_result(&entry, GroupResult::Unwound).await;
+        assert!(s.open_hedges.is_empty());
+
+        s.on_group_result(&entry, GroupResult::Filled(&[position(5), position(7)])).await;
+        let exit = group(feed(&mut s, &[mark(100.05)]).await);
+        s.on_group_result(&exit, GroupResult::Unwound).await;
+        assert!(s.open_hedges[TOKEN].is_complete());
+    }
+
+    #[tokio::test]
+    async fn stranded_entry_leg_is_closed_on_the_next_event() {
+        let mut s = strategy().await;
+        let entry = group(feed(&mut s, &[funding(0.0), price(100.0), mark(101.0)]).await);
+        s.on_group_result(&entry, GroupResult::Stranded(&[Some(position(5)), None])).await;
+
+        // The basis is still wide, but an unhedged spot leg is closed rather than held.
+        let exit = group(feed(&mut s, &[mark(101.0)]).await);
+        assert_eq!(exit.len(), 1);
+        assert_eq!((exit[0].instrument, exit[0].closes.clone()), (Instrument::Spot, Some(position(5))));
+
+        s.on_group_result(&exit, GroupResult::Filled(&[position(5)])).await;
+        assert!(s.open_hedges.is_empty());
+    }
+
+    #[tokio::test]
+    async fn stranded_exit_keeps_only_the_legs_still_open() {
+        let mut s = strategy().await;
+        let entry = group(feed(&mut s, &[funding(0.0), price(100.0), mark(101.0)]).await);
+        s.on_group_result(&entry, GroupResult::Filled(&[position(5), position(7)])).await;
+        let exit = group(feed(&mut s, &[mark(100.05)]).await);
+
+        // The spot close filled and could not be reversed; the perp close failed.
+        s.on_group_result(&exit, GroupResult::Stranded(&[Some(position(5)), None])).await;
+        let hedge = &s.open_hedges[TOKEN];
+        assert_eq!((hedge.spot.clone(), hedge.perp.clone()), (None, Some(position(7))));
+
+        let retry = group(feed(&mut s, &[mark(100.05)]).await);
+        assert_eq!(retry.len(), 1);
+        assert_eq!(retry[0].instrument, Instrument::Perp);
+    }
+}
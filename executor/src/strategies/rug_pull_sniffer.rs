@@ -1,4 +1,4 @@
-use crate::{register_strategy, strategies::Strategy};
+use crate::strategies::Strategy;
 use anyhow::Result;
 use async_trait::async_trait;
 use serde_json::Value;
@@ -1,4 +1,4 @@
-use crate::{register_strategy, strategies::Strategy};
+use crate::strategies::Strategy;
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::Deserialize;
@@ -60,7 +60,7 @@ impl Strategy for SocialBuzz {
                 return Ok(StrategyAction::Execute(OrderDetails {
                     token_address: mention.token_address.clone(),
                     suggested_size_usd: 750.0,
-                    confidence: sentiment_score,
+                    confidence: mention.sentiment.clamp(0.0, 1.0),
                     side: Side::Long,
                 }));
             }
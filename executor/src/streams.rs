@@ -0,0 +1,59 @@
+// executor/src/streams.rs
+use redis::{streams::{StreamReadOptions, StreamReadReply}, AsyncCommands};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, Receiver};
+use tracing::{error, warn};
+
+/// How long one XREAD blocks before it is reissued.
+const BLOCK_MS: usize = 5_000;
+/// Entries fetched per stream per XREAD.
+const BATCH: usize = 256;
+/// Wait before reconnecting after Redis dropped the connection.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Tails the data consumers' Redis Streams from their current end and yields
+/// `(stream, event JSON)` for every entry, in order per stream. Producers write the JSON
+/// into each entry's `event` field.
+pub fn tail(client: redis::Client, streams: &[&'static str]) -> Receiver<(String, String)> {
+    let streams = streams.to_vec();
+    let (tx, rx) = mpsc::channel(1024);
+    tokio::spawn(async move {
+        // Last delivered id per stream; a reconnect resumes after it rather than at the end.
+        let mut last_ids = vec!["$".to_string(); streams.len()];
+        let options = StreamReadOptions::default().block(BLOCK_MS).count(BATCH);
+        loop {
+            let mut conn = match client.get_multiplexed_async_connection().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    error!(%e, "Redis stream connection failed");
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    continue;
+                }
+            };
+            loop {
+                let reply: StreamReadReply = match conn.xread_options(&streams, &last_ids, &options).await {
+                    Ok(reply) => reply,
+                    Err(e) => {
+                        warn!(%e, "XREAD failed, reconnecting");
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                        break;
+                    }
+                };
+                for key in reply.keys {
+                    let Some(index) = streams.iter().position(|s| *s == key.key) else { continue };
+                    for entry in key.ids {
+                        last_ids[index] = entry.id.clone();
+                        let Some(event) = entry.get::<String>("event") else {
+                            warn!(stream = %key.key, id = %entry.id, "stream entry has no event field");
+                            continue;
+                        };
+                        if tx.send((key.key.clone(), event)).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    });
+    rx
+}
@@ -0,0 +1,112 @@
+// executor/src/tips.rs
+use crate::config::CONFIG;
+use anyhow::{ensure, Context, Result};
+use shared_models::JitoTipFloorEvent;
+use solana_sdk::pubkey::Pubkey;
+use std::{str::FromStr, sync::Arc};
+use tokio::sync::Mutex;
+use tracing::info;
+
+/// Jito mainnet tip accounts; one is picked at random per bundle to spread write locks.
+const TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+pub fn tip_account() -> Pubkey {
+    let index = rand::random::<usize>() % TIP_ACCOUNTS.len();
+    Pubkey::from_str(TIP_ACCOUNTS[index]).unwrap()
+}
+
+/// Sizes the Jito tip per order: aim for a landed-tip percentile, but never pay more than a
+/// fraction of the order's expected edge, within fixed min/max bounds.
+pub struct TipPolicy {
+    /// Share of the expected edge we are willing to tip away.
+    pub edge_fraction: f64,
+    /// Edge assumed for a full-confidence signal, in bps of order size.
+    pub assumed_edge_bps: f64,
+    /// Landed-tip percentile to target: 25, 50, 75, 95 or 99.
+    pub percentile: u8,
+    pub min_lamports: u64,
+    pub max_lamports: u64,
+    floor: Mutex<Option<JitoTipFloorEvent>>,
+    sol_price: Arc<Mutex<f64>>,
+}
+
+impl TipPolicy {
+    pub fn from_config(sol_price: Arc<Mutex<f64>>) -> Result<Self> {
+        let floor = match &CONFIG.jito_tip_floor_path {
+            Some(path) => {
+                let raw = std::fs::read_to_string(path).with_context(|| format!("Failed to read tip floor file {}", path))?;
+                Some(serde_json::from_str::<JitoTipFloorEvent>(&raw).with_context(|| format!("Invalid tip floor file {}", path))?)
+            }
+            None => None,
+        };
+        Ok(Self {
+            edge_fraction: CONFIG.tip_edge_fraction,
+            assumed_edge_bps: CONFIG.tip_assumed_edge_bps,
+            percentile: CONFIG.tip_percentile,
+            min_lamports: CONFIG.tip_min_lamports,
+            max_lamports: CONFIG.tip_max_lamports,
+            floor: Mutex::new(floor),
+            sol_price,
+        })
+    }
+
+    /// Replaces the landed-tip percentiles, e.g. from `events:jito_tip_floor`.
+    pub async fn update_floor(&self, floor: JitoTipFloorEvent) {
+        info!(p50 = floor.p50_lamports, p75 = floor.p75_lamports, "Jito tip floor updated");
+        *self.floor.lock().await = Some(floor);
+    }
+
+    /// Tip for an order of `size_usd` at signal `confidence`. Without percentile data the static
+    /// `JITO_TIP_LAMPORTS` is the target. Fails without a SOL/USD price to convert the edge cap.
+    pub async fn tip_for(&self, size_usd: f64, confidence: f64) -> Result<u64> {
+        let target = match &*self.floor.lock().await {
+            Some(floor) => floor.percentile(self.percentile),
+            None => CONFIG.jito_tip_lamports,
+        };
+        let sol_usd = *self.sol_price.lock().await;
+        ensure!(sol_usd > 0.0, "no SOL/USD price yet");
+        let edge_usd = size_usd * self.assumed_edge_bps / 10_000.0 * confidence.clamp(0.0, 1.0);
+        let edge_cap = (edge_usd * self.edge_fraction / sol_usd * 1e9) as u64;
+        Ok(target.min(edge_cap).clamp(self.min_lamports, self.max_lamports))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(sol_usd: f64) -> TipPolicy {
+        TipPolicy {
+            edge_fraction: 0.1,
+            assumed_edge_bps: 100.0,
+            percentile: 50,
+            min_lamports: 1_000,
+            max_lamports: 1_000_000,
+            floor: Mutex::new(Some(JitoTipFloorEvent { p25_lamports: 10_000, p50_lamports: 50_000, p75_lamports: 100_000, p95_lamports: 500_000, p99_lamports: 900_000 })),
+            sol_price: Arc::new(Mutex::new(sol_usd)),
+        }
+    }
+
+    #[tokio::test]
+    async fn tip_is_capped_by_edge() {
+        // $100 at 100 bps edge is $1; a tenth of it at $100/SOL is 1_000_000 lamports, above the p50 target.
+        assert_eq!(policy(100.0).tip_for(100.0, 1.0).await.unwrap(), 50_000);
+        // $1 order: edge cap 10_000 lamports.
+        assert_eq!(policy(100.0).tip_for(1.0, 1.0).await.unwrap(), 10_000);
+    }
+
+    #[tokio::test]
+    async fn tip_needs_a_sol_price() {
+        assert!(policy(0.0).tip_for(100.0, 1.0).await.is_err());
+        assert!(policy(-1.0).tip_for(100.0, 1.0).await.is_err());
+    }
+}
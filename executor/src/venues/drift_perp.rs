@@ -0,0 +1,180 @@
+// executor/src/venues/drift_perp.rs
+use super::{BuiltOrder, Confirmation, ExecutionVenue, SubmitPath, Submitted, TxSubmitter, VenueOrder, VenueQuote};
+use crate::{
+    config::CONFIG,
+    confirmation::{ConfirmationPolicy, Fill},
+    drift::{limit_price, DriftInstructions, PerpPosition},
+    drift_markets::{PerpMarket, PerpMarkBook, PerpMarketRegistry},
+    simulation::Simulation,
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use shared_models::Side;
+use std::{sync::Arc, time::{Duration, Instant}};
+use tracing::warn;
+
+/// Perp orders on the Drift market registered for the token. Orders are built here as unsigned
+/// transactions and signed by the signer like any other, so its policy covers perps too.
+pub struct DriftPerpVenue {
+    instructions: DriftInstructions,
+    marks: PerpMarkBook,
+    perp_markets: Arc<PerpMarketRegistry>,
+    submitter: Arc<TxSubmitter>,
+}
+
+impl DriftPerpVenue {
+    pub fn new(instructions: DriftInstructions, marks: PerpMarkBook, perp_markets: Arc<PerpMarketRegistry>, submitter: Arc<TxSubmitter>) -> Self {
+        Self { instructions, marks, perp_markets, submitter }
+    }
+}
+
+/// Base-asset units to trade: exactly what is being closed, otherwise `size_usd` at `price`.
+fn base_amount(order: &VenueOrder, market: &PerpMarket, price: f64) -> Result<u64> {
+    match order.amount_raw {
+        Some(raw) => Ok(raw),
+        None => market.base_asset_amount(order.size_usd, price),
+    }
+}
+
+/// What moved between two positions in the order's direction, priced from the quote paid or
+/// received, fees included.
+pub(super) fn fill_between(side: &Side, before: &PerpPosition, after: &PerpPosition, base_precision: u64) -> Fill {
+    let moved = after.base_asset_amount.saturating_sub(before.base_asset_amount);
+    let amount_raw = match side { Side::Long => moved, Side::Short => -moved }.max(0) as u64;
+    let size_usd = after.quote_asset_amount.abs_diff(before.quote_asset_amount) as f64 / 1e6;
+    let price_usd = if amount_raw == 0 { 0.0 } else { size_usd / (amount_raw as f64 / base_precision as f64) };
+    Fill { amount_raw, price_usd, size_usd }
+}
+
+#[async_trait]
+impl ExecutionVenue for DriftPerpVenue {
+    fn name(&self) -> &'static str { "drift_perp" }
+
+    async fn quote(&self, order: &VenueOrder) -> Result<VenueQuote> {
+        // Never proxy through SOL-PERP: the token must have its own market.
+        let market = self.perp_markets.get(&order.token_address)
+            .ok_or_else(|| anyhow!("no Drift perp market for {}", order.token_address))?;
+        let price = self.marks.oracle_price(&order.token_address, Duration::from_secs(CONFIG.perp_mark_max_age_secs)).await
+            .ok_or_else(|| anyhow!("no fresh Drift oracle price for {}", market.symbol))?;
+        let amount_raw = base_amount(order, market, price)?;
+        Ok(VenueQuote { price_usd: price, size_usd: order.size_usd, amount_raw, route: Some(market.symbol.clone()), swap_quote: None })
+    }
+
+    async fn build(&self, order: &VenueOrder, quote: &VenueQuote) -> Result<BuiltOrder> {
+        let market = self.perp_markets.get(&order.token_address)
+            .ok_or_else(|| anyhow!("no Drift perp market for {}", order.token_address))?;
+        let authority = self.submitter.pubkey(&order.signer_key).await?;
+        let price = limit_price(&order.side, quote.price_usd, order.slippage_bps)?;
+        let tx_b64 = self.instructions
+            .place_perp_order(&authority, market.market_index, &order.side, quote.amount_raw, price, order.reduce_only)
+            .await?;
+        Ok(BuiltOrder::Transactions { transactions_b64: vec![tx_b64], swap_index: None, tip_lamports: order.tip_lamports, path: order.submit_path, signer_key: order.signer_key.clone() })
+    }
+
+    async fn simulate(&self, built: &BuiltOrder, quote: &VenueQuote) -> Result<Option<Simulation>> {
+        self.submitter.simulate(built, quote).await
+    }
+
+    async fn submit(&self, built: BuiltOrder) -> Result<Submitted> {
+        let BuiltOrder::Transactions { transactions_b64, tip_lamports, path, signer_key, .. } = built;
+        self.submitter.send_all(&transactions_b64, tip_lamports, path, &signer_key).await
+    }
+
+    async fn confirm(&self, signature: &str) -> Result<Confirmation> {
+        self.submitter.status(signature).await
+    }
+
+    async fn cancel(&self, order: &VenueOrder, _signature: &str) -> Result<()> {
+        // Cancels every order still resting on the user account, not just this one.
+        let authority = self.submitter.pubkey(&order.signer_key).await?;
+        let tx_b64 = self.instructions.cancel_orders(&authority).await?;
+        self.submitter.send_all(&[tx_b64], CONFIG.jito_tip_lamports, SubmitPath::from_config(), &order.signer_key).await?;
+        Ok(())
+    }
+
+    async fn position(&self, order: &VenueOrder) -> Result<Option<PerpPosition>> {
+        let market = self.perp_markets.get(&order.token_address)
+            .ok_or_else(|| anyhow!("no Drift perp market for {}", order.token_address))?;
+        let authority = self.submitter.pubkey(&order.signer_key).await?;
+        Ok(Some(self.instructions.perp_position(&authority, market.market_index).await?))
+    }
+
+    /// A landed order is only resting: keepers fill it over the auction. Waits for it to leave
+    /// the book, cancelling it once after the confirmation timeout.
+    async fn fill_since(&self, order: &VenueOrder, before: &PerpPosition) -> Result<Fill> {
+        let market = self.perp_markets.get(&order.token_address)
+            .ok_or_else(|| anyhow!("no Drift perp market for {}", order.token_address))?;
+        let authority = self.submitter.pubkey(&order.signer_key).await?;
+        let policy = ConfirmationPolicy::from_config();
+        let mut started = Instant::now();
+        let mut cancelled = false;
+        loop {
+            let after = self.instructions.perp_position(&authority, market.market_index).await?;
+            let done = after.open_orders <= before.open_orders;
+            if done || (cancelled && started.elapsed() >= policy.max_wait) {
+                if !done {
+                    warn!(market = %market.symbol, "perp order still open after cancelling; recording the fill so far");
+                }
+                return Ok(fill_between(&order.side, before, &after, market.base_precision));
+            }
+            if !cancelled && started.elapsed() >= policy.max_wait {
+                self.cancel(order, "").await?;
+                cancelled = true;
+                started = Instant::now();
+            }
+            tokio::time::sleep(policy.poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_models::Instrument;
+
+    fn market() -> PerpMarket {
+        PerpMarket { mint: "TOKEN".to_string(), market_index: 7, symbol: "TOKEN-PERP".to_string(), base_precision: 1_000_000_000 }
+    }
+
+    fn order(amount_raw: Option<u64>) -> VenueOrder {
+        VenueOrder {
+            instrument: Instrument::Perp,
+            token_address: "TOKEN".to_string(),
+            side: Side::Long,
+            size_usd: 100.0,
+            amount_raw,
+            reduce_only: amount_raw.is_some(),
+            all_or_none: false,
+            short_venue: None,
+            slippage_bps: 50,
+            tip_lamports: 0,
+            submit_path: SubmitPath::Jito,
+            signer_key: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn opening_order_is_sized_from_usd() {
+        assert_eq!(base_amount(&order(None), &market(), 4.0).unwrap(), 25_000_000_000);
+        assert!(base_amount(&order(None), &market(), 0.0).is_err());
+    }
+
+    #[test]
+    fn fill_is_the_position_change_in_the_order_direction() {
+        let before = PerpPosition { base_asset_amount: -1_000_000_000, quote_asset_amount: 150_000_000, open_orders: 0 };
+        // Shorted 2 more at $150 less a $0.30 fee.
+        let after = PerpPosition { base_asset_amount: -3_000_000_000, quote_asset_amount: 449_700_000, open_orders: 0 };
+        let fill = fill_between(&Side::Short, &before, &after, 1_000_000_000);
+        assert_eq!(fill.amount_raw, 2_000_000_000);
+        assert!((fill.size_usd - 299.7).abs() < 1e-9 && (fill.price_usd - 149.85).abs() < 1e-9);
+        // A long order does not count a position that only moved short.
+        assert_eq!(fill_between(&Side::Long, &before, &after, 1_000_000_000).amount_raw, 0);
+        assert_eq!(fill_between(&Side::Short, &before, &before, 1_000_000_000).price_usd, 0.0);
+    }
+
+    #[test]
+    fn reduce_only_closes_exactly_what_was_filled() {
+        // The price has moved since the fill: the close still trades the filled amount.
+        assert_eq!(base_amount(&order(Some(25_000_000_000)), &market(), 5.0).unwrap(), 25_000_000_000);
+    }
+}
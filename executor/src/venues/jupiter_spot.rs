@@ -0,0 +1,60 @@
+// executor/src/venues/jupiter_spot.rs
+use super::{BuiltOrder, Confirmation, ExecutionVenue, Submitted, TxSubmitter, VenueOrder, VenueQuote};
+use crate::{jupiter::{JupiterClient, SOL_MINT}, simulation::Simulation};
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Spot buys routed through Jupiter and landed via Jito.
+pub struct JupiterSpotVenue {
+    jupiter: Arc<JupiterClient>,
+    submitter: Arc<TxSubmitter>,
+}
+
+impl JupiterSpotVenue {
+    pub fn new(jupiter: Arc<JupiterClient>, submitter: Arc<TxSubmitter>) -> Self {
+        Self { jupiter, submitter }
+    }
+}
+
+#[async_trait]
+impl ExecutionVenue for JupiterSpotVenue {
+    fn name(&self) -> &'static str { "jupiter_spot" }
+
+    async fn quote(&self, order: &VenueOrder) -> Result<VenueQuote> {
+        let lamports = self.jupiter.usd_to_lamports(order.size_usd).await?;
+        let quote = self.jupiter.get_quote(SOL_MINT, &order.token_address, lamports, order.slippage_bps).await?;
+        quote.validate()?;
+        Ok(VenueQuote {
+            price_usd: self.jupiter.price_usd(&quote).await?,
+            size_usd: order.size_usd,
+            amount_raw: quote.out_amount()?,
+            route: None,
+            swap_quote: Some(quote),
+        })
+    }
+
+    async fn build(&self, order: &VenueOrder, quote: &VenueQuote) -> Result<BuiltOrder> {
+        let swap_quote = quote.swap_quote.as_ref().ok_or_else(|| anyhow!("{} quote carries no Jupiter route", self.name()))?;
+        let user_pk = self.submitter.pubkey(&order.signer_key).await?;
+        let swap_b64 = self.jupiter.swap_transaction(&user_pk, swap_quote).await?;
+        Ok(BuiltOrder::Transactions { transactions_b64: vec![swap_b64], swap_index: Some(0), tip_lamports: order.tip_lamports, path: order.submit_path, signer_key: order.signer_key.clone() })
+    }
+
+    async fn simulate(&self, built: &BuiltOrder, quote: &VenueQuote) -> Result<Option<Simulation>> {
+        self.submitter.simulate(built, quote).await
+    }
+
+    async fn submit(&self, built: BuiltOrder) -> Result<Submitted> {
+        let BuiltOrder::Transactions { transactions_b64, tip_lamports, path, signer_key, .. } = built;
+        self.submitter.send_all(&transactions_b64, tip_lamports, path, &signer_key).await
+    }
+
+    async fn confirm(&self, signature: &str) -> Result<Confirmation> {
+        self.submitter.status(signature).await
+    }
+
+    async fn cancel(&self, _order: &VenueOrder, signature: &str) -> Result<()> {
+        bail!("swap {} cannot be recalled once submitted", signature)
+    }
+}
@@ -0,0 +1,208 @@
+// executor/src/venues/mock.rs
+use super::{drift_perp::fill_between, BuiltOrder, Confirmation, ExecutionVenue, SubmitPath, Submitted, VenueOrder, VenueQuote};
+use crate::{confirmation::Fill, drift::PerpPosition};
+#[cfg(test)]
+use crate::{jupiter::JupiterQuote, short_venue::{ShortPlan, ShortVenue}};
+#[cfg(test)]
+use solana_sdk::pubkey::Pubkey;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use shared_models::{Instrument, Side};
+use std::{collections::{HashMap, VecDeque}, sync::Mutex};
+
+/// Offline venue: quotes at a fixed price and settles each submission with the next scripted
+/// outcome, `Confirmed` once the script is empty. A `Pending` order stays pending until cancelled.
+/// Perp orders rest like Drift's and move one position by the next scripted fill, in full by default.
+/// Selected with `MOCK_VENUES=true` to run the full order lifecycle without any network.
+pub struct MockVenue {
+    price_usd: f64,
+    script: Mutex<VecDeque<Confirmation>>,
+    orders: Mutex<HashMap<String, Confirmation>>,
+    built: Mutex<Vec<VenueOrder>>,
+    fills: Mutex<VecDeque<u64>>,
+    /// Signed base amount the last built perp order fills once it lands.
+    placing: Mutex<Option<i64>>,
+    position: Mutex<PerpPosition>,
+}
+
+impl MockVenue {
+    pub fn new(price_usd: f64) -> Self {
+        Self {
+            price_usd,
+            script: Mutex::new(VecDeque::new()),
+            orders: Mutex::new(HashMap::new()),
+            built: Mutex::new(vec![]),
+            fills: Mutex::new(VecDeque::new()),
+            placing: Mutex::new(None),
+            position: Mutex::new(PerpPosition::default()),
+        }
+    }
+
+    /// Queues how the next submissions settle, in order.
+    #[cfg(test)]
+    pub fn script(&self, outcomes: impl IntoIterator<Item = Confirmation>) {
+        self.script.lock().unwrap().extend(outcomes);
+    }
+
+    /// Queues how much the next landed perp orders fill, in order.
+    #[cfg(test)]
+    pub fn script_fills(&self, amounts_raw: impl IntoIterator<Item = u64>) {
+        self.fills.lock().unwrap().extend(amounts_raw);
+    }
+
+    /// Every order built so far, oldest first.
+    #[cfg(test)]
+    pub fn built(&self) -> Vec<VenueOrder> {
+        self.built.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl ExecutionVenue for MockVenue {
+    fn name(&self) -> &'static str { "mock" }
+
+    async fn quote(&self, order: &VenueOrder) -> Result<VenueQuote> {
+        let amount_raw = order.amount_raw.unwrap_or((order.size_usd / self.price_usd * 1e6) as u64);
+        Ok(VenueQuote { price_usd: self.price_usd, size_usd: order.size_usd, amount_raw, route: Some(self.name().to_string()), swap_quote: None })
+    }
+
+    async fn build(&self, order: &VenueOrder, quote: &VenueQuote) -> Result<BuiltOrder> {
+        self.built.lock().unwrap().push(order.clone());
+        if order.instrument == Instrument::Perp {
+            let filled = self.fills.lock().unwrap().pop_front().unwrap_or(quote.amount_raw) as i64;
+            *self.placing.lock().unwrap() = Some(if order.side == Side::Long { filled } else { -filled });
+        }
+        Ok(BuiltOrder::Transactions { transactions_b64: vec![], swap_index: None, tip_lamports: 0, path: SubmitPath::Jito, signer_key: order.signer_key.clone() })
+    }
+
+    async fn submit(&self, _built: BuiltOrder) -> Result<Submitted> {
+        let outcome = self.script.lock().unwrap().pop_front().unwrap_or(Confirmation::Confirmed);
+        let mut orders = self.orders.lock().unwrap();
+        let signature = format!("mock-{}", orders.len() + 1);
+        let placing = self.placing.lock().unwrap().take();
+        if let (Some(filled), Confirmation::Confirmed) = (placing, &outcome) {
+            let mut position = self.position.lock().unwrap();
+            position.base_asset_amount += filled;
+            position.quote_asset_amount -= (filled as f64 * self.price_usd) as i64;
+        }
+        orders.insert(signature.clone(), outcome);
+        Ok(Submitted::direct(signature, "mock"))
+    }
+
+    async fn confirm(&self, signature: &str) -> Result<Confirmation> {
+        self.orders.lock().unwrap().get(signature).cloned()
+            .ok_or_else(|| anyhow!("unknown mock order {}", signature))
+    }
+
+    async fn cancel(&self, _order: &VenueOrder, signature: &str) -> Result<()> {
+        let mut orders = self.orders.lock().unwrap();
+        match orders.get(signature) {
+            Some(Confirmation::Pending) => {
+                orders.insert(signature.to_string(), Confirmation::Failed("cancelled".to_string()));
+                Ok(())
+            }
+            Some(_) => Err(anyhow!("mock order {} already settled", signature)),
+            None => Err(anyhow!("unknown mock order {}", signature)),
+        }
+    }
+
+    async fn position(&self, order: &VenueOrder) -> Result<Option<PerpPosition>> {
+        Ok((order.instrument == Instrument::Perp).then(|| *self.position.lock().unwrap()))
+    }
+
+    async fn fill_since(&self, order: &VenueOrder, before: &PerpPosition) -> Result<Fill> {
+        Ok(fill_between(&order.side, before, &self.position.lock().unwrap(), 1_000_000))
+    }
+}
+
+/// Short venue with a fixed capacity per token, for exercising venue selection.
+#[cfg(test)]
+pub struct MockShortVenue {
+    pub name: &'static str,
+    pub capacity_raw: u64,
+}
+
+#[cfg(test)]
+#[async_trait]
+impl ShortVenue for MockShortVenue {
+    fn name(&self) -> &'static str { self.name }
+
+    async fn capacity(&self, _signer_key: &str, _token: &str, wanted_raw: u64) -> Result<u64> {
+        Ok(self.capacity_raw.min(wanted_raw))
+    }
+
+    async fn open(&self, _user: &Pubkey, _token: &str, _sell_quote: &JupiterQuote) -> Result<ShortPlan> {
+        Ok(ShortPlan { transactions_b64: vec![], swap_index: 0 })
+    }
+
+    async fn cover(&self, _user: &Pubkey, _token: &str, _amount_raw: u64, _buy_quote: &JupiterQuote) -> Result<ShortPlan> {
+        Ok(ShortPlan { transactions_b64: vec![], swap_index: 0 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{confirmation::{ConfirmationPolicy, ConfirmationTracker}, rpc::StubRpc};
+    use std::{sync::Arc, time::Duration};
+
+    fn order() -> VenueOrder {
+        VenueOrder {
+            instrument: Instrument::Spot,
+            token_address: "token".to_string(),
+            side: Side::Long,
+            size_usd: 50.0,
+            amount_raw: None,
+            reduce_only: false,
+            all_or_none: false,
+            short_venue: None,
+            slippage_bps: 50,
+            tip_lamports: 0,
+            submit_path: SubmitPath::Jito,
+            signer_key: "momentum".to_string(),
+        }
+    }
+
+    fn tracker() -> ConfirmationTracker {
+        let policy = ConfirmationPolicy { poll_interval: Duration::from_millis(1), max_wait: Duration::from_millis(20) };
+        ConfirmationTracker::new(Arc::new(StubRpc::default()), Arc::new(tokio::sync::Mutex::new(100.0)), policy)
+    }
+
+    /// quote → build → submit → confirm, as `submit_and_confirm` drives it.
+    async fn run(venue: &MockVenue) -> (String, Confirmation) {
+        let order = order();
+        let quote = venue.quote(&order).await.unwrap();
+        assert_eq!(quote.amount_raw, 25_000_000);
+        let built = venue.build(&order, &quote).await.unwrap();
+        let signature = venue.submit(built).await.unwrap().signature;
+        let outcome = tracker().wait(venue, &signature).await.unwrap();
+        (signature, outcome)
+    }
+
+    #[tokio::test]
+    async fn lifecycle_confirms_by_default() {
+        let venue = MockVenue::new(2.0);
+        assert_eq!(run(&venue).await.1, Confirmation::Confirmed);
+        assert_eq!(venue.built().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn lifecycle_follows_script() {
+        let venue = MockVenue::new(2.0);
+        venue.script([Confirmation::Failed("0x1771".to_string()), Confirmation::Expired]);
+        assert_eq!(run(&venue).await.1, Confirmation::Failed("0x1771".to_string()));
+        assert_eq!(run(&venue).await.1, Confirmation::Expired);
+        assert_eq!(run(&venue).await.1, Confirmation::Confirmed);
+    }
+
+    #[tokio::test]
+    async fn pending_order_times_out_and_can_be_cancelled() {
+        let venue = MockVenue::new(2.0);
+        venue.script([Confirmation::Pending]);
+        let (signature, outcome) = run(&venue).await;
+        assert_eq!(outcome, Confirmation::Pending);
+        venue.cancel(&order(), &signature).await.unwrap();
+        assert_eq!(venue.confirm(&signature).await.unwrap(), Confirmation::Failed("cancelled".to_string()));
+        assert!(venue.cancel(&order(), &signature).await.is_err(), "a settled order cannot be cancelled again");
+    }
+}
@@ -0,0 +1,473 @@
+// executor/src/venues/mod.rs
+use crate::{
+    config::CONFIG,
+    confirmation::Fill,
+    drift::PerpPosition,
+    drift_markets::PerpMarketRegistry,
+    fees::FeePolicy,
+    jito::{self, JitoClient},
+    jupiter::{self, JupiterQuote},
+    rpc::SolanaRpc,
+    signer_client::SignerClient,
+    simulation::{Preflight, Simulation},
+    tips,
+};
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use shared_models::{Instrument, OrderLeg, Side};
+use solana_sdk::{
+    hash::Hash,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    system_instruction,
+    transaction::VersionedTransaction,
+};
+use tracing::{info, warn};
+use std::{collections::HashMap, str::FromStr, sync::{Arc, Mutex}};
+
+pub mod drift_perp;
+pub mod jupiter_spot;
+pub mod mock;
+pub mod spot_short;
+
+/// A venue-agnostic order, derived from a strategy leg or from reversing a fill.
+#[derive(Debug, Clone)]
+pub struct VenueOrder {
+    pub instrument: Instrument,
+    pub token_address: String,
+    pub side: Side,
+    pub size_usd: f64,
+    /// Exact raw amount to trade, set when reversing a known fill.
+    pub amount_raw: Option<u64>,
+    pub reduce_only: bool,
+    /// Set for group legs: the venue takes the whole order or fails, since a smaller leg
+    /// would no longer match its hedge.
+    pub all_or_none: bool,
+    /// Set when covering a spot short: the short venue that opened it.
+    pub short_venue: Option<String>,
+    /// Widened by the retry policy after slippage failures.
+    pub slippage_bps: u16,
+    /// Jito tip for the whole order, set from the tip policy before building.
+    pub tip_lamports: u64,
+    pub submit_path: SubmitPath,
+    /// Signer key of the strategy placing the order; its wallet pays for and signs everything.
+    pub signer_key: String,
+}
+
+impl VenueOrder {
+    pub fn from_leg(leg: &OrderLeg, size_usd: f64, signer_key: &str) -> Self {
+        Self {
+            instrument: leg.instrument,
+            token_address: leg.details.token_address.clone(),
+            side: leg.details.side.clone(),
+            size_usd,
+            amount_raw: leg.closes.as_ref().map(|p| p.amount_raw),
+            reduce_only: leg.closes.is_some(),
+            all_or_none: false,
+            short_venue: leg.closes.as_ref().and_then(|p| p.short_venue.clone()),
+            slippage_bps: CONFIG.slippage_bps,
+            tip_lamports: CONFIG.jito_tip_lamports,
+            submit_path: SubmitPath::from_config(),
+            signer_key: signer_key.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VenueQuote {
+    pub price_usd: f64,
+    /// The size the venue can actually take, which may be below the requested size.
+    pub size_usd: f64,
+    /// Expected fill in raw units: tokens bought or sold for spot, base-asset units for perps.
+    pub amount_raw: u64,
+    /// Venue-specific routing decision, e.g. which short venue was selected.
+    pub route: Option<String>,
+    /// The Jupiter quote the swap will be built from, for swap-based venues.
+    pub swap_quote: Option<JupiterQuote>,
+}
+
+/// How signed transactions reach the cluster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmitPath {
+    /// Jito block engine, paying a tip.
+    Jito,
+    /// Plain RPC `sendTransaction`, paying a priority fee.
+    Rpc,
+    /// Jito, falling back to RPC: from scratch if preparing the Jito submission failed, with the
+    /// same signed transaction if sending it did.
+    JitoThenRpc,
+}
+
+impl SubmitPath {
+    pub fn from_config() -> Self {
+        match CONFIG.submit_path.as_str() {
+            "rpc" => SubmitPath::Rpc,
+            "jito_then_rpc" => SubmitPath::JitoThenRpc,
+            _ => SubmitPath::Jito,
+        }
+    }
+}
+
+pub enum BuiltOrder {
+    /// Unsigned base64 transactions, sent in order, with the tip for all of them and how to send them.
+    /// `swap_index` is the transaction executing the quote's swap, if any; `signer_key` signs them all.
+    Transactions { transactions_b64: Vec<String>, swap_index: Option<usize>, tip_lamports: u64, path: SubmitPath, signer_key: String },
+}
+
+/// A submitted order and what landing it costs on top of base fees.
+#[derive(Debug, Clone)]
+pub struct Submitted {
+    /// Identifies the order: the last transaction sent.
+    pub signature: String,
+    /// "jito", "rpc", or the venue's own client.
+    pub path: &'static str,
+    pub tip_lamports: u64,
+    /// Tip paid inside the identifying transaction itself, so already in its balance delta.
+    pub tip_in_tx_lamports: u64,
+    pub priority_fee_lamports: u64,
+}
+
+impl Submitted {
+    /// An order sent by a venue's own client, with no tip or priority fee of ours.
+    pub fn direct(signature: String, path: &'static str) -> Self {
+        Self { signature, path, tip_lamports: 0, tip_in_tx_lamports: 0, priority_fee_lamports: 0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Confirmation {
+    Pending,
+    Confirmed,
+    Failed(String),
+    Expired,
+}
+
+/// One place orders can be executed. The lifecycle is quote → build → submit → confirm,
+/// with cancel as a best-effort abort.
+#[async_trait]
+pub trait ExecutionVenue: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn quote(&self, order: &VenueOrder) -> Result<VenueQuote>;
+    async fn build(&self, order: &VenueOrder, quote: &VenueQuote) -> Result<BuiltOrder>;
+    /// Pre-flight check of a built order before anything is signed. `None` when the venue cannot simulate.
+    async fn simulate(&self, _built: &BuiltOrder, _quote: &VenueQuote) -> Result<Option<Simulation>> {
+        Ok(None)
+    }
+    async fn submit(&self, built: BuiltOrder) -> Result<Submitted>;
+    async fn confirm(&self, signature: &str) -> Result<Confirmation>;
+    async fn cancel(&self, order: &VenueOrder, signature: &str) -> Result<()>;
+    /// The order's market position, for venues whose landed transaction only places the order.
+    /// `None` when the landed transaction is itself the fill.
+    async fn position(&self, _order: &VenueOrder) -> Result<Option<PerpPosition>> {
+        Ok(None)
+    }
+    /// What a landed order filled since the position was `before`, once it stops working; any
+    /// unfilled remainder is cancelled.
+    async fn fill_since(&self, _order: &VenueOrder, _before: &PerpPosition) -> Result<Fill> {
+        Err(anyhow!("{} does not place resting orders", self.name()))
+    }
+}
+
+/// The transaction to simulate and the quote its output must meet, if any.
+fn simulation_target<'a>(built: &'a BuiltOrder, quote: &'a VenueQuote) -> Result<Option<(&'a str, Option<&'a JupiterQuote>)>> {
+    let BuiltOrder::Transactions { transactions_b64, swap_index, .. } = built;
+    if transactions_b64.is_empty() {
+        return Ok(None);
+    }
+    let index = swap_index.unwrap_or(0);
+    let tx = transactions_b64.get(index)
+        .ok_or_else(|| anyhow!("swap index {} out of range for {} transactions", index, transactions_b64.len()))?;
+    Ok(Some((tx.as_str(), swap_index.and(quote.swap_quote.as_ref()))))
+}
+
+/// Transactions signed for Jito, not yet sent.
+struct JitoBatch {
+    /// The order's transactions, followed by the tip transfer when bundled.
+    signed: Vec<VersionedTransaction>,
+    blockhash: Hash,
+    bundled: bool,
+    /// Tip carried inside each of the order's transactions.
+    tip_in_tx_lamports: u64,
+}
+
+impl JitoBatch {
+    /// The order's own transactions, without a bundle's tip transfer.
+    fn order(&self) -> &[VersionedTransaction] {
+        if self.bundled { &self.signed[..self.signed.len() - 1] } else { &self.signed }
+    }
+}
+
+/// Signs through the isolated signer and lands transactions via Jito or plain RPC.
+pub struct TxSubmitter {
+    jito: Arc<JitoClient>,
+    signer: Arc<SignerClient>,
+    rpc: Arc<dyn SolanaRpc>,
+    fees: FeePolicy,
+    preflight: Preflight,
+    /// Blockhash each signature was sent with, to tell "not landed yet" from "can never land".
+    blockhashes: Mutex<HashMap<String, Hash>>,
+}
+
+impl TxSubmitter {
+    pub fn new(jito: Arc<JitoClient>, signer: Arc<SignerClient>, rpc: Arc<dyn SolanaRpc>) -> Self {
+        Self {
+            jito,
+            signer,
+            fees: FeePolicy::from_config(rpc.clone()),
+            preflight: Preflight::new(rpc.clone()),
+            rpc,
+            blockhashes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Simulates the transaction carrying the swap, checking its output against the quote's
+    /// minimum, or the first transaction when the order has no swap.
+    pub async fn simulate(&self, built: &BuiltOrder, quote: &VenueQuote) -> Result<Option<Simulation>> {
+        let Some((tx, expected)) = simulation_target(built, quote)? else { return Ok(None) };
+        Ok(Some(self.preflight.check(tx, expected).await?))
+    }
+
+    async fn sign(&self, tx: &VersionedTransaction, key: &str) -> Result<VersionedTransaction> {
+        let signed_b64 = self.signer.sign_transaction(&B64.encode(bincode::serialize(tx)?), key).await?;
+        jupiter::deserialize_transaction(&signed_b64)
+    }
+
+    /// Stamps `blockhash` (and an inline tip, if given) onto an unsigned transaction, then signs it.
+    /// Everything that changes the message happens before signing.
+    async fn prepare(&self, unsigned_b64: &str, blockhash: Hash, tip_lamports: Option<u64>, key: &str) -> Result<VersionedTransaction> {
+        let mut tx = jupiter::deserialize_transaction(unsigned_b64)?;
+        tx.message.set_recent_blockhash(blockhash);
+        if let Some(tip) = tip_lamports {
+            jito::attach_tip(&mut tx, tip)?;
+        }
+        self.sign(&tx, key).await
+    }
+
+    /// An unsigned SOL transfer of `tip_lamports` from `key`'s wallet to a Jito tip account.
+    async fn tip_transaction(&self, tip_lamports: u64, blockhash: Hash, key: &str) -> Result<String> {
+        let payer = self.signer.pubkey(key).await?;
+        let ix = system_instruction::transfer(&payer, &tips::tip_account(), tip_lamports);
+        let message = v0::Message::try_compile(&payer, &[ix], &[], blockhash)?;
+        let tx = VersionedTransaction { signatures: vec![Signature::default()], message: VersionedMessage::V0(message) };
+        Ok(B64.encode(bincode::serialize(&tx)?))
+    }
+
+    /// The wallet orders signed by `key` are built for.
+    pub async fn pubkey(&self, key: &str) -> Result<Pubkey> {
+        self.signer.pubkey(key).await
+    }
+
+    /// Signs the transactions with `key` and sends them in order along `path`. A sequence of more than
+    /// one transaction only goes out as a Jito bundle, since on its own the first could land without the rest.
+    pub async fn send_all(&self, transactions_b64: &[String], tip_lamports: u64, path: SubmitPath, key: &str) -> Result<Submitted> {
+        if transactions_b64.is_empty() {
+            bail!("venue produced no transactions");
+        }
+        if transactions_b64.len() > 1 {
+            if !CONFIG.jito_bundles || path == SubmitPath::Rpc {
+                bail!("{} dependent transactions need an atomic Jito bundle (JITO_BUNDLES=true and a Jito submit path)", transactions_b64.len());
+            }
+            let batch = self.sign_jito(transactions_b64, tip_lamports, key).await?;
+            return self.send_jito(&batch, tip_lamports).await;
+        }
+        match path {
+            SubmitPath::Jito => {
+                let batch = self.sign_jito(transactions_b64, tip_lamports, key).await?;
+                self.send_jito(&batch, tip_lamports).await
+            }
+            SubmitPath::Rpc => self.send_rpc(transactions_b64, key).await,
+            SubmitPath::JitoThenRpc => {
+                // Nothing has left while the batch is being prepared, so RPC can start over from scratch.
+                let batch = match self.sign_jito(transactions_b64, tip_lamports, key).await {
+                    Ok(batch) => batch,
+                    Err(e) => {
+                        warn!(%e, "Jito submission could not be prepared, falling back to RPC");
+                        return self.send_rpc(transactions_b64, key).await;
+                    }
+                };
+                match self.send_jito(&batch, tip_lamports).await {
+                    Ok(submitted) => Ok(submitted),
+                    // The block engine may have taken the transaction before the error reached us. Resending
+                    // the same signed transaction means both copies share one signature, so only one can land.
+                    Err(e) => {
+                        warn!(%e, "Jito send failed, resending the same signed transaction over RPC");
+                        self.resend_rpc(&batch).await
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fetches a blockhash and signs everything the order sends through Jito. With `JITO_BUNDLES`
+    /// the batch ends in the tip transfer, so nothing lands unless everything does; otherwise each
+    /// transaction carries an equal share of the tip inline. Nothing is sent yet.
+    async fn sign_jito(&self, transactions_b64: &[String], tip_lamports: u64, key: &str) -> Result<JitoBatch> {
+        let blockhash = self.rpc.latest_blockhash().await?;
+        let mut signed = Vec::with_capacity(transactions_b64.len() + 1);
+        if CONFIG.jito_bundles {
+            for tx_b64 in transactions_b64 {
+                signed.push(self.prepare(tx_b64, blockhash, None, key).await?);
+            }
+            let tip_tx = self.tip_transaction(tip_lamports, blockhash, key).await?;
+            signed.push(self.prepare(&tip_tx, blockhash, None, key).await?);
+            Ok(JitoBatch { signed, blockhash, bundled: true, tip_in_tx_lamports: 0 })
+        } else {
+            let tip_share = tip_lamports / transactions_b64.len() as u64;
+            for tx_b64 in transactions_b64 {
+                signed.push(self.prepare(tx_b64, blockhash, Some(tip_share), key).await?);
+            }
+            Ok(JitoBatch { signed, blockhash, bundled: false, tip_in_tx_lamports: tip_share })
+        }
+    }
+
+    /// Sends a signed batch to the block engine, as one bundle or transaction by transaction.
+    async fn send_jito(&self, batch: &JitoBatch, tip_lamports: u64) -> Result<Submitted> {
+        if batch.bundled {
+            let bundle_id = self.jito.send_bundle(&batch.signed).await?;
+            info!(%bundle_id, txs = batch.signed.len(), tip_lamports, "bundle submitted");
+        } else {
+            for tx in &batch.signed {
+                self.jito.send_transaction(tx).await?;
+            }
+        }
+        let signature = self.track(batch.order(), batch.blockhash)?;
+        Ok(Submitted { signature, path: "jito", tip_lamports, tip_in_tx_lamports: batch.tip_in_tx_lamports, priority_fee_lamports: 0 })
+    }
+
+    /// Sends the order's transactions exactly as signed for Jito over plain RPC: same blockhash,
+    /// same inline tip if any, no priority fee.
+    async fn resend_rpc(&self, batch: &JitoBatch) -> Result<Submitted> {
+        for tx in batch.order() {
+            self.rpc.send_transaction(tx).await?;
+        }
+        let signature = self.track(batch.order(), batch.blockhash)?;
+        info!(%signature, "resent via RPC");
+        let tip = batch.tip_in_tx_lamports;
+        Ok(Submitted { signature, path: "rpc", tip_lamports: tip, tip_in_tx_lamports: tip, priority_fee_lamports: 0 })
+    }
+
+    /// Sends over plain RPC with the fee policy's compute budget instead of a tip.
+    async fn send_rpc(&self, transactions_b64: &[String], key: &str) -> Result<Submitted> {
+        let bh = self.rpc.latest_blockhash().await?;
+        let mut signed = Vec::with_capacity(transactions_b64.len());
+        let mut priority_fee_lamports = 0;
+        for tx_b64 in transactions_b64 {
+            let mut tx = jupiter::deserialize_transaction(tx_b64)?;
+            tx.message.set_recent_blockhash(bh);
+            let budget = self.fees.apply(&mut tx.message).await?;
+            priority_fee_lamports += budget.priority_fee_lamports();
+            let tx = self.sign(&tx, key).await?;
+            self.rpc.send_transaction(&tx).await?;
+            signed.push(tx);
+        }
+        let signature = self.track(&signed, bh)?;
+        info!(%signature, txs = signed.len(), priority_fee_lamports, "sent via RPC");
+        Ok(Submitted { signature, path: "rpc", tip_lamports: 0, tip_in_tx_lamports: 0, priority_fee_lamports })
+    }
+
+    /// Remembers the blockhash of the order's last transaction and returns its signature.
+    fn track(&self, signed: &[VersionedTransaction], blockhash: Hash) -> Result<String> {
+        let signature = signed.last().map(|tx| tx.signatures[0].to_string()).ok_or_else(|| anyhow!("nothing was signed"))?;
+        self.blockhashes.lock().unwrap().insert(signature.clone(), blockhash);
+        Ok(signature)
+    }
+
+    pub async fn status(&self, signature: &str) -> Result<Confirmation> {
+        let sig = Signature::from_str(signature)?;
+        let status = match self.rpc.signature_status(&sig).await? {
+            None => {
+                let blockhash = self.blockhashes.lock().unwrap().get(signature).copied();
+                match blockhash {
+                    Some(bh) if !self.rpc.is_blockhash_valid(&bh).await? => Confirmation::Expired,
+                    _ => return Ok(Confirmation::Pending),
+                }
+            }
+            Some(Ok(())) => Confirmation::Confirmed,
+            Some(Err(e)) => Confirmation::Failed(e),
+        };
+        self.blockhashes.lock().unwrap().remove(signature);
+        Ok(status)
+    }
+}
+
+/// Chooses the venue for an order from its instrument, side, token and config.
+pub struct OrderRouter {
+    jupiter_spot: Arc<dyn ExecutionVenue>,
+    drift_perp: Arc<dyn ExecutionVenue>,
+    spot_short: Arc<dyn ExecutionVenue>,
+    mock: Option<Arc<dyn ExecutionVenue>>,
+    perp_markets: Arc<PerpMarketRegistry>,
+}
+
+impl OrderRouter {
+    pub fn new(
+        jupiter_spot: Arc<dyn ExecutionVenue>,
+        drift_perp: Arc<dyn ExecutionVenue>,
+        spot_short: Arc<dyn ExecutionVenue>,
+        mock: Option<Arc<dyn ExecutionVenue>>,
+        perp_markets: Arc<PerpMarketRegistry>,
+    ) -> Self {
+        Self { jupiter_spot, drift_perp, spot_short, mock, perp_markets }
+    }
+
+    pub fn has_perp(&self, token: &str) -> bool {
+        self.perp_markets.get(token).is_some()
+    }
+
+    pub fn route(&self, order: &VenueOrder) -> Arc<dyn ExecutionVenue> {
+        if let Some(mock) = &self.mock {
+            return mock.clone();
+        }
+        match (order.instrument, &order.side) {
+            (Instrument::Perp, _) => self.drift_perp.clone(),
+            (Instrument::Spot, Side::Short) => self.spot_short.clone(),
+            (Instrument::Spot, Side::Long) if order.short_venue.is_some() => self.spot_short.clone(),
+            (Instrument::Spot, Side::Long) => self.jupiter_spot.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote() -> VenueQuote {
+        let swap: JupiterQuote = serde_json::from_value(serde_json::json!({
+            "inputMint": "token", "inAmount": "100", "outputMint": jupiter::SOL_MINT, "outAmount": "50",
+            "otherAmountThreshold": "49", "swapMode": "ExactIn", "slippageBps": 50, "priceImpactPct": "0", "routePlan": [],
+        })).unwrap();
+        VenueQuote { price_usd: 1.0, size_usd: 1.0, amount_raw: 100, route: None, swap_quote: Some(swap) }
+    }
+
+    fn built(txs: &[&str], swap_index: Option<usize>) -> BuiltOrder {
+        BuiltOrder::Transactions { transactions_b64: txs.iter().map(|t| t.to_string()).collect(), swap_index, tip_lamports: 0, path: SubmitPath::Jito, signer_key: "test".to_string() }
+    }
+
+    #[test]
+    fn simulates_the_swap_with_its_minimum() {
+        let quote = quote();
+        // Borrow then sell: the sell is the swap.
+        let built = built(&["borrow", "sell"], Some(1));
+        let (tx, expected) = simulation_target(&built, &quote).unwrap().unwrap();
+        assert_eq!(tx, "sell");
+        assert_eq!(expected.unwrap().other_amount_threshold, "49");
+    }
+
+    #[test]
+    fn without_a_swap_simulates_the_first_unchecked() {
+        let quote = quote();
+        let built = built(&["perp"], None);
+        let (tx, expected) = simulation_target(&built, &quote).unwrap().unwrap();
+        assert_eq!(tx, "perp");
+        assert!(expected.is_none());
+    }
+
+    #[test]
+    fn empty_or_out_of_range_orders() {
+        let quote = quote();
+        assert!(simulation_target(&built(&[], None), &quote).unwrap().is_none());
+        assert!(simulation_target(&built(&["buy"], Some(1)), &quote).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+// executor/src/venues/spot_short.rs
+use super::{BuiltOrder, Confirmation, ExecutionVenue, Submitted, TxSubmitter, VenueOrder, VenueQuote};
+use crate::{jupiter::{JupiterClient, SOL_MINT}, short_venue::{self, ShortVenue}, simulation::Simulation};
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use shared_models::Side;
+use std::sync::Arc;
+
+/// Extra bought on cover so rounding and fees do not leave the short venue owed.
+const COVER_BUFFER: f64 = 1.01;
+
+/// Spot shorts for tokens without a perp, opened and covered through the configured short venues.
+pub struct SpotShortVenue {
+    short_venues: Arc<Vec<Box<dyn ShortVenue>>>,
+    jupiter: Arc<JupiterClient>,
+    submitter: Arc<TxSubmitter>,
+}
+
+impl SpotShortVenue {
+    pub fn new(
+        short_venues: Arc<Vec<Box<dyn ShortVenue>>>,
+        jupiter: Arc<JupiterClient>,
+        submitter: Arc<TxSubmitter>,
+    ) -> Self {
+        Self { short_venues, jupiter, submitter }
+    }
+
+    fn venue(&self, name: &str) -> Result<&dyn ShortVenue> {
+        self.short_venues.iter().find(|v| v.name() == name).map(|v| v.as_ref())
+            .ok_or_else(|| anyhow!("short venue {} is no longer configured", name))
+    }
+}
+
+/// SOL to spend on a cover: the short's value plus the buffer.
+fn cover_lamports(lamports: u64) -> u64 {
+    (lamports as f64 * COVER_BUFFER) as u64
+}
+
+/// The venue to sell through and how much it sells. Shrinks to what the venue can actually
+/// sell (e.g. sell to flat), unless the order is all-or-none. Closing a long sells what we
+/// hold and must never borrow.
+async fn sell_venue<'a>(venues: &'a [Box<dyn ShortVenue>], order: &VenueOrder, wanted_raw: u64) -> Result<(&'a dyn ShortVenue, u64)> {
+    if !order.reduce_only {
+        return short_venue::select(venues, &order.signer_key, &order.token_address, wanted_raw, order.all_or_none).await;
+    }
+    let inventory = venues.iter().find(|v| v.name() == "inventory").map(|v| v.as_ref())
+        .ok_or_else(|| anyhow!("short venue inventory is no longer configured"))?;
+    match inventory.capacity(&order.signer_key, &order.token_address, wanted_raw).await? {
+        0 => bail!("no inventory of {} left to sell", order.token_address),
+        held if order.all_or_none && held < wanted_raw => {
+            bail!("only {} of {} {} left to sell, and the order is all-or-none", held, wanted_raw, order.token_address)
+        }
+        held => Ok((inventory, held)),
+    }
+}
+
+#[async_trait]
+impl ExecutionVenue for SpotShortVenue {
+    fn name(&self) -> &'static str { "spot_short" }
+
+    async fn quote(&self, order: &VenueOrder) -> Result<VenueQuote> {
+        let lamports = self.jupiter.usd_to_lamports(order.size_usd).await?;
+
+        if order.side == Side::Long {
+            // Covering: the venue that opened the short has to settle it. Buy with a small
+            // buffer so rounding and fees do not leave the venue short.
+            let name = order.short_venue.clone().ok_or_else(|| anyhow!("cover order without a short venue"))?;
+            let buy_lamports = cover_lamports(lamports);
+            let buy = self.jupiter.get_quote(SOL_MINT, &order.token_address, buy_lamports, order.slippage_bps).await?;
+            buy.validate()?;
+            return Ok(VenueQuote {
+                price_usd: self.jupiter.price_usd(&buy).await?,
+                size_usd: order.size_usd,
+                amount_raw: order.amount_raw.unwrap_or(buy.out_amount()?),
+                route: Some(name),
+                swap_quote: Some(buy),
+            });
+        }
+
+        // Size the short by what `size_usd` of SOL buys, unless the amount is already known.
+        let wanted_raw = match order.amount_raw {
+            Some(raw) => raw,
+            None => self.jupiter.get_quote(SOL_MINT, &order.token_address, lamports, order.slippage_bps).await?.out_amount()?,
+        };
+        let (venue, amount_raw) = sell_venue(&self.short_venues, order, wanted_raw).await?;
+        let sell = self.jupiter.get_quote(&order.token_address, SOL_MINT, amount_raw, order.slippage_bps).await?;
+        sell.validate()?;
+        Ok(VenueQuote {
+            price_usd: self.jupiter.price_usd(&sell).await?,
+            size_usd: order.size_usd * amount_raw as f64 / wanted_raw as f64,
+            amount_raw,
+            route: Some(venue.name().to_string()),
+            swap_quote: Some(sell),
+        })
+    }
+
+    async fn build(&self, order: &VenueOrder, quote: &VenueQuote) -> Result<BuiltOrder> {
+        let name = quote.route.as_deref().ok_or_else(|| anyhow!("spot short quote has no venue"))?;
+        let venue = self.venue(name)?;
+        let swap_quote = quote.swap_quote.as_ref().ok_or_else(|| anyhow!("spot short quote carries no Jupiter route"))?;
+        let user_pk = self.submitter.pubkey(&order.signer_key).await?;
+        let plan = match order.side {
+            Side::Short => venue.open(&user_pk, &order.token_address, swap_quote).await?,
+            Side::Long => venue.cover(&user_pk, &order.token_address, quote.amount_raw, swap_quote).await?,
+        };
+        Ok(BuiltOrder::Transactions { transactions_b64: plan.transactions_b64, swap_index: Some(plan.swap_index), tip_lamports: order.tip_lamports, path: order.submit_path, signer_key: order.signer_key.clone() })
+    }
+
+    async fn simulate(&self, built: &BuiltOrder, quote: &VenueQuote) -> Result<Option<Simulation>> {
+        self.submitter.simulate(built, quote).await
+    }
+
+    async fn submit(&self, built: BuiltOrder) -> Result<Submitted> {
+        let BuiltOrder::Transactions { transactions_b64, tip_lamports, path, signer_key, .. } = built;
+        self.submitter.send_all(&transactions_b64, tip_lamports, path, &signer_key).await
+    }
+
+    async fn confirm(&self, signature: &str) -> Result<Confirmation> {
+        self.submitter.status(signature).await
+    }
+
+    async fn cancel(&self, _order: &VenueOrder, signature: &str) -> Result<()> {
+        bail!("spot short {} cannot be recalled once submitted", signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::venues::{mock::MockShortVenue, SubmitPath};
+    use shared_models::Instrument;
+
+    fn venues(capacities: &[(&'static str, u64)]) -> Vec<Box<dyn ShortVenue>> {
+        capacities.iter().map(|&(name, capacity_raw)| Box::new(MockShortVenue { name, capacity_raw }) as Box<dyn ShortVenue>).collect()
+    }
+
+    fn order(reduce_only: bool, all_or_none: bool) -> VenueOrder {
+        VenueOrder {
+            instrument: Instrument::Spot,
+            token_address: "TOKEN".to_string(),
+            side: Side::Short,
+            size_usd: 100.0,
+            amount_raw: None,
+            reduce_only,
+            all_or_none,
+            short_venue: None,
+            slippage_bps: 50,
+            tip_lamports: 0,
+            submit_path: SubmitPath::Jito,
+            signer_key: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn cover_buys_one_percent_extra() {
+        assert_eq!(cover_lamports(1_000_000_000), 1_010_000_000);
+        assert_eq!(cover_lamports(0), 0);
+    }
+
+    #[tokio::test]
+    async fn opening_short_sells_to_venue_capacity() {
+        let venues = venues(&[("inventory", 300), ("lending_borrow", 1_000)]);
+        let (venue, amount) = sell_venue(&venues, &order(false, false), 1_000).await.unwrap();
+        assert_eq!((venue.name(), amount), ("inventory", 300));
+    }
+
+    #[tokio::test]
+    async fn group_short_never_shrinks() {
+        let venues = venues(&[("inventory", 300), ("lending_borrow", 1_000)]);
+        let (venue, amount) = sell_venue(&venues, &order(false, true), 1_000).await.unwrap();
+        assert_eq!((venue.name(), amount), ("lending_borrow", 1_000));
+
+        let venues = self::venues(&[("inventory", 300), ("lending_borrow", 600)]);
+        assert!(sell_venue(&venues, &order(false, true), 1_000).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reduce_only_sells_inventory_and_never_borrows() {
+        let venues = venues(&[("lending_borrow", 1_000), ("inventory", 300)]);
+        let (venue, amount) = sell_venue(&venues, &order(true, false), 1_000).await.unwrap();
+        assert_eq!((venue.name(), amount), ("inventory", 300));
+
+        assert!(sell_venue(&venues, &order(true, true), 1_000).await.is_err(), "a group close must not sell less than its hedge");
+        let empty = self::venues(&[("lending_borrow", 1_000), ("inventory", 0)]);
+        assert!(sell_venue(&empty, &order(true, false), 1_000).await.is_err());
+    }
+}
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 use std::time::Duration;
 use tracing::{info, warn, level_filters::LevelFilter};
 use tracing_subscriber::EnvFilter;
-use statrs::statistics::{Mean, StandardDeviation};
+use statrs::statistics::Statistics;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -21,7 +21,7 @@ async fn main() -> Result<()> {
 
     loop {
         info!("Allocator loop starting...");
-        let mut conn = match client.get_async_connection().await {
+        let mut conn = match client.get_multiplexed_async_connection().await {
             Ok(c) => c,
             Err(e) => {
                 warn!("Failed to connect to Redis: {}. Retrying in 10s.", e);
@@ -62,8 +62,8 @@ async fn main() -> Result<()> {
                 .collect();
 
             if pnl_values.len() > 1 {
-                let mean_pnl = pnl_values.mean();
-                let std_dev_pnl = pnl_values.std_dev();
+                let mean_pnl = pnl_values.iter().mean();
+                let std_dev_pnl = pnl_values.iter().std_dev();
                 
                 // Calculate Sharpe Ratio (simplified: uses mean PnL as excess return, std dev as risk)
                 // A true Sharpe would use daily returns and risk-free rate
@@ -86,7 +86,7 @@ async fn main() -> Result<()> {
 
         let mut allocations: Vec<StrategyAllocation> = Vec::new();
         let mut total_sharpe_for_weighting = 0.0;
-        for (i, spec) in sorted_strategies.iter().enumerate() {
+        for spec in sorted_strategies.iter() {
             let (_, sharpe) = strategy_metrics.get(&spec.id).unwrap_or(&(0.0, 0.0));
             // Only consider positive Sharpe ratios for weighting, or a small base weight for new strategies
             let weight_factor = sharpe.max(0.1); // Give a floor to new/low-sharpe strategies
@@ -101,16 +101,16 @@ async fn main() -> Result<()> {
                 1.0 / specs.len() as f64 // Fallback if no positive sharpe sum
             };
             
-            allocations.push(StrategyAllocation { id: spec.id.clone(), weight, sharpe_ratio: *sharpe });
+            allocations.push(StrategyAllocation { id: spec.id.clone(), weight, sharpe_ratio: *sharpe, params: spec.params.clone() });
         }
 
         info!("Publishing {} allocations with dynamic Sharpe-based weights.", allocations.len());
         let payload = serde_json::to_string(&allocations)?;
         
         // Store current allocations for dashboard
-        conn.set("active_allocations", &payload).await?; 
+        conn.set::<_, _, ()>("active_allocations", &payload).await?; 
         // Publish to executor
-        if let Err(e) = conn.publish("allocations_channel", payload).await {
+        if let Err(e) = conn.publish::<_, _, ()>("allocations_channel", payload).await {
             warn!("Failed to publish allocations: {}.", e);
         }
 
@@ -1,23 +1,30 @@
 //! Common structs used by every service.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
 
 /* ---------- enums ---------- */
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum EventType {
     Price,
     Social,
     Depth,
     Bridge,
     Funding,
+    PerpMark,
     SolPrice,
+    JitoTipFloor,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Side { Long, Short }
 
+impl Side {
+    pub fn opposite(&self) -> Side {
+        match self { Side::Long => Side::Short, Side::Short => Side::Long }
+    }
+}
+
 /* ---------- strategy plumbing ---------- */
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -32,6 +39,9 @@ pub struct StrategyAllocation {
     pub id: String,
     pub weight: f64,
     pub sharpe_ratio: f64,
+    /// The strategy spec's params, passed to `Strategy::init`.
+    #[serde(default)]
+    pub params: serde_json::Value,
 }
 
 /* ---------- market events ---------- */
@@ -47,7 +57,25 @@ pub struct BridgeEvent     { pub token_address: String, pub source_chain: String
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FundingEvent    { pub token_address: String, pub funding_rate_pct: f64, pub next_funding_time_sec: u64 }
 #[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PerpMarkEvent   { pub token_address: String, pub mark_price_usd: f64, pub oracle_price_usd: f64 }
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SolPriceEvent   { pub price_usd: f64 }
+/// Recent landed Jito tips by percentile, in lamports.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JitoTipFloorEvent { pub p25_lamports: u64, pub p50_lamports: u64, pub p75_lamports: u64, pub p95_lamports: u64, pub p99_lamports: u64 }
+
+impl JitoTipFloorEvent {
+    /// The nearest published percentile at or above `p`.
+    pub fn percentile(&self, p: u8) -> u64 {
+        match p {
+            0..=25  => self.p25_lamports,
+            26..=50 => self.p50_lamports,
+            51..=75 => self.p75_lamports,
+            76..=95 => self.p95_lamports,
+            _       => self.p99_lamports,
+        }
+    }
+}
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "type")]
 pub enum MarketEvent {
@@ -56,7 +84,9 @@ pub enum MarketEvent {
     Depth(DepthEvent),
     Bridge(BridgeEvent),
     Funding(FundingEvent),
+    PerpMark(PerpMarkEvent),
     SolPrice(SolPriceEvent),
+    JitoTipFloor(JitoTipFloorEvent),
 }
 
 impl MarketEvent {
@@ -68,15 +98,22 @@ impl MarketEvent {
             Depth(_)      => EventType::Depth,
             Bridge(_)     => EventType::Bridge,
             Funding(_)    => EventType::Funding,
+            PerpMark(_)   => EventType::PerpMark,
             SolPrice(_)   => EventType::SolPrice,
+            JitoTipFloor(_) => EventType::JitoTipFloor,
         }
     }
     /// helper for strategies that need the token symbol quickly
     pub fn token(&self) -> &str {
         use MarketEvent::*;
         match self {
-            Price(e)   | Social(e) | Depth(e) | Bridge(e) | Funding(e) => e.token_address.as_str(),
-            SolPrice(_) => "So11111111111111111111111111111111111111112",
+            Price(e)    => e.token_address.as_str(),
+            Social(e)   => e.token_address.as_str(),
+            Depth(e)    => e.token_address.as_str(),
+            Bridge(e)   => e.token_address.as_str(),
+            Funding(e)  => e.token_address.as_str(),
+            PerpMark(e) => e.token_address.as_str(),
+            SolPrice(_) | JitoTipFloor(_) => "So11111111111111111111111111111111111111112",
         }
     }
 }
@@ -91,15 +128,82 @@ pub struct OrderDetails {
     pub side: Side,
 }
 
+/// Which market a leg trades on. `Spot` routes through Jupiter, `Perp` through Drift.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instrument { Spot, Perp }
+
+/// What a filled leg left open: enough to close it again.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LegPosition {
+    /// Raw token units for spot, base-asset units for perps.
+    pub amount_raw: u64,
+    /// Set for spot shorts: the short venue that has to cover them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub short_venue: Option<String>,
+}
+
+/// One leg of a hedged order group.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OrderLeg {
+    pub instrument: Instrument,
+    pub details: OrderDetails,
+    /// Set when the leg closes an earlier fill instead of opening a position: perps go
+    /// reduce-only, spot shorts are covered through their venue and spot longs sell inventory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closes: Option<LegPosition>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegStatus {
+    Pending,
+    Filled,
+    Failed,
+    Unwound,   // filled, then reversed because a sibling leg failed
+    UnwindFailed, // filled, and still open because reversing it failed
+}
+
+impl LegStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LegStatus::Pending => "PENDING",
+            LegStatus::Filled => "FILLED",
+            LegStatus::Failed => "FAILED",
+            LegStatus::Unwound => "UNWOUND",
+            LegStatus::UnwindFailed => "UNWIND_FAILED",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum StrategyAction {
     Execute(OrderDetails),   // single unified action
+    /// All-or-nothing group: if any leg fails, the executor unwinds the legs that filled.
+    ExecuteGroup(Vec<OrderLeg>),
+    /// The earlier signal on this token no longer holds: stop any of its unsent child orders.
+    Withdraw(String),
     Hold,
 }
 
 /* ---------- signer ---------- */
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct SignRequest  { pub transaction_b64: String }
+pub struct SignRequest {
+    pub transaction_b64: String,
+    /// Which of the signer's named keys to sign with; the signer's `default` key when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct SignResponse { pub signed_transaction_b64: String }
+pub struct SignResponse {
+    pub signed_transaction_b64: String,
+    /// Signature slots the signer filled; any other slot is left as it came in.
+    #[serde(default)]
+    pub signed_slots: Vec<usize>,
+}
+/// Body of every non-2xx signer response. `error` is a stable code, e.g. `policy_rejected`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignerErrorBody {
+    pub error: String,
+    #[serde(default)]
+    pub reasons: Vec<String>,
+}
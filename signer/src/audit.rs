@@ -0,0 +1,244 @@
+// signer/src/audit.rs
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// `prev_hash` of the first entry.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// What one signing request asked for and what the signer did about it.
+#[derive(Debug, Clone, Default)]
+pub struct AuditRecord {
+    pub caller: String,
+    /// Which of the signer's keys was asked for.
+    pub key: Option<String>,
+    /// SHA-256 of the serialized message; `None` when the request never decoded.
+    pub message_hash: Option<String>,
+    pub instructions: Vec<String>,
+    /// `signed`, or the error code returned to the caller.
+    pub decision: String,
+    pub reasons: Vec<String>,
+    pub signature: Option<String>,
+}
+
+/// One line of the log. `hash` covers every other field, `prev_hash` included, so editing or
+/// dropping any line breaks the chain from there on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AuditEntry {
+    seq: u64,
+    time: i64,
+    caller: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    message_hash: Option<String>,
+    instructions: Vec<String>,
+    decision: String,
+    reasons: Vec<String>,
+    signature: Option<String>,
+    prev_hash: String,
+    #[serde(default)]
+    hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> Result<String> {
+        let unhashed = AuditEntry { hash: String::new(), ..self.clone() };
+        Ok(hex::encode(Sha256::digest(serde_json::to_vec(&unhashed)?)))
+    }
+}
+
+struct Head {
+    seq: u64,
+    hash: String,
+    file: File,
+}
+
+/// Append-only, hash-chained JSON-lines log of every signing request.
+pub struct AuditLog {
+    path: PathBuf,
+    head: Mutex<Head>,
+}
+
+impl AuditLog {
+    /// Opens `path` for appending, continuing the chain from its last entry.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let (seq, hash) = match File::open(&path) {
+            Ok(file) => {
+                // An unreadable line could hide the real head of the chain, so refuse to open.
+                let mut last = None;
+                for line in BufReader::new(file).lines() {
+                    let line = line.with_context(|| format!("Failed to read audit log {}", path.display()))?;
+                    if !line.trim().is_empty() {
+                        last = Some(line);
+                    }
+                }
+                match last {
+                    Some(line) => {
+                        let last: AuditEntry = serde_json::from_str(&line).context("Last audit entry is not valid JSON")?;
+                        (last.seq + 1, last.hash)
+                    }
+                    None => (0, GENESIS_HASH.to_string()),
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (0, GENESIS_HASH.to_string()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read audit log {}", path.display())),
+        };
+        let file = OpenOptions::new().create(true).append(true).open(&path)
+            .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+        Ok(Self { path, head: Mutex::new(Head { seq, hash, file }) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the log file can still be opened for appending.
+    pub fn writable(&self) -> bool {
+        OpenOptions::new().append(true).open(&self.path).is_ok()
+    }
+
+    /// Appends and fsyncs one entry. Callers must not sign if this fails.
+    pub fn append(&self, record: AuditRecord) -> Result<()> {
+        let mut head = self.head.lock().map_err(|_| anyhow!("audit log lock poisoned"))?;
+        let mut entry = AuditEntry {
+            seq: head.seq,
+            time: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0),
+            caller: record.caller,
+            key: record.key,
+            message_hash: record.message_hash,
+            instructions: record.instructions,
+            decision: record.decision,
+            reasons: record.reasons,
+            signature: record.signature,
+            prev_hash: head.hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash()?;
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        head.file.write_all(&line)?;
+        head.file.sync_data()?;
+        head.seq += 1;
+        head.hash = entry.hash;
+        Ok(())
+    }
+}
+
+/// SHA-256 of a serialized message, as recorded in the log.
+pub fn message_hash(message_bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(message_bytes))
+}
+
+/// Walks the whole log checking sequence numbers, hashes and links. Returns the entry count.
+pub fn verify(path: &Path) -> Result<u64> {
+    let file = File::open(path).with_context(|| format!("Failed to open audit log {}", path.display()))?;
+    let mut expected_prev = GENESIS_HASH.to_string();
+    let mut count = 0u64;
+    for (n, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: AuditEntry = serde_json::from_str(&line).with_context(|| format!("line {}: not a valid entry", n + 1))?;
+        if entry.seq != count {
+            return Err(anyhow!("line {}: sequence {} where {} was expected", n + 1, entry.seq, count));
+        }
+        if entry.prev_hash != expected_prev {
+            return Err(anyhow!("line {}: chain broken, prev_hash does not match entry {}", n + 1, count.saturating_sub(1)));
+        }
+        if entry.compute_hash()? != entry.hash {
+            return Err(anyhow!("line {}: entry {} was modified", n + 1, entry.seq));
+        }
+        expected_prev = entry.hash;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn record(decision: &str) -> AuditRecord {
+        AuditRecord { caller: "executor".to_string(), decision: decision.to_string(), ..Default::default() }
+    }
+
+    /// A fresh log with three entries, reopened between writes to exercise chain continuation.
+    fn written_log(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("signer_audit_{}_{}.jsonl", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        AuditLog::open(&path).unwrap().append(record("signed")).unwrap();
+        let log = AuditLog::open(&path).unwrap();
+        log.append(record("policy_rejected")).unwrap();
+        log.append(record("signed")).unwrap();
+        path
+    }
+
+    fn lines(path: &Path) -> Vec<String> {
+        fs::read_to_string(path).unwrap().lines().map(str::to_string).collect()
+    }
+
+    fn write_lines(path: &Path, lines: &[String]) {
+        fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    #[test]
+    fn intact_log_verifies() {
+        let path = written_log("intact");
+        assert_eq!(verify(&path).unwrap(), 3);
+    }
+
+    #[test]
+    fn tampered_line_is_detected() {
+        let path = written_log("tampered");
+        let mut lines = lines(&path);
+        lines[1] = lines[1].replace("policy_rejected", "signed");
+        write_lines(&path, &lines);
+        assert_eq!(verify(&path).unwrap_err().to_string(), "line 2: entry 1 was modified");
+    }
+
+    #[test]
+    fn rehashed_tampered_line_breaks_the_chain() {
+        let path = written_log("rehashed");
+        let mut lines = lines(&path);
+        let mut entry: AuditEntry = serde_json::from_str(&lines[1]).unwrap();
+        entry.decision = "signed".to_string();
+        entry.hash = entry.compute_hash().unwrap();
+        lines[1] = serde_json::to_string(&entry).unwrap();
+        write_lines(&path, &lines);
+        assert_eq!(verify(&path).unwrap_err().to_string(), "line 3: chain broken, prev_hash does not match entry 1");
+    }
+
+    #[test]
+    fn reordered_or_dropped_lines_are_detected() {
+        let path = written_log("reordered");
+        let mut lines = lines(&path);
+        lines.swap(1, 2);
+        write_lines(&path, &lines);
+        assert_eq!(verify(&path).unwrap_err().to_string(), "line 2: sequence 2 where 1 was expected");
+
+        lines.swap(1, 2);
+        lines.remove(0);
+        write_lines(&path, &lines);
+        assert_eq!(verify(&path).unwrap_err().to_string(), "line 1: sequence 1 where 0 was expected");
+    }
+
+    #[test]
+    fn unreadable_last_entry_refuses_to_open() {
+        let path = written_log("unreadable");
+        let mut raw = fs::read(&path).unwrap();
+        raw.extend(b"\xff\xfe\n");
+        fs::write(&path, raw).unwrap();
+        let err = AuditLog::open(&path).err().unwrap().to_string();
+        assert!(err.starts_with("Failed to read audit log"), "{}", err);
+    }
+}
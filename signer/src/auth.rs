@@ -0,0 +1,259 @@
+// signer/src/auth.rs
+use crate::{keys::env_suffix, reject, AppState};
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{collections::HashMap, env, sync::Arc, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+pub const KEY_ID_HEADER: &str = "x-signer-key-id";
+pub const TIMESTAMP_HEADER: &str = "x-signer-timestamp";
+pub const NONCE_HEADER: &str = "x-signer-nonce";
+pub const SIGNATURE_HEADER: &str = "x-signer-signature";
+
+/// Largest request body the signer will buffer for authentication.
+const MAX_BODY_BYTES: usize = 1 << 20;
+/// At most one unauthenticated-request warning per interval; the metric counts every one.
+const UNAUTHENTICATED_WARN_INTERVAL: Duration = Duration::from_secs(10);
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Which client key authenticated a request; added as a request extension.
+#[derive(Debug, Clone)]
+pub struct Caller(pub String);
+
+/// HMAC-SHA256 request authentication with replay protection. Each client has a key id and a
+/// shared secret, and signs `METHOD\nPATH\nTIMESTAMP\nNONCE\nBODY`. A request is accepted once,
+/// and only within `max_skew_secs` of the signer's clock. Each client may only sign with the
+/// keys it is scoped to.
+pub struct Authenticator {
+    secrets: HashMap<String, Vec<u8>>,
+    /// Signing keys each client may use; `*` allows any.
+    allowed_keys: HashMap<String, Vec<String>>,
+    max_skew_secs: i64,
+    /// Nonces seen within the skew window, with their timestamps.
+    seen: Mutex<HashMap<String, i64>>,
+    unauthenticated: WarnLimiter,
+}
+
+/// Lets one warning through per interval and counts the ones held back, so a flood of bad
+/// requests cannot flood the logs.
+struct WarnLimiter {
+    interval: Duration,
+    /// When a warning last went through, and how many were held back since.
+    state: std::sync::Mutex<(Option<Instant>, u64)>,
+}
+
+impl WarnLimiter {
+    fn new(interval: Duration) -> Self {
+        Self { interval, state: std::sync::Mutex::new((None, 0)) }
+    }
+
+    /// `Some(held back since the last one)` if a warning may be logged now.
+    fn admit(&self, now: Instant) -> Option<u64> {
+        let mut state = self.state.lock().unwrap();
+        match state.0 {
+            Some(last) if now.duration_since(last) < self.interval => {
+                state.1 += 1;
+                None
+            }
+            _ => {
+                state.0 = Some(now);
+                Some(std::mem::take(&mut state.1))
+            }
+        }
+    }
+}
+
+impl Authenticator {
+    /// `SIGNER_HMAC_KEYS` is a comma-separated list of `key_id=secret`; at least one is required.
+    /// Each key id also needs `SIGNER_CALLER_KEYS_<KEY ID>`, the comma-separated signing keys out of
+    /// `key_names` it may use, or `*` for all of them.
+    pub fn from_env(key_names: &[&str]) -> Result<Self> {
+        let raw = env::var("SIGNER_HMAC_KEYS").context("SIGNER_HMAC_KEYS must be set")?;
+        let mut secrets = HashMap::new();
+        for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, secret) = entry.split_once('=').ok_or_else(|| anyhow!("SIGNER_HMAC_KEYS entries must be key_id=secret"))?;
+            if secret.len() < 32 {
+                return Err(anyhow!("HMAC secret for {} must be at least 32 characters", id));
+            }
+            secrets.insert(id.to_string(), secret.as_bytes().to_vec());
+        }
+        if secrets.is_empty() {
+            return Err(anyhow!("SIGNER_HMAC_KEYS has no keys"));
+        }
+        let mut allowed_keys = HashMap::new();
+        for id in secrets.keys() {
+            let var = format!("SIGNER_CALLER_KEYS_{}", env_suffix(id));
+            let raw = env::var(&var).with_context(|| format!("{} must list the signing keys {} may use", var, id))?;
+            let keys: Vec<String> = raw.split(',').map(str::trim).filter(|k| !k.is_empty()).map(String::from).collect();
+            if let Some(unknown) = keys.iter().find(|k| k.as_str() != "*" && !key_names.contains(&k.as_str())) {
+                return Err(anyhow!("{} names unknown signing key {}", var, unknown));
+            }
+            allowed_keys.insert(id.clone(), keys);
+        }
+        let max_skew_secs = env::var("SIGNER_AUTH_MAX_SKEW_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()
+            .context("SIGNER_AUTH_MAX_SKEW_SECS must be an integer")?;
+        Ok(Self { secrets, allowed_keys, max_skew_secs, seen: Mutex::new(HashMap::new()), unauthenticated: WarnLimiter::new(UNAUTHENTICATED_WARN_INTERVAL) })
+    }
+
+    /// Whether `caller` may sign with the signing key `key`.
+    pub fn may_use(&self, caller: &str, key: &str) -> bool {
+        self.allowed_keys.get(caller).is_some_and(|keys| keys.iter().any(|k| k == "*" || k == key))
+    }
+
+    /// Returns the authenticated key id, or why the request was refused.
+    pub async fn verify(&self, method: &str, path: &str, headers: &HeaderMap, body: &[u8]) -> Result<String, String> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).ok_or_else(|| format!("missing {} header", name));
+        let key_id = header(KEY_ID_HEADER)?;
+        let timestamp = header(TIMESTAMP_HEADER)?;
+        let nonce = header(NONCE_HEADER)?;
+        let signature = hex::decode(header(SIGNATURE_HEADER)?).map_err(|_| "signature is not hex".to_string())?;
+
+        let secret = self.secrets.get(key_id).ok_or_else(|| format!("unknown key id {}", key_id))?;
+        let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts any key length");
+        mac.update(&canonical_prefix(method, path, timestamp, nonce));
+        mac.update(body);
+        mac.verify_slice(&signature).map_err(|_| "bad signature".to_string())?;
+
+        // Only trust the timestamp and nonce once the MAC has covered them.
+        let ts: i64 = timestamp.parse().map_err(|_| "timestamp is not an integer".to_string())?;
+        let now = unix_now();
+        if (now - ts).abs() > self.max_skew_secs {
+            return Err(format!("timestamp {} outside the {}s window", ts, self.max_skew_secs));
+        }
+        let mut seen = self.seen.lock().await;
+        seen.retain(|_, t| now - *t <= self.max_skew_secs);
+        if seen.insert(format!("{}:{}", key_id, nonce), ts).is_some() {
+            return Err("nonce already used".to_string());
+        }
+        Ok(key_id.to_string())
+    }
+}
+
+/// Bytes the MAC covers ahead of the body.
+pub fn canonical_prefix(method: &str, path: &str, timestamp: &str, nonce: &str) -> Vec<u8> {
+    format!("{}\n{}\n{}\n{}\n", method, path, timestamp, nonce).into_bytes()
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+/// Middleware rejecting unauthenticated requests with 401. They are counted, not audited: the
+/// audit log is for what callers asked of the signer, and anyone can send these.
+pub async fn require_hmac(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let bytes = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return reject(StatusCode::PAYLOAD_TOO_LARGE, "body_too_large", vec![]),
+    };
+    match state.auth.verify(parts.method.as_str(), parts.uri.path(), &parts.headers, &bytes).await {
+        Ok(key_id) => {
+            parts.extensions.insert(Caller(key_id));
+            next.run(Request::from_parts(parts, Body::from(bytes))).await
+        }
+        Err(reason) => {
+            state.metrics.rejected("unauthorized");
+            if let Some(suppressed) = state.auth.unauthenticated.admit(Instant::now()) {
+                warn!(path = parts.uri.path(), %reason, suppressed, "Unauthenticated signer request");
+            }
+            reject(StatusCode::UNAUTHORIZED, "unauthorized", vec![reason])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    fn authenticator() -> Authenticator {
+        let secrets = HashMap::from([("executor".to_string(), SECRET.as_bytes().to_vec())]);
+        let allowed_keys = HashMap::from([("executor".to_string(), vec!["momentum".to_string(), "meanrev".to_string()])]);
+        Authenticator { secrets, allowed_keys, max_skew_secs: 30, seen: Mutex::new(HashMap::new()), unauthenticated: WarnLimiter::new(UNAUTHENTICATED_WARN_INTERVAL) }
+    }
+
+    fn headers(secret: &str, timestamp: i64, nonce: &str, body: &[u8]) -> HeaderMap {
+        let timestamp = timestamp.to_string();
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(&canonical_prefix("POST", "/sign", &timestamp, nonce));
+        mac.update(body);
+        let mut headers = HeaderMap::new();
+        headers.insert(KEY_ID_HEADER, HeaderValue::from_static("executor"));
+        headers.insert(TIMESTAMP_HEADER, HeaderValue::from_str(&timestamp).unwrap());
+        headers.insert(NONCE_HEADER, HeaderValue::from_str(nonce).unwrap());
+        headers.insert(SIGNATURE_HEADER, HeaderValue::from_str(&hex::encode(mac.finalize().into_bytes())).unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn accepts_a_signed_request() {
+        let auth = authenticator();
+        let key_id = auth.verify("POST", "/sign", &headers(SECRET, unix_now(), "n1", b"{}"), b"{}").await;
+        assert_eq!(key_id, Ok("executor".to_string()));
+    }
+
+    #[tokio::test]
+    async fn rejects_a_bad_mac() {
+        let auth = authenticator();
+        let wrong_secret = headers("fedcba9876543210fedcba9876543210", unix_now(), "n1", b"{}");
+        assert_eq!(auth.verify("POST", "/sign", &wrong_secret, b"{}").await, Err("bad signature".to_string()));
+        let tampered_body = headers(SECRET, unix_now(), "n2", b"{}");
+        assert_eq!(auth.verify("POST", "/sign", &tampered_body, b"{\"key\":\"x\"}").await, Err("bad signature".to_string()));
+        let other_path = headers(SECRET, unix_now(), "n3", b"");
+        assert_eq!(auth.verify("GET", "/admin/budget", &other_path, b"").await, Err("bad signature".to_string()));
+    }
+
+    #[tokio::test]
+    async fn rejects_clock_skew() {
+        let auth = authenticator();
+        for timestamp in [unix_now() - 31, unix_now() + 31] {
+            let result = auth.verify("POST", "/sign", &headers(SECRET, timestamp, "n1", b""), b"").await;
+            assert!(result.unwrap_err().contains("outside the 30s window"));
+        }
+        assert!(auth.verify("POST", "/sign", &headers(SECRET, unix_now() - 29, "n2", b""), b"").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_a_replayed_nonce() {
+        let auth = authenticator();
+        let signed = headers(SECRET, unix_now(), "n1", b"{}");
+        assert!(auth.verify("POST", "/sign", &signed, b"{}").await.is_ok());
+        assert_eq!(auth.verify("POST", "/sign", &signed, b"{}").await, Err("nonce already used".to_string()));
+        assert!(auth.verify("POST", "/sign", &headers(SECRET, unix_now(), "n2", b"{}"), b"{}").await.is_ok());
+    }
+
+    #[test]
+    fn callers_are_scoped_to_their_keys() {
+        let mut auth = authenticator();
+        assert!(auth.may_use("executor", "momentum"));
+        assert!(!auth.may_use("executor", "default"));
+        assert!(!auth.may_use("unknown", "momentum"));
+        auth.allowed_keys.insert("ops".to_string(), vec!["*".to_string()]);
+        assert!(auth.may_use("ops", "default"));
+    }
+
+    #[test]
+    fn unauthenticated_warnings_are_rate_limited() {
+        let limiter = WarnLimiter::new(Duration::from_secs(10));
+        let start = Instant::now();
+        assert_eq!(limiter.admit(start), Some(0));
+        assert_eq!(limiter.admit(start + Duration::from_secs(1)), None);
+        assert_eq!(limiter.admit(start + Duration::from_secs(9)), None);
+        assert_eq!(limiter.admit(start + Duration::from_secs(10)), Some(2), "reports what it held back");
+        assert_eq!(limiter.admit(start + Duration::from_secs(11)), None);
+    }
+}
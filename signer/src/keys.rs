@@ -0,0 +1,170 @@
+// signer/src/keys.rs
+use crate::{keystore, policy::Policy};
+use anyhow::{anyhow, Context, Result};
+use solana_sdk::{
+    message::VersionedMessage,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::VersionedTransaction,
+};
+use std::{collections::BTreeMap, env};
+use tracing::info;
+use zeroize::Zeroizing;
+
+/// Name of the key used when a request does not ask for one.
+pub const DEFAULT_KEY: &str = "default";
+
+/// Suffix scoping a setting to one key or caller, e.g. `momentum-v2` → `MOMENTUM_V2`.
+pub fn env_suffix(name: &str) -> String {
+    name.to_uppercase().replace('-', "_")
+}
+
+/// A wallet the signer holds, with the policy that applies to it. The secret key bytes are
+/// zeroized when the keypair is dropped.
+pub struct NamedKey {
+    pub keypair: Keypair,
+    pub policy: Policy,
+}
+
+impl NamedKey {
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    /// Signature slots this key must fill: its positions among the message's required signers.
+    pub fn signer_slots(&self, message: &VersionedMessage) -> Vec<usize> {
+        let required = message.header().num_required_signatures as usize;
+        let pubkey = self.pubkey();
+        message.static_account_keys().iter().take(required).enumerate()
+            .filter(|(_, key)| **key == pubkey)
+            .map(|(slot, _)| slot)
+            .collect()
+    }
+
+    /// Signs only this key's slots, leaving signatures from other signers untouched.
+    pub fn partial_sign(&self, tx: &mut VersionedTransaction, slots: &[usize]) {
+        let required = tx.message.header().num_required_signatures as usize;
+        if tx.signatures.len() < required {
+            tx.signatures.resize(required, Signature::default());
+        }
+        let signature = self.keypair.sign_message(&tx.message.serialize());
+        for slot in slots {
+            tx.signatures[*slot] = signature;
+        }
+    }
+}
+
+/// Every keypair the signer can sign with, by name, so each strategy family or risk bucket can
+/// trade from its own wallet.
+pub struct KeyRing {
+    keys: BTreeMap<String, NamedKey>,
+}
+
+impl KeyRing {
+    /// `SIGNER_KEYS` is a comma-separated list of `name=path`. Without it (or when empty), `WALLET_KEYPAIR_PATH`
+    /// is loaded as the single `default` key. Paths may be plain keypairs or encrypted keystores,
+    /// which all unlock with the one passphrase.
+    pub fn from_env() -> Result<Self> {
+        let specs: Vec<(String, String)> = match env::var("SIGNER_KEYS") {
+            Ok(raw) if !raw.trim().is_empty() => raw.split(',').map(str::trim).filter(|e| !e.is_empty())
+                .map(|entry| {
+                    entry.split_once('=')
+                        .map(|(name, path)| (name.trim().to_string(), path.trim().to_string()))
+                        .ok_or_else(|| anyhow!("SIGNER_KEYS entries must be name=path"))
+                })
+                .collect::<Result<_>>()?,
+            _ => {
+                let path = env::var("WALLET_KEYPAIR_PATH").map_err(|_| anyhow!("SIGNER_KEYS or WALLET_KEYPAIR_PATH must be set"))?;
+                vec![(DEFAULT_KEY.to_string(), path)]
+            }
+        };
+
+        let mut keys = BTreeMap::new();
+        let mut passphrase: Option<Zeroizing<String>> = None;
+        for (name, path) in specs {
+            let keypair = keystore::load_keypair(&path, || {
+                if passphrase.is_none() {
+                    passphrase = Some(keystore::passphrase_from_env()?);
+                }
+                passphrase.clone().ok_or_else(|| anyhow!("keystore passphrase unavailable"))
+            }).with_context(|| format!("Failed to load key {}", name))?;
+            let policy = Policy::from_env(&name, keypair.pubkey()).with_context(|| format!("Invalid signing policy for key {}", name))?;
+            info!(key = %name, pubkey = %keypair.pubkey(), max_sol_out_lamports = policy.max_sol_out_lamports, "Keypair loaded.");
+            if keys.insert(name.clone(), NamedKey { keypair, policy }).is_some() {
+                return Err(anyhow!("key {} is configured twice", name));
+            }
+        }
+        if keys.is_empty() {
+            return Err(anyhow!("no keypairs configured"));
+        }
+        Ok(Self { keys })
+    }
+
+    /// The key named `name`, or `default` when none is given.
+    pub fn get(&self, name: Option<&str>) -> Option<(&str, &NamedKey)> {
+        let name = name.unwrap_or(DEFAULT_KEY);
+        self.keys.get_key_value(name).map(|(n, k)| (n.as_str(), k))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &NamedKey)> {
+        self.keys.iter()
+    }
+
+    /// Fresh keypairs under `names`, each with the policy from the environment.
+    #[cfg(test)]
+    pub fn generated(names: &[&str]) -> Self {
+        let keys = names.iter().map(|name| {
+            let keypair = Keypair::new();
+            let policy = Policy::from_env(name, keypair.pubkey()).unwrap();
+            (name.to_string(), NamedKey { keypair, policy })
+        });
+        Self { keys: keys.collect() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{hash::Hash, instruction::{AccountMeta, Instruction}, message::{v0, VersionedMessage}};
+
+    fn named_key() -> NamedKey {
+        let keypair = Keypair::new();
+        let policy = Policy::from_env("test", keypair.pubkey()).unwrap();
+        NamedKey { keypair, policy }
+    }
+
+    /// A message paid by `payer` that also needs `cosigner`'s signature.
+    fn two_signer_message(payer: &Pubkey, cosigner: &Pubkey) -> VersionedMessage {
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![AccountMeta::new_readonly(*cosigner, true)]);
+        VersionedMessage::V0(v0::Message::try_compile(payer, &[ix], &[], Hash::default()).unwrap())
+    }
+
+    #[test]
+    fn a_key_outside_the_signers_has_no_slots() {
+        let key = named_key();
+        let message = two_signer_message(&Pubkey::new_unique(), &Pubkey::new_unique());
+        assert!(key.signer_slots(&message).is_empty());
+
+        // Present in the message but not as a signer.
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![AccountMeta::new(key.pubkey(), false)]);
+        let message = VersionedMessage::V0(v0::Message::try_compile(&Pubkey::new_unique(), &[ix], &[], Hash::default()).unwrap());
+        assert!(key.signer_slots(&message).is_empty());
+    }
+
+    #[test]
+    fn partial_sign_fills_only_its_own_slot() {
+        let payer = Keypair::new();
+        let key = named_key();
+        let message = two_signer_message(&payer.pubkey(), &key.pubkey());
+        let slots = key.signer_slots(&message);
+        assert_eq!(slots, vec![1]);
+
+        let payer_signature = payer.sign_message(&message.serialize());
+        let mut tx = VersionedTransaction { signatures: vec![payer_signature], message };
+        key.partial_sign(&mut tx, &slots);
+
+        assert_eq!(tx.signatures.len(), 2);
+        assert_eq!(tx.signatures[0], payer_signature, "the other signer's signature is kept");
+        assert!(tx.verify_with_results().iter().all(|ok| *ok));
+    }
+}
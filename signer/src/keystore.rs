@@ -0,0 +1,192 @@
+// signer/src/keystore.rs
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::{Keypair, Signer};
+use std::{env, fs, io::Read, path::Path};
+use zeroize::Zeroizing;
+
+/// scrypt cost: N = 2^15, r = 8, p = 1 (about 32 MiB and a fraction of a second to unlock).
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// On-disk form of an encrypted keypair. The 64 keypair bytes are sealed with AES-256-GCM under
+/// a key derived from the passphrase with scrypt; the public key is kept in clear for lookup.
+#[derive(Debug, Serialize, Deserialize)]
+struct Keystore {
+    version: u8,
+    pubkey: String,
+    kdf: String,
+    salt: String,
+    log_n: u8,
+    r: u32,
+    p: u32,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], log_n: u8, r: u32, p: u32) -> Result<Zeroizing<[u8; 32]>> {
+    let params = scrypt::Params::new(log_n, r, p, 32).map_err(|e| anyhow!("invalid scrypt parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, key.as_mut()).map_err(|e| anyhow!("scrypt failed: {}", e))?;
+    Ok(key)
+}
+
+fn seal(keypair: &Keypair, passphrase: &str) -> Result<Keystore> {
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+    let key = derive_key(passphrase, &salt, SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P)?;
+    let secret = Zeroizing::new(keypair.to_bytes());
+    let ciphertext = Aes256Gcm::new_from_slice(key.as_ref())?
+        .encrypt(&Nonce::from(nonce), secret.as_ref())
+        .map_err(|_| anyhow!("encryption failed"))?;
+    Ok(Keystore {
+        version: 1,
+        pubkey: keypair.pubkey().to_string(),
+        kdf: "scrypt".to_string(),
+        salt: B64.encode(salt),
+        log_n: SCRYPT_LOG_N,
+        r: SCRYPT_R,
+        p: SCRYPT_P,
+        cipher: "aes-256-gcm".to_string(),
+        nonce: B64.encode(nonce),
+        ciphertext: B64.encode(ciphertext),
+    })
+}
+
+fn open(store: &Keystore, passphrase: &str) -> Result<Keypair> {
+    if store.version != 1 || store.kdf != "scrypt" || store.cipher != "aes-256-gcm" {
+        return Err(anyhow!("unsupported keystore format"));
+    }
+    let key = derive_key(passphrase, &B64.decode(&store.salt)?, store.log_n, store.r, store.p)?;
+    let nonce: [u8; 12] = B64.decode(&store.nonce)?.try_into().map_err(|_| anyhow!("keystore nonce must be 12 bytes"))?;
+    let secret = Zeroizing::new(
+        Aes256Gcm::new_from_slice(key.as_ref())?
+            .decrypt(&Nonce::from(nonce), B64.decode(&store.ciphertext)?.as_ref())
+            .map_err(|_| anyhow!("wrong passphrase or corrupted keystore"))?,
+    );
+    let keypair = Keypair::from_bytes(&secret).map_err(|e| anyhow!("keystore holds an invalid keypair: {}", e))?;
+    if keypair.pubkey().to_string() != store.pubkey {
+        return Err(anyhow!("keystore public key does not match its secret"));
+    }
+    Ok(keypair)
+}
+
+fn write_private(path: &Path, store: &Keystore) -> Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+    let tmp = path.with_extension("tmp");
+    let mut file = fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(&tmp)?;
+    std::io::Write::write_all(&mut file, &serde_json::to_vec_pretty(store)?)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Loads a keypair file, either a plain Solana keypair JSON array or an encrypted keystore.
+/// `passphrase` is only asked for when the file is encrypted.
+pub fn load_keypair(path: &str, passphrase: impl FnOnce() -> Result<Zeroizing<String>>) -> Result<Keypair> {
+    let raw = Zeroizing::new(fs::read_to_string(path).with_context(|| format!("Failed to read keypair at {}", path))?);
+    if raw.trim_start().starts_with('[') {
+        let bytes: Zeroizing<Vec<u8>> = Zeroizing::new(serde_json::from_str(&raw).with_context(|| format!("Invalid keypair file {}", path))?);
+        return Keypair::from_bytes(&bytes).map_err(|e| anyhow!("Invalid keypair file {}: {}", path, e));
+    }
+    let store: Keystore = serde_json::from_str(&raw).with_context(|| format!("{} is neither a keypair nor a keystore", path))?;
+    open(&store, &passphrase()?).with_context(|| format!("Failed to unlock keystore {}", path))
+}
+
+/// The unlock passphrase, from `SIGNER_KEYSTORE_PASSPHRASE_FD` (read to EOF) or else
+/// `SIGNER_KEYSTORE_PASSPHRASE`, which is removed from the environment once read.
+pub fn passphrase_from_env() -> Result<Zeroizing<String>> {
+    if let Ok(fd) = env::var("SIGNER_KEYSTORE_PASSPHRASE_FD") {
+        use std::os::fd::FromRawFd;
+        let fd: i32 = fd.parse().context("SIGNER_KEYSTORE_PASSPHRASE_FD must be a file descriptor number")?;
+        // The descriptor is handed to us by the launcher and not used anywhere else.
+        let mut file = unsafe { fs::File::from_raw_fd(fd) };
+        let mut passphrase = Zeroizing::new(String::new());
+        file.read_to_string(&mut passphrase)?;
+        let trimmed = Zeroizing::new(passphrase.trim_end_matches(['\r', '\n']).to_string());
+        return non_empty(trimmed, "SIGNER_KEYSTORE_PASSPHRASE_FD");
+    }
+    let passphrase = env::var("SIGNER_KEYSTORE_PASSPHRASE")
+        .map_err(|_| anyhow!("encrypted keystore needs SIGNER_KEYSTORE_PASSPHRASE_FD or SIGNER_KEYSTORE_PASSPHRASE"))?;
+    env::remove_var("SIGNER_KEYSTORE_PASSPHRASE");
+    non_empty(Zeroizing::new(passphrase), "SIGNER_KEYSTORE_PASSPHRASE")
+}
+
+/// An empty passphrase is a launcher misconfiguration, e.g. an unset variable passed through.
+fn non_empty(passphrase: Zeroizing<String>, source: &str) -> Result<Zeroizing<String>> {
+    if passphrase.is_empty() {
+        return Err(anyhow!("{} is set but empty; encrypted keystores need a passphrase", source));
+    }
+    Ok(passphrase)
+}
+
+fn prompt_new_passphrase() -> Result<Zeroizing<String>> {
+    let first = Zeroizing::new(rpassword::prompt_password("New passphrase: ")?);
+    let second = Zeroizing::new(rpassword::prompt_password("Repeat new passphrase: ")?);
+    if *first != *second {
+        return Err(anyhow!("passphrases do not match"));
+    }
+    if first.len() < 12 {
+        return Err(anyhow!("passphrase must be at least 12 characters"));
+    }
+    Ok(first)
+}
+
+/// `signer keystore encrypt <keypair.json> <out>` and `signer keystore rotate <keystore>`.
+pub fn run_cli(args: &[String]) -> Result<()> {
+    match args {
+        [cmd, input, output] if cmd == "encrypt" => {
+            let keypair = load_keypair(input, || Err(anyhow!("{} is already encrypted", input)))?;
+            let store = seal(&keypair, &prompt_new_passphrase()?)?;
+            write_private(Path::new(output), &store)?;
+            println!("encrypted {} to {}; delete the plaintext file once the keystore is backed up", store.pubkey, output);
+            Ok(())
+        }
+        [cmd, path] if cmd == "rotate" => {
+            let current = Zeroizing::new(rpassword::prompt_password("Current passphrase: ")?);
+            let keypair = load_keypair(path, || Ok(current))?;
+            let store = seal(&keypair, &prompt_new_passphrase()?)?;
+            write_private(Path::new(path), &store)?;
+            println!("passphrase rotated for {}", store.pubkey);
+            Ok(())
+        }
+        _ => Err(anyhow!("usage: signer keystore encrypt <keypair.json> <out> | signer keystore rotate <keystore>")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_keypair_opens_with_its_passphrase() {
+        let keypair = Keypair::new();
+        let store = seal(&keypair, "correct horse battery staple").unwrap();
+        assert_eq!(store.pubkey, keypair.pubkey().to_string());
+        let opened = open(&store, "correct horse battery staple").unwrap();
+        assert_eq!(opened.to_bytes(), keypair.to_bytes());
+    }
+
+    #[test]
+    fn wrong_passphrase_does_not_open() {
+        let store = seal(&Keypair::new(), "correct horse battery staple").unwrap();
+        let err = open(&store, "incorrect horse").unwrap_err();
+        assert_eq!(err.to_string(), "wrong passphrase or corrupted keystore");
+    }
+
+    #[test]
+    fn empty_passphrase_is_rejected() {
+        let err = non_empty(Zeroizing::new(String::new()), "SIGNER_KEYSTORE_PASSPHRASE_FD").unwrap_err();
+        assert_eq!(err.to_string(), "SIGNER_KEYSTORE_PASSPHRASE_FD is set but empty; encrypted keystores need a passphrase");
+        assert!(non_empty(Zeroizing::new("x".to_string()), "SIGNER_KEYSTORE_PASSPHRASE").is_ok());
+    }
+}
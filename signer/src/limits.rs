@@ -0,0 +1,283 @@
+// signer/src/limits.rs
+use crate::keys::env_suffix;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::{BTreeMap, HashMap}, env, fs, path::PathBuf, str::FromStr, time::{SystemTime, UNIX_EPOCH}};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+/// Rolling limits the signer enforces on one key, whatever the executor asks for.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_signatures_per_minute: u32,
+    pub max_sol_out_per_hour_lamports: u64,
+    pub max_sol_out_per_day_lamports: u64,
+}
+
+impl Limits {
+    /// `SIGNER_MAX_SIGNATURES_PER_MINUTE`, `SIGNER_MAX_SOL_OUT_PER_HOUR_LAMPORTS` and
+    /// `SIGNER_MAX_SOL_OUT_PER_DAY_LAMPORTS`, each replaced for one key by a `_<KEY NAME>` suffix.
+    pub fn from_env(key_name: &str) -> Result<Self> {
+        let suffix = env_suffix(key_name);
+        Ok(Self {
+            max_signatures_per_minute: parse_var("SIGNER_MAX_SIGNATURES_PER_MINUTE", &suffix, "60")?,
+            max_sol_out_per_hour_lamports: parse_var("SIGNER_MAX_SOL_OUT_PER_HOUR_LAMPORTS", &suffix, "20000000000")?,
+            max_sol_out_per_day_lamports: parse_var("SIGNER_MAX_SOL_OUT_PER_DAY_LAMPORTS", &suffix, "100000000000")?,
+        })
+    }
+}
+
+/// One signature handed out, with the SOL it sends out of the wallet.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Spend {
+    at: i64,
+    lamports: u64,
+}
+
+/// What is left in each window, as served by `/admin/budget`.
+#[derive(Debug, Serialize)]
+pub struct Budget {
+    pub signatures_last_minute: u32,
+    pub signatures_remaining_this_minute: u32,
+    pub sol_out_last_hour_lamports: u64,
+    pub sol_out_remaining_hour_lamports: u64,
+    pub sol_out_last_day_lamports: u64,
+    pub sol_out_remaining_day_lamports: u64,
+}
+
+/// The last 24h of signatures of every key, each against its own limits, persisted to
+/// `SIGNER_BUDGET_PATH` after every change so a restart does not reset the windows.
+pub struct SpendLedger {
+    pub limits: BTreeMap<String, Limits>,
+    path: PathBuf,
+    state: Mutex<LedgerState>,
+}
+
+#[derive(Default)]
+struct LedgerState {
+    keys: HashMap<String, Entries>,
+    next_hold: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    /// Charged signatures, the ones persisted.
+    spends: Vec<Spend>,
+    /// Admitted requests still being signed; they count against the budget until charged or released.
+    held: Vec<(u64, Spend)>,
+}
+
+/// Budget set aside on one key for one request between admission and charging.
+#[must_use = "a hold must be charged or released"]
+#[derive(Debug)]
+pub struct Hold {
+    key: String,
+    id: u64,
+}
+
+impl SpendLedger {
+    /// Limits for each of `key_names`; windows of keys no longer configured are dropped.
+    pub fn from_env<'a>(key_names: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let limits = key_names.into_iter()
+            .map(|name| Ok((name.to_string(), Limits::from_env(name)?)))
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        let path = PathBuf::from(env::var("SIGNER_BUDGET_PATH").unwrap_or_else(|_| "/app/state/signer_budget.json".to_string()));
+        Self::open(limits, path)
+    }
+
+    /// Loads the spends already recorded at `path`; a missing file is an empty ledger.
+    pub fn open(limits: BTreeMap<String, Limits>, path: PathBuf) -> Result<Self> {
+        let stored: HashMap<String, Vec<Spend>> = match fs::read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw).with_context(|| format!("Invalid budget file {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read budget file {}", path.display())),
+        };
+        let keys = stored.into_iter()
+            .filter(|(key, _)| limits.contains_key(key))
+            .map(|(key, spends)| (key, Entries { spends, held: vec![] }))
+            .collect();
+        Ok(Self { limits, path, state: Mutex::new(LedgerState { keys, next_hold: 0 }) })
+    }
+
+    /// Admits one signature by `key` sending `sol_out_lamports` out, or returns which of the key's
+    /// limits it would break. The admitted amount is held until `charge` or `release`.
+    pub async fn admit(&self, key: &str, sol_out_lamports: u64) -> Result<Hold, String> {
+        self.admit_at(key, sol_out_lamports, unix_now()).await
+    }
+
+    async fn admit_at(&self, key: &str, sol_out_lamports: u64, now: i64) -> Result<Hold, String> {
+        let limits = self.limits.get(key).ok_or_else(|| format!("no signing limits for key {}", key))?;
+        let mut state = self.state.lock().await;
+        let id = state.next_hold;
+        let entries = state.keys.entry(key.to_string()).or_default();
+        entries.spends.retain(|s| now - s.at < DAY);
+        let budget = budget_at(limits, entries, now);
+
+        if budget.signatures_remaining_this_minute == 0 {
+            return Err(format!("{} signatures in the last minute, limit {}", budget.signatures_last_minute, limits.max_signatures_per_minute));
+        }
+        if sol_out_lamports > budget.sol_out_remaining_hour_lamports {
+            return Err(format!("{} lamports out would exceed the hourly budget, {} remaining", sol_out_lamports, budget.sol_out_remaining_hour_lamports));
+        }
+        if sol_out_lamports > budget.sol_out_remaining_day_lamports {
+            return Err(format!("{} lamports out would exceed the daily budget, {} remaining", sol_out_lamports, budget.sol_out_remaining_day_lamports));
+        }
+
+        entries.held.push((id, Spend { at: now, lamports: sol_out_lamports }));
+        state.next_hold += 1;
+        Ok(Hold { key: key.to_string(), id })
+    }
+
+    /// Records a held signature once it has been signed, persisting it before returning.
+    pub async fn charge(&self, hold: Hold) -> Result<(), String> {
+        let mut state = self.state.lock().await;
+        let Some(spend) = take_hold(&mut state, &hold) else { return Err("budget hold already settled".to_string()) };
+        state.keys.entry(hold.key.clone()).or_default().spends.push(spend);
+        if let Err(e) = self.persist(&state) {
+            // Refuse rather than hand out something a restart would forget.
+            state.keys.entry(hold.key).or_default().spends.pop();
+            warn!(error = %e, "Failed to persist signer budget");
+            return Err(format!("budget file not writable: {}", e));
+        }
+        Ok(())
+    }
+
+    /// Returns a held amount to the budget when the request is not signed after all.
+    pub async fn release(&self, hold: Hold) {
+        take_hold(&mut *self.state.lock().await, &hold);
+    }
+
+    /// What is left for each key.
+    pub async fn budget(&self) -> BTreeMap<String, Budget> {
+        let state = self.state.lock().await;
+        let now = unix_now();
+        self.limits.iter()
+            .map(|(key, limits)| (key.clone(), budget_at(limits, state.keys.get(key).unwrap_or(&Entries::default()), now)))
+            .collect()
+    }
+
+    /// Write-then-rename, so a crash never leaves a truncated file.
+    fn persist(&self, state: &LedgerState) -> Result<()> {
+        let spends: BTreeMap<&String, &Vec<Spend>> = state.keys.iter().map(|(key, entries)| (key, &entries.spends)).collect();
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&spends)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+fn budget_at(limits: &Limits, entries: &Entries, now: i64) -> Budget {
+    let all = || entries.spends.iter().chain(entries.held.iter().map(|(_, s)| s));
+    let signatures_last_minute = all().filter(|s| now - s.at < MINUTE).count() as u32;
+    let sol_out_last_hour_lamports = all().filter(|s| now - s.at < HOUR).fold(0u64, |sum, s| sum.saturating_add(s.lamports));
+    let sol_out_last_day_lamports = all().filter(|s| now - s.at < DAY).fold(0u64, |sum, s| sum.saturating_add(s.lamports));
+    Budget {
+        signatures_last_minute,
+        signatures_remaining_this_minute: limits.max_signatures_per_minute.saturating_sub(signatures_last_minute),
+        sol_out_last_hour_lamports,
+        sol_out_remaining_hour_lamports: limits.max_sol_out_per_hour_lamports.saturating_sub(sol_out_last_hour_lamports),
+        sol_out_last_day_lamports,
+        sol_out_remaining_day_lamports: limits.max_sol_out_per_day_lamports.saturating_sub(sol_out_last_day_lamports),
+    }
+}
+
+fn parse_var<T: FromStr>(var: &str, suffix: &str, default: &str) -> Result<T> {
+    env::var(format!("{}_{}", var, suffix))
+        .or_else(|_| env::var(var))
+        .unwrap_or_else(|_| default.to_string())
+        .parse()
+        .ok()
+        .with_context(|| format!("{} must be a non-negative integer", var))
+}
+
+fn take_hold(state: &mut LedgerState, hold: &Hold) -> Option<Spend> {
+    let held = &mut state.keys.get_mut(&hold.key)?.held;
+    let index = held.iter().position(|(id, _)| *id == hold.id)?;
+    Some(held.remove(index).1)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: Limits = Limits { max_signatures_per_minute: 100, max_sol_out_per_hour_lamports: 1_000, max_sol_out_per_day_lamports: 2_500 };
+
+    fn ledger(name: &str) -> SpendLedger {
+        let path = env::temp_dir().join(format!("signer_budget_{}_{}.json", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        let limits = BTreeMap::from([("momentum".to_string(), LIMITS), ("meanrev".to_string(), LIMITS)]);
+        SpendLedger { limits, path, state: Mutex::new(LedgerState::default()) }
+    }
+
+    async fn spend(ledger: &SpendLedger, lamports: u64, at: i64) -> Result<(), String> {
+        spend_on(ledger, "momentum", lamports, at).await
+    }
+
+    async fn spend_on(ledger: &SpendLedger, key: &str, lamports: u64, at: i64) -> Result<(), String> {
+        let hold = ledger.admit_at(key, lamports, at).await?;
+        ledger.charge(hold).await
+    }
+
+    #[tokio::test]
+    async fn hourly_budget_rolls_over() {
+        let ledger = ledger("hour");
+        spend(&ledger, 800, 0).await.unwrap();
+        let err = spend(&ledger, 300, HOUR - 1).await.unwrap_err();
+        assert!(err.contains("hourly budget, 200 remaining"), "{}", err);
+        spend(&ledger, 300, HOUR).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn daily_budget_rolls_over() {
+        let ledger = ledger("day");
+        for hour in 0..2 {
+            spend(&ledger, 1_000, hour * HOUR).await.unwrap();
+        }
+        let err = spend(&ledger, 600, 2 * HOUR).await.unwrap_err();
+        assert!(err.contains("daily budget, 500 remaining"), "{}", err);
+        // The first spend leaves the window a day after it was made.
+        spend(&ledger, 600, DAY).await.unwrap();
+        assert_eq!(ledger.state.lock().await.keys["momentum"].spends.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn held_amounts_count_until_released() {
+        let ledger = ledger("hold");
+        let hold = ledger.admit_at("momentum", 900, 0).await.unwrap();
+        assert!(ledger.admit_at("momentum", 200, 1).await.is_err(), "a held amount is not available to others");
+        ledger.release(hold).await;
+        let hold = ledger.admit_at("momentum", 200, 2).await.unwrap();
+        ledger.charge(hold).await.unwrap();
+        let persisted: HashMap<String, Vec<Spend>> = serde_json::from_slice(&fs::read(&ledger.path).unwrap()).unwrap();
+        assert_eq!(persisted["momentum"].len(), 1);
+        assert_eq!(persisted["momentum"][0].lamports, 200);
+    }
+
+    #[tokio::test]
+    async fn each_key_has_its_own_budget() {
+        let ledger = ledger("per_key");
+        spend_on(&ledger, "momentum", 1_000, 0).await.unwrap();
+        assert!(spend_on(&ledger, "momentum", 1, 1).await.is_err());
+        spend_on(&ledger, "meanrev", 1_000, 1).await.unwrap();
+        assert_eq!(spend_on(&ledger, "other", 1, 1).await.unwrap_err(), "no signing limits for key other");
+
+        let budget = ledger.budget().await;
+        assert_eq!(budget.keys().collect::<Vec<_>>(), vec!["meanrev", "momentum"]);
+    }
+
+    #[test]
+    fn sums_saturate_instead_of_overflowing() {
+        let entries = Entries { spends: vec![Spend { at: 0, lamports: u64::MAX }, Spend { at: 1, lamports: u64::MAX }], held: vec![] };
+        let budget = budget_at(&LIMITS, &entries, 2);
+        assert_eq!(budget.sol_out_last_hour_lamports, u64::MAX);
+        assert_eq!(budget.sol_out_remaining_day_lamports, 0);
+    }
+}
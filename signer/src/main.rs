@@ -1,17 +1,42 @@
 // signer/src/main.rs
-use anyhow::{anyhow, Context, Result};
-use axum::{extract::State, http::StatusCode, routing::{get, post}, Json, Router};
-use shared_models::{SignRequest, SignResponse};
+mod audit;
+mod auth;
+mod keys;
+mod keystore;
+mod metrics;
+mod limits;
+mod policy;
+
+use crate::audit::{AuditLog, AuditRecord};
+use crate::auth::{Authenticator, Caller};
+use crate::keys::KeyRing;
+use crate::metrics::Metrics;
+use crate::limits::{Budget, SpendLedger};
+use anyhow::Result;
+use axum::{
+    extract::{Extension, Path as UrlPath, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    middleware,
+    routing::{get, post},
+    Json, Router,
+};
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use shared_models::{SignRequest, SignResponse, SignerErrorBody};
 use solana_sdk::{
-    signature::{read_keypair_file, Keypair, Signer},
+    signature::Signature,
     transaction::VersionedTransaction,
 };
-use std::{env, net::SocketAddr, sync::Arc};
-use tracing::{error, info, instrument, level_filters::LevelFilter};
+use std::{collections::BTreeMap, env, net::SocketAddr, path::Path, sync::Arc, time::Instant};
+use tracing::{error, info, instrument, level_filters::LevelFilter, warn};
 use tracing_subscriber::EnvFilter;
 
-struct AppState {
-    keypair: Keypair,
+pub struct AppState {
+    keys: KeyRing,
+    auth: Authenticator,
+    ledger: SpendLedger,
+    audit: AuditLog,
+    metrics: Metrics,
 }
 
 #[tokio::main]
@@ -21,20 +46,46 @@ async fn main() -> Result<()> {
         .from_env_lossy();
     tracing_subscriber::fmt().with_env_filter(filter).init();
 
+    // Maintenance subcommands run and exit without starting the service.
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("verify-audit") => {
+            let path = args.get(2).cloned().unwrap_or_else(audit_log_path);
+            let entries = audit::verify(Path::new(&path))?;
+            println!("audit log {} intact: {} entries", path, entries);
+            return Ok(());
+        }
+        Some("keystore") => return keystore::run_cli(&args[2..]),
+        _ => {}
+    }
+
     info!("🔒 Starting Signer Service...");
 
-    let keypair_path = env::var("WALLET_KEYPAIR_PATH").expect("WALLET_KEYPAIR_PATH must be set");
-    let keypair = read_keypair_file(&keypair_path)
-        .map_err(|e| anyhow!("Failed to read keypair at {}: {}", keypair_path, e))?;
-    
-    let pubkey = keypair.pubkey();
-    info!(%pubkey, "Wallet loaded successfully. This service is now ready to sign transactions.");
+    let keys = KeyRing::from_env()?;
+    info!("Keypairs loaded.");
+
+    let key_names: Vec<&str> = keys.iter().map(|(name, _)| name.as_str()).collect();
+    let auth = Authenticator::from_env(&key_names)?;
+    let ledger = SpendLedger::from_env(key_names.iter().copied())?;
+    info!(limits = ?ledger.limits, "Signing limits loaded.");
+
+    let audit = AuditLog::open(audit_log_path())?;
+    info!(path = %audit.path().display(), "Audit log opened.");
 
-    let state = Arc::new(AppState { keypair });
+    let metrics = Metrics::new()?;
+
+    let state = Arc::new(AppState { keys, auth, ledger, audit, metrics });
 
     let app = Router::new()
-        .route("/pubkey", get(get_pubkey))
+        .route("/pubkey", get(get_default_pubkey))
+        .route("/pubkey/:name", get(get_pubkey))
         .route("/sign", post(sign_transaction))
+        .route("/admin/budget", get(get_budget))
+        .layer(middleware::from_fn_with_state(state.clone(), auth::require_hmac))
+        // Probes and scrapes come from the orchestrator, not a signing client.
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(metrics_handler))
         .with_state(state);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 8989));
@@ -45,21 +96,96 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+fn audit_log_path() -> String {
+    env::var("SIGNER_AUDIT_LOG_PATH").unwrap_or_else(|_| "/app/state/signer_audit.jsonl".to_string())
+}
+
+/// Liveness: the process is up and serving.
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// Readiness: keys and their policies are loaded and the audit log can be written, i.e. a valid
+/// request would be signed rather than refused for a local fault.
+async fn readyz(State(state): State<Arc<AppState>>) -> Response {
+    let (status, body) = readiness(&state.keys, &state.audit);
+    (status, Json(body)).into_response()
+}
+
+fn readiness(keys: &KeyRing, audit: &AuditLog) -> (StatusCode, serde_json::Value) {
+    let keys_loaded = keys.iter().count();
+    let audit_writable = audit.writable();
+    let ready = keys_loaded > 0 && audit_writable;
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let body = serde_json::json!({
+        "ready": ready,
+        "keys_loaded": keys_loaded,
+        // Every key carries a parsed policy: startup fails otherwise.
+        "policies_loaded": keys_loaded,
+        "audit_log_writable": audit_writable,
+    });
+    (status, body)
+}
+
+async fn metrics_handler(State(state): State<Arc<AppState>>) -> Response {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], state.metrics.render()).into_response()
+}
+
+#[instrument(skip(state), name="get_pubkey_handler")]
+async fn get_default_pubkey(State(state): State<Arc<AppState>>) -> Response {
+    pubkey_of(&state, None)
+}
+
 #[instrument(skip(state), name="get_pubkey_handler")]
-async fn get_pubkey(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
-    Json(serde_json::json!({ "pubkey": state.keypair.pubkey().to_string() }))
+async fn get_pubkey(State(state): State<Arc<AppState>>, UrlPath(name): UrlPath<String>) -> Response {
+    pubkey_of(&state, Some(&name))
+}
+
+fn pubkey_of(state: &AppState, name: Option<&str>) -> Response {
+    match state.keys.get(name) {
+        Some((name, key)) => Json(serde_json::json!({ "key": name, "pubkey": key.pubkey().to_string() })).into_response(),
+        None => reject(StatusCode::NOT_FOUND, "unknown_key", vec![format!("no key named {}", name.unwrap_or(keys::DEFAULT_KEY))]),
+    }
+}
+
+/// Budgets of the keys the caller may sign with.
+#[instrument(skip(state), fields(caller = %caller.0), name="get_budget_handler")]
+async fn get_budget(State(state): State<Arc<AppState>>, Extension(caller): Extension<Caller>) -> Json<BTreeMap<String, Budget>> {
+    let mut budget = state.ledger.budget().await;
+    budget.retain(|key, _| state.auth.may_use(&caller.0, key));
+    Json(budget)
 }
 
-#[instrument(skip(state, request), name="sign_transaction_handler")]
+#[instrument(skip(state, request), fields(caller = %caller.0), name="sign_transaction_handler")]
 async fn sign_transaction(
     State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
     Json(request): Json<SignRequest>,
-) -> Result<Json<SignResponse>, StatusCode> {
-    let tx_bytes = match base64::decode(&request.transaction_b64) {
+) -> Result<Json<SignResponse>, Response> {
+    let started = Instant::now();
+    let result = sign(&state, caller, request).await;
+    let outcome = if result.is_ok() { "signed" } else { "rejected" };
+    state.metrics.observe_sign(outcome, started.elapsed().as_secs_f64());
+    result
+}
+
+async fn sign(state: &AppState, caller: Caller, request: SignRequest) -> Result<Json<SignResponse>, Response> {
+    let mut record = AuditRecord { caller: caller.0.clone(), key: request.key.clone(), ..Default::default() };
+    let Some((key_name, key)) = state.keys.get(request.key.as_deref()) else {
+        let reason = format!("no key named {}", request.key.as_deref().unwrap_or(keys::DEFAULT_KEY));
+        return Err(refuse(state, record, StatusCode::NOT_FOUND, "unknown_key", vec![reason]));
+    };
+    record.key = Some(key_name.to_string());
+    if !state.auth.may_use(&caller.0, key_name) {
+        let reason = format!("{} may not sign with key {}", caller.0, key_name);
+        return Err(refuse(state, record, StatusCode::FORBIDDEN, "key_not_allowed", vec![reason]));
+    }
+
+    let tx_bytes = match B64.decode(&request.transaction_b64) {
         Ok(bytes) => bytes,
         Err(e) => {
             error!(error = %e, "Failed to decode base64 transaction");
-            return Err(StatusCode::BAD_REQUEST);
+            return Err(refuse(state, record, StatusCode::BAD_REQUEST, "bad_request", vec![format!("invalid base64: {}", e)]));
         }
     };
 
@@ -67,25 +193,103 @@ async fn sign_transaction(
         Ok(tx) => tx,
         Err(e) => {
             error!(error = %e, "Failed to deserialize transaction");
-            return Err(StatusCode::BAD_REQUEST);
+            return Err(refuse(state, record, StatusCode::BAD_REQUEST, "bad_request", vec![format!("invalid transaction: {}", e)]));
         }
     };
+    record.message_hash = Some(audit::message_hash(&tx.message.serialize()));
 
-    if let Err(e) = tx.sign(&[&state.keypair], tx.message.recent_blockhash()) {
-        error!(error = %e, "Failed to sign transaction");
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    let slots = key.signer_slots(&tx.message);
+    if slots.is_empty() {
+        warn!(pubkey = %key.pubkey(), "Key is not a required signer of the transaction");
+        let reason = format!("{} is not a required signer", key.pubkey());
+        return Err(refuse(state, record, StatusCode::FORBIDDEN, "not_a_signer", vec![reason]));
     }
 
+    let inspection = key.policy.inspect(&tx.message);
+    record.instructions = inspection.instructions.clone();
+    if !inspection.violations.is_empty() {
+        warn!(reasons = ?inspection.violations, "Transaction rejected by signing policy");
+        return Err(refuse(state, record, StatusCode::FORBIDDEN, "policy_rejected", inspection.violations));
+    }
+    let hold = match state.ledger.admit(key_name, inspection.sol_out_lamports).await {
+        Ok(hold) => hold,
+        Err(reason) => {
+            warn!(%reason, "Transaction rejected by signing limits");
+            return Err(refuse(state, record, StatusCode::TOO_MANY_REQUESTS, "rate_limited", vec![reason]));
+        }
+    };
+
+    key.partial_sign(&mut tx, &slots);
+
     let signed_tx_bytes = match bincode::serialize(&tx) {
         Ok(bytes) => bytes,
         Err(e) => {
             error!(error = %e, "Failed to serialize signed transaction");
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            state.ledger.release(hold).await;
+            return Err(refuse(state, record, StatusCode::INTERNAL_SERVER_ERROR, "signing_failed", vec![e.to_string()]));
         }
     };
 
-    info!("Transaction signed successfully.");
+    // Nothing signed leaves the signer unless it is charged to the budget and on the audit log.
+    // Charging first means the log only ever records the outcome the caller actually got.
+    if let Err(reason) = state.ledger.charge(hold).await {
+        error!(%reason, "Failed to charge signing budget, withholding signed transaction");
+        let reason = format!("signed transaction withheld: {}", reason);
+        return Err(refuse(state, record, StatusCode::INTERNAL_SERVER_ERROR, "budget_unavailable", vec![reason]));
+    }
+    record.decision = "signed".to_string();
+    record.signature = Some(tx.signatures[slots[0]].to_string());
+    if let Err(e) = state.audit.append(record) {
+        // The charge stands: over-counting the budget is the safe side.
+        error!(error = %e, "Failed to write audit log, withholding signed transaction");
+        state.metrics.rejected("audit_unavailable");
+        return Err(reject(StatusCode::INTERNAL_SERVER_ERROR, "audit_unavailable", vec![e.to_string()]));
+    }
+
+    state.metrics.signed();
+    let missing = tx.signatures.iter().enumerate().filter(|(i, s)| !slots.contains(i) && **s == Signature::default()).count();
+    info!(key = key_name, ?slots, missing, sol_out_lamports = inspection.sol_out_lamports, "Transaction signed successfully.");
     Ok(Json(SignResponse {
-        signed_transaction_b64: base64::encode(&signed_tx_bytes),
+        signed_transaction_b64: B64.encode(&signed_tx_bytes),
+        signed_slots: slots,
     }))
 }
+
+/// Records a refused request on the audit log, then builds the error response.
+fn refuse(state: &AppState, mut record: AuditRecord, status: StatusCode, error: &str, reasons: Vec<String>) -> Response {
+    state.metrics.rejected(error);
+    record.decision = error.to_string();
+    record.reasons = reasons.clone();
+    if let Err(e) = state.audit.append(record) {
+        error!(error = %e, "Failed to write audit log");
+    }
+    reject(status, error, reasons)
+}
+
+/// Structured error body, so callers can tell a policy rejection from a transport failure.
+pub fn reject(status: StatusCode, error: &str, reasons: Vec<String>) -> Response {
+    (status, Json(SignerErrorBody { error: error.to_string(), reasons })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unwritable_audit_log_is_not_ready() {
+        let path = env::temp_dir().join(format!("signer_readyz_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let audit = AuditLog::open(&path).unwrap();
+        let keys = KeyRing::generated(&["default"]);
+
+        let (status, body) = readiness(&keys, &audit);
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        // Permissions do not stop root, so take the file away instead.
+        std::fs::remove_file(&path).unwrap();
+        let (status, body) = readiness(&keys, &audit);
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["ready"], false);
+        assert_eq!(body["audit_log_writable"], false);
+    }
+}
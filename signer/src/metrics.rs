@@ -0,0 +1,70 @@
+// signer/src/metrics.rs
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder};
+
+/// Prometheus counters and latency histograms for the signing path.
+pub struct Metrics {
+    registry: Registry,
+    signed: IntCounter,
+    rejected: IntCounterVec,
+    latency: HistogramVec,
+}
+
+impl Metrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new();
+        let signed = IntCounter::new("signer_signed_total", "Transactions signed")?;
+        let rejected = IntCounterVec::new(Opts::new("signer_rejected_total", "Requests refused, by error code"), &["reason"])?;
+        let latency = HistogramVec::new(
+            HistogramOpts::new("signer_sign_duration_seconds", "Time to handle /sign, by outcome")
+                .buckets(vec![0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0]),
+            &["outcome"],
+        )?;
+        registry.register(Box::new(signed.clone()))?;
+        registry.register(Box::new(rejected.clone()))?;
+        registry.register(Box::new(latency.clone()))?;
+        Ok(Self { registry, signed, rejected, latency })
+    }
+
+    pub fn signed(&self) {
+        self.signed.inc();
+    }
+
+    /// `reason` is the error code in the response body, e.g. `policy_rejected`.
+    pub fn rejected(&self, reason: &str) {
+        self.rejected.with_label_values(&[reason]).inc();
+    }
+
+    pub fn observe_sign(&self, outcome: &str, seconds: f64) {
+        self.latency.with_label_values(&[outcome]).observe(seconds);
+    }
+
+    /// Text exposition format for `/metrics`.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if TextEncoder::new().encode(&self.registry.gather(), &mut buffer).is_err() {
+            return String::new();
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_exposes_counters_and_latency() {
+        let metrics = Metrics::new().unwrap();
+        metrics.signed();
+        metrics.observe_sign("signed", 0.003);
+        metrics.rejected("policy_rejected");
+        metrics.observe_sign("rejected", 0.02);
+
+        let text = metrics.render();
+        assert!(text.contains("signer_signed_total 1"), "{}", text);
+        assert!(text.contains("signer_rejected_total{reason=\"policy_rejected\"} 1"), "{}", text);
+        assert!(text.contains("signer_sign_duration_seconds_count{outcome=\"signed\"} 1"), "{}", text);
+        assert!(text.contains("signer_sign_duration_seconds_bucket{outcome=\"signed\",le=\"0.005\"} 1"), "{}", text);
+        assert!(text.contains("signer_sign_duration_seconds_bucket{outcome=\"rejected\",le=\"0.01\"} 0"), "{}", text);
+    }
+}
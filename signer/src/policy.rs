@@ -0,0 +1,702 @@
+// signer/src/policy.rs
+use crate::keys::env_suffix;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use solana_sdk::{
+    instruction::CompiledInstruction,
+    message::VersionedMessage,
+    pubkey::Pubkey,
+    system_instruction::SystemInstruction,
+    system_program,
+};
+use std::{collections::HashSet, env, str::FromStr};
+
+const JUPITER_V6_PROGRAM_ID: &str = "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4";
+const DRIFT_PROGRAM_ID: &str = "dRiftyHA39MWEi3m9aunc5MzRF1JYuBsbn6VPcn33UH";
+const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+const COMPUTE_BUDGET_PROGRAM_ID: &str = "ComputeBudget111111111111111111111111111111";
+const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+/// Jito mainnet tip accounts.
+const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+/// SPL token instruction tags the policy looks at. Only transfers, closing an account and
+/// syncing wrapped SOL are allowed; every other token instruction is refused.
+const TOKEN_TRANSFER: u8 = 3;
+const TOKEN_APPROVE: u8 = 4;
+const TOKEN_SET_AUTHORITY: u8 = 6;
+const TOKEN_CLOSE_ACCOUNT: u8 = 9;
+const TOKEN_TRANSFER_CHECKED: u8 = 12;
+const TOKEN_APPROVE_CHECKED: u8 = 13;
+const TOKEN_SYNC_NATIVE: u8 = 17;
+
+/// Jupiter v6 swap instructions and where their accounts sit.
+const JUPITER_SWAPS: [JupiterSwap; 6] = [
+    JupiterSwap { name: "route", source: 2, destinations: &[3, 4], destination_mint: 5, mode: SwapMode::ExactIn },
+    JupiterSwap { name: "shared_accounts_route", source: 3, destinations: &[6], destination_mint: 8, mode: SwapMode::ExactIn },
+    JupiterSwap { name: "exact_out_route", source: 2, destinations: &[3, 4], destination_mint: 6, mode: SwapMode::ExactOut },
+    JupiterSwap { name: "shared_accounts_exact_out_route", source: 3, destinations: &[6], destination_mint: 8, mode: SwapMode::ExactOut },
+    JupiterSwap { name: "route_with_token_ledger", source: 2, destinations: &[3, 4], destination_mint: 5, mode: SwapMode::TokenLedger },
+    JupiterSwap { name: "shared_accounts_route_with_token_ledger", source: 3, destinations: &[6], destination_mint: 8, mode: SwapMode::TokenLedger },
+];
+
+struct JupiterSwap {
+    name: &'static str,
+    /// The wallet's token account the input is taken from.
+    source: usize,
+    /// Token accounts the output may be paid into. An optional account left out is passed as
+    /// the Jupiter program id.
+    destinations: &'static [usize],
+    destination_mint: usize,
+    mode: SwapMode,
+}
+
+/// Drift instructions the executor builds, with where their authority sits.
+const DRIFT_INSTRUCTIONS: [(&str, usize); 4] = [
+    ("initialize_user_stats", 2),
+    ("initialize_user", 3),
+    ("place_perp_order", 2),
+    ("cancel_orders", 2),
+];
+
+/// Drift's `OrderParams` after the discriminator: where the fields the policy checks sit.
+const DRIFT_ORDER_BASE_AMOUNT: usize = 4;
+const DRIFT_ORDER_PRICE: usize = 12;
+const DRIFT_ORDER_MARKET_INDEX: usize = 20;
+const DRIFT_ORDER_REDUCE_ONLY: usize = 22;
+/// Drift perp amounts carry nine decimals and prices six, on every market.
+const DRIFT_BASE_PRECISION: f64 = 1e9;
+const DRIFT_PRICE_PRECISION: f64 = 1e6;
+
+/// Every Jupiter swap ends in `amount: u64, quoted_amount: u64, slippage_bps: u16, platform_fee_bps: u8`.
+const SWAP_TAIL_LEN: usize = 19;
+
+#[derive(Clone, Copy)]
+enum SwapMode {
+    /// `amount` is what goes in.
+    ExactIn,
+    /// `quoted_amount` is what goes in, plus up to `slippage_bps`.
+    ExactOut,
+    /// What goes in is read from a token ledger at execution time.
+    TokenLedger,
+}
+
+/// What the signer will put its key to. Every top-level instruction must target an allowed
+/// program, and value may only leave the wallet towards known destinations, up to a cap.
+pub struct Policy {
+    owner: Pubkey,
+    allowed_programs: HashSet<Pubkey>,
+    /// Where SOL and tokens may be sent: the wallet itself, its wrapped-SOL accounts, Jito tip
+    /// accounts and anything listed in `SIGNER_ALLOWED_DESTINATIONS`.
+    allowed_destinations: HashSet<Pubkey>,
+    /// The wallet's wrapped-SOL accounts: what is spent from them counts as SOL out.
+    wsol_accounts: HashSet<Pubkey>,
+    pub max_sol_out_lamports: u64,
+    /// Largest perp order, valued at its limit price; reduce-only orders are not capped.
+    pub max_perp_notional_usd: f64,
+}
+
+/// Result of inspecting one message.
+#[derive(Debug, Default)]
+pub struct Inspection {
+    pub sol_out_lamports: u64,
+    /// One line per top-level instruction: program, instruction tag and account count.
+    pub instructions: Vec<String>,
+    /// Empty when the message may be signed.
+    pub violations: Vec<String>,
+}
+
+impl Inspection {
+    fn add_sol_out(&mut self, i: usize, lamports: u64) {
+        match self.sol_out_lamports.checked_add(lamports) {
+            Some(total) => self.sol_out_lamports = total,
+            None => self.violations.push(format!("instruction {}: SOL out overflows", i)),
+        }
+    }
+}
+
+impl Policy {
+    /// Built-in allow-lists plus `SIGNER_ALLOWED_PROGRAMS` / `SIGNER_ALLOWED_DESTINATIONS`
+    /// (comma-separated pubkeys), `SIGNER_MAX_SOL_OUT_LAMPORTS` and `SIGNER_MAX_PERP_NOTIONAL_USD`.
+    /// Each can be scoped to one key with a `_<KEY NAME>` suffix: lists add to the global ones,
+    /// caps replace them.
+    pub fn from_env(key_name: &str, owner: Pubkey) -> Result<Self> {
+        let suffix = env_suffix(key_name);
+        let mut allowed_programs = parse_keys(&[
+            JUPITER_V6_PROGRAM_ID,
+            DRIFT_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            TOKEN_2022_PROGRAM_ID,
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            COMPUTE_BUDGET_PROGRAM_ID,
+        ])?;
+        allowed_programs.insert(system_program::id());
+        allowed_programs.extend(keys_from_env("SIGNER_ALLOWED_PROGRAMS")?);
+        allowed_programs.extend(keys_from_env(&format!("SIGNER_ALLOWED_PROGRAMS_{}", suffix))?);
+
+        let wsol = Pubkey::from_str(WSOL_MINT)?;
+        let wsol_accounts = [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID].iter()
+            .map(|program| associated_token_address(&owner, &wsol, &Pubkey::from_str(program)?))
+            .collect::<Result<HashSet<_>>>()?;
+        let mut allowed_destinations = parse_keys(&JITO_TIP_ACCOUNTS)?;
+        allowed_destinations.insert(owner);
+        allowed_destinations.extend(&wsol_accounts);
+        allowed_destinations.extend(keys_from_env("SIGNER_ALLOWED_DESTINATIONS")?);
+        allowed_destinations.extend(keys_from_env(&format!("SIGNER_ALLOWED_DESTINATIONS_{}", suffix))?);
+
+        let max_sol_out_lamports = env::var(format!("SIGNER_MAX_SOL_OUT_LAMPORTS_{}", suffix))
+            .or_else(|_| env::var("SIGNER_MAX_SOL_OUT_LAMPORTS"))
+            .unwrap_or_else(|_| "5000000000".to_string())
+            .parse()
+            .context("SIGNER_MAX_SOL_OUT_LAMPORTS must be an integer")?;
+        let max_perp_notional_usd = env::var(format!("SIGNER_MAX_PERP_NOTIONAL_USD_{}", suffix))
+            .or_else(|_| env::var("SIGNER_MAX_PERP_NOTIONAL_USD"))
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse()
+            .context("SIGNER_MAX_PERP_NOTIONAL_USD must be a number")?;
+
+        Ok(Self { owner, allowed_programs, allowed_destinations, wsol_accounts, max_sol_out_lamports, max_perp_notional_usd })
+    }
+
+    /// Checks every top-level instruction and adds up the SOL sent out by transfers, wrapped-SOL
+    /// token transfers and Jupiter swaps spending wrapped SOL. Accounts loaded from address lookup
+    /// tables cannot be resolved here, so a transfer to one counts as an unknown destination.
+    pub fn inspect(&self, message: &VersionedMessage) -> Inspection {
+        let keys = message.static_account_keys();
+        let mut inspection = Inspection::default();
+
+        for (i, ix) in message.instructions().iter().enumerate() {
+            let Some(program) = keys.get(ix.program_id_index as usize).copied() else {
+                inspection.violations.push(format!("instruction {}: program id is not a static account", i));
+                continue;
+            };
+            inspection.instructions.push(format!(
+                "{} tag={} accounts={}",
+                program_name(&program),
+                ix.data.first().map_or("-".to_string(), |t| t.to_string()),
+                ix.accounts.len()
+            ));
+            if !self.allowed_programs.contains(&program) {
+                inspection.violations.push(format!("instruction {}: program {} is not allow-listed", i, program));
+                continue;
+            }
+            if program == system_program::id() {
+                self.inspect_system(i, ix, keys, &mut inspection);
+            } else if program.to_string() == TOKEN_PROGRAM_ID || program.to_string() == TOKEN_2022_PROGRAM_ID {
+                self.inspect_token(i, ix, keys, &mut inspection);
+            } else if program.to_string() == JUPITER_V6_PROGRAM_ID {
+                self.inspect_swap(i, ix, keys, &mut inspection);
+            } else if program.to_string() == DRIFT_PROGRAM_ID {
+                self.inspect_drift(i, ix, keys, &mut inspection);
+            }
+        }
+
+        if inspection.sol_out_lamports > self.max_sol_out_lamports {
+            inspection.violations.push(format!(
+                "transfers {} lamports out, above the {} lamport cap",
+                inspection.sol_out_lamports, self.max_sol_out_lamports
+            ));
+        }
+        inspection
+    }
+
+    fn inspect_system(&self, i: usize, ix: &CompiledInstruction, keys: &[Pubkey], inspection: &mut Inspection) {
+        let account = |n: usize| ix.accounts.get(n).and_then(|a| keys.get(*a as usize)).copied();
+        match bincode::deserialize::<SystemInstruction>(&ix.data) {
+            Ok(SystemInstruction::Transfer { lamports }) => self.check_transfer(i, account(0), account(1), lamports, inspection),
+            Ok(SystemInstruction::TransferWithSeed { lamports, .. }) => self.check_transfer(i, account(1), account(2), lamports, inspection),
+            // The new account is funded from the wallet, so it is a destination like any other.
+            Ok(SystemInstruction::CreateAccount { lamports, .. } | SystemInstruction::CreateAccountWithSeed { lamports, .. }) => {
+                self.check_transfer(i, account(0), account(1), lamports, inspection)
+            }
+            Ok(SystemInstruction::AdvanceNonceAccount) => {}
+            Ok(other) => inspection.violations.push(format!("instruction {}: system instruction {:?} is not allowed", i, other)),
+            Err(_) => inspection.violations.push(format!("instruction {}: undecodable system instruction", i)),
+        }
+    }
+
+    fn inspect_token(&self, i: usize, ix: &CompiledInstruction, keys: &[Pubkey], inspection: &mut Inspection) {
+        let account = |n: usize| ix.accounts.get(n).and_then(|a| keys.get(*a as usize)).copied();
+        let (source, destination) = match ix.data.first().copied() {
+            Some(TOKEN_TRANSFER) => (account(0), account(1)),
+            Some(TOKEN_TRANSFER_CHECKED) => (account(0), account(2)),
+            Some(TOKEN_CLOSE_ACCOUNT) => (None, account(1)),
+            Some(tag @ (TOKEN_APPROVE | TOKEN_APPROVE_CHECKED | TOKEN_SET_AUTHORITY)) => {
+                inspection.violations.push(format!("instruction {}: token instruction {} (delegate/authority change) is not allowed", i, tag));
+                return;
+            }
+            // Only credits a wrapped-SOL account with lamports it already holds.
+            Some(TOKEN_SYNC_NATIVE) => return,
+            Some(tag) => {
+                inspection.violations.push(format!("instruction {}: token instruction {} is not allowed", i, tag));
+                return;
+            }
+            None => {
+                inspection.violations.push(format!("instruction {}: undecodable token instruction", i));
+                return;
+            }
+        };
+        if !destination.is_some_and(|d| self.allowed_destinations.contains(&d)) {
+            inspection.violations.push(format!("instruction {}: token transfer to unknown destination {}", i, describe(destination)));
+        }
+        let leaves_wallet = source.is_some_and(|s| self.wsol_accounts.contains(&s)) && !destination.is_some_and(|d| self.is_own(&d));
+        if leaves_wallet {
+            match ix.data.get(1..9) {
+                Some(amount) => inspection.add_sol_out(i, u64::from_le_bytes(amount.try_into().unwrap())),
+                None => inspection.violations.push(format!("instruction {}: undecodable token transfer", i)),
+            }
+        }
+    }
+
+    fn inspect_swap(&self, i: usize, ix: &CompiledInstruction, keys: &[Pubkey], inspection: &mut Inspection) {
+        let Some(swap) = ix.data.get(..8).and_then(|tag| JUPITER_SWAPS.iter().find(|s| discriminator(s.name) == tag)) else {
+            inspection.violations.push(format!("instruction {}: jupiter instruction is not allowed", i));
+            return;
+        };
+        let account = |n: usize| ix.accounts.get(n).and_then(|a| keys.get(*a as usize)).copied();
+        let jupiter = Pubkey::from_str(JUPITER_V6_PROGRAM_ID).unwrap();
+        for destination in swap.destinations.iter().map(|n| account(*n)).filter(|d| *d != Some(jupiter)) {
+            if !destination.is_some_and(|d| self.is_swap_destination(&d, account(swap.destination_mint))) {
+                inspection.violations.push(format!("instruction {}: swap output to unknown destination {}", i, describe(destination)));
+            }
+        }
+        let Some(source) = account(swap.source) else {
+            inspection.violations.push(format!("instruction {}: swap source {}", i, describe(None)));
+            return;
+        };
+        if !self.wsol_accounts.contains(&source) {
+            return;
+        }
+        let Some(tail) = ix.data.len().checked_sub(SWAP_TAIL_LEN).filter(|start| *start >= 8).map(|start| &ix.data[start..]) else {
+            inspection.violations.push(format!("instruction {}: undecodable swap", i));
+            return;
+        };
+        let amount = u64::from_le_bytes(tail[0..8].try_into().unwrap());
+        let quoted = u64::from_le_bytes(tail[8..16].try_into().unwrap());
+        let slippage_bps = u16::from_le_bytes(tail[16..18].try_into().unwrap());
+        match swap.mode {
+            SwapMode::ExactIn => inspection.add_sol_out(i, amount),
+            SwapMode::ExactOut => match u64::try_from(quoted as u128 * (10_000 + slippage_bps as u128) / 10_000) {
+                Ok(max_in) => inspection.add_sol_out(i, max_in),
+                Err(_) => inspection.violations.push(format!("instruction {}: swap input overflows", i)),
+            },
+            SwapMode::TokenLedger => inspection.violations.push(format!("instruction {}: token-ledger swap spends an unbounded amount of SOL", i)),
+        }
+    }
+
+    /// An allowed destination, or the wallet's own token account for the swap's output mint.
+    fn is_swap_destination(&self, destination: &Pubkey, mint: Option<Pubkey>) -> bool {
+        if self.allowed_destinations.contains(destination) {
+            return true;
+        }
+        let Some(mint) = mint else { return false };
+        [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID].iter().any(|program| {
+            associated_token_address(&self.owner, &mint, &Pubkey::from_str(program).unwrap()).ok() == Some(*destination)
+        })
+    }
+
+    /// Only the instructions the executor builds, and only for the wallet's own Drift account.
+    fn inspect_drift(&self, i: usize, ix: &CompiledInstruction, keys: &[Pubkey], inspection: &mut Inspection) {
+        let Some((name, authority_index)) = ix.data.get(..8).and_then(|tag| DRIFT_INSTRUCTIONS.iter().find(|(name, _)| discriminator(name) == tag)) else {
+            inspection.violations.push(format!("instruction {}: drift instruction is not allowed", i));
+            return;
+        };
+        let authority = ix.accounts.get(*authority_index).and_then(|a| keys.get(*a as usize)).copied();
+        if authority != Some(self.owner) {
+            inspection.violations.push(format!("instruction {}: drift {} for authority {}", i, name, describe(authority)));
+        }
+        if *name == "place_perp_order" {
+            self.check_perp_order(i, &ix.data[8..], inspection);
+        }
+    }
+
+    /// Caps what a perp order can open, valued at its limit price; an order without one could
+    /// fill at any price.
+    fn check_perp_order(&self, i: usize, params: &[u8], inspection: &mut Inspection) {
+        let Some(reduce_only) = params.get(DRIFT_ORDER_REDUCE_ONLY) else {
+            inspection.violations.push(format!("instruction {}: undecodable drift order", i));
+            return;
+        };
+        if *reduce_only != 0 {
+            return;
+        }
+        let u64_at = |at: usize| u64::from_le_bytes(params[at..at + 8].try_into().unwrap());
+        let market_index = u16::from_le_bytes([params[DRIFT_ORDER_MARKET_INDEX], params[DRIFT_ORDER_MARKET_INDEX + 1]]);
+        let price = u64_at(DRIFT_ORDER_PRICE);
+        if price == 0 {
+            inspection.violations.push(format!("instruction {}: drift order on perp market {} has no limit price", i, market_index));
+            return;
+        }
+        let notional_usd = u64_at(DRIFT_ORDER_BASE_AMOUNT) as f64 / DRIFT_BASE_PRECISION * (price as f64 / DRIFT_PRICE_PRECISION);
+        if notional_usd > self.max_perp_notional_usd {
+            inspection.violations.push(format!(
+                "instruction {}: drift order on perp market {} for ${:.2} notional, above the ${} cap",
+                i, market_index, notional_usd, self.max_perp_notional_usd
+            ));
+        }
+    }
+
+    /// The wallet itself or one of its wrapped-SOL accounts.
+    fn is_own(&self, key: &Pubkey) -> bool {
+        *key == self.owner || self.wsol_accounts.contains(key)
+    }
+
+    fn check_transfer(&self, i: usize, from: Option<Pubkey>, to: Option<Pubkey>, lamports: u64, inspection: &mut Inspection) {
+        // Wrapping SOL keeps it in the wallet; it is counted when the wrapped SOL is spent.
+        if from != Some(self.owner) || to.is_some_and(|t| self.is_own(&t)) {
+            return;
+        }
+        inspection.add_sol_out(i, lamports);
+        if !to.is_some_and(|d| self.allowed_destinations.contains(&d)) {
+            inspection.violations.push(format!("instruction {}: SOL transfer to unknown destination {}", i, describe(to)));
+        }
+    }
+}
+
+/// Anchor instruction discriminator.
+fn discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("global:{}", name).as_bytes());
+    hash[..8].try_into().unwrap()
+}
+
+fn program_name(program: &Pubkey) -> String {
+    let name = match program.to_string().as_str() {
+        JUPITER_V6_PROGRAM_ID => "jupiter",
+        DRIFT_PROGRAM_ID => "drift",
+        TOKEN_PROGRAM_ID => "token",
+        TOKEN_2022_PROGRAM_ID => "token-2022",
+        ASSOCIATED_TOKEN_PROGRAM_ID => "associated-token",
+        COMPUTE_BUDGET_PROGRAM_ID => "compute-budget",
+        _ if *program == system_program::id() => "system",
+        other => return other.to_string(),
+    };
+    name.to_string()
+}
+
+fn describe(key: Option<Pubkey>) -> String {
+    key.map(|k| k.to_string()).unwrap_or_else(|| "(lookup table account)".to_string())
+}
+
+fn parse_keys(keys: &[&str]) -> Result<HashSet<Pubkey>> {
+    keys.iter().map(|k| Pubkey::from_str(k).with_context(|| format!("invalid pubkey {}", k))).collect()
+}
+
+fn keys_from_env(var: &str) -> Result<HashSet<Pubkey>> {
+    let raw = env::var(var).unwrap_or_default();
+    let keys: Vec<&str> = raw.split(',').map(str::trim).filter(|k| !k.is_empty()).collect();
+    parse_keys(&keys).with_context(|| format!("{} must be comma-separated pubkeys", var))
+}
+
+fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Result<Pubkey> {
+    let ata_program = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID)?;
+    Ok(Pubkey::find_program_address(&[owner.as_ref(), token_program.as_ref(), mint.as_ref()], &ata_program).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::message::{v0::{self, MessageAddressTableLookup}, MessageHeader};
+
+    fn policy(owner: Pubkey) -> Policy {
+        let mut allowed_programs = parse_keys(&[JUPITER_V6_PROGRAM_ID, TOKEN_PROGRAM_ID, COMPUTE_BUDGET_PROGRAM_ID]).unwrap();
+        allowed_programs.insert(system_program::id());
+        let mut allowed_destinations = parse_keys(&JITO_TIP_ACCOUNTS).unwrap();
+        allowed_destinations.insert(owner);
+        let wsol_accounts = HashSet::from([wsol_account(&owner)]);
+        allowed_destinations.extend(&wsol_accounts);
+        Policy { owner, allowed_programs, allowed_destinations, wsol_accounts, max_sol_out_lamports: 1_000_000, max_perp_notional_usd: 1_000.0 }
+    }
+
+    fn wsol_account(owner: &Pubkey) -> Pubkey {
+        associated_token_address(owner, &Pubkey::from_str(WSOL_MINT).unwrap(), &Pubkey::from_str(TOKEN_PROGRAM_ID).unwrap()).unwrap()
+    }
+
+    /// A message signed by `keys[0]` whose instructions index into `keys`, plus two accounts
+    /// loaded from a lookup table at indexes `keys.len()` and `keys.len() + 1`.
+    fn message(keys: Vec<Pubkey>, instructions: Vec<CompiledInstruction>) -> VersionedMessage {
+        VersionedMessage::V0(v0::Message {
+            header: MessageHeader { num_required_signatures: 1, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 0 },
+            account_keys: keys,
+            recent_blockhash: Default::default(),
+            instructions,
+            address_table_lookups: vec![MessageAddressTableLookup { account_key: Pubkey::new_unique(), writable_indexes: vec![0, 1], readonly_indexes: vec![] }],
+        })
+    }
+
+    fn transfer(program: u8, from: u8, to: u8, lamports: u64) -> CompiledInstruction {
+        CompiledInstruction { program_id_index: program, accounts: vec![from, to], data: bincode::serialize(&SystemInstruction::Transfer { lamports }).unwrap() }
+    }
+
+    fn token(program: u8, tag: u8, accounts: Vec<u8>) -> CompiledInstruction {
+        CompiledInstruction { program_id_index: program, accounts, data: vec![tag, 0, 0, 0, 0, 0, 0, 0, 0] }
+    }
+
+    #[test]
+    fn allowed_programs_pass_and_others_are_rejected() {
+        let owner = Pubkey::new_unique();
+        let compute_budget = Pubkey::from_str(COMPUTE_BUDGET_PROGRAM_ID).unwrap();
+        let unknown = Pubkey::new_unique();
+        let ix = |program| CompiledInstruction { program_id_index: program, accounts: vec![0], data: vec![1] };
+
+        let ok = policy(owner).inspect(&message(vec![owner, compute_budget], vec![ix(1)]));
+        assert!(ok.violations.is_empty(), "{:?}", ok.violations);
+        assert_eq!(ok.instructions, vec!["compute-budget tag=1 accounts=1"]);
+
+        let denied = policy(owner).inspect(&message(vec![owner, compute_budget, unknown], vec![ix(1), ix(2)]));
+        assert_eq!(denied.violations, vec![format!("instruction 1: program {} is not allow-listed", unknown)]);
+    }
+
+    #[test]
+    fn program_from_a_lookup_table_is_rejected() {
+        let owner = Pubkey::new_unique();
+        let inspection = policy(owner).inspect(&message(vec![owner], vec![CompiledInstruction { program_id_index: 1, accounts: vec![], data: vec![] }]));
+        assert_eq!(inspection.violations, vec!["instruction 0: program id is not a static account"]);
+    }
+
+    #[test]
+    fn sol_transfers_only_to_known_destinations() {
+        let owner = Pubkey::new_unique();
+        let tip = Pubkey::from_str(JITO_TIP_ACCOUNTS[0]).unwrap();
+        let stranger = Pubkey::new_unique();
+        let keys = vec![owner, system_program::id(), tip, stranger];
+
+        let tip_only = policy(owner).inspect(&message(keys.clone(), vec![transfer(1, 0, 2, 10_000)]));
+        assert!(tip_only.violations.is_empty(), "{:?}", tip_only.violations);
+        assert_eq!(tip_only.sol_out_lamports, 10_000);
+
+        let drained = policy(owner).inspect(&message(keys, vec![transfer(1, 0, 3, 10_000)]));
+        assert_eq!(drained.violations, vec![format!("instruction 0: SOL transfer to unknown destination {}", stranger)]);
+    }
+
+    #[test]
+    fn transfers_to_lookup_table_accounts_are_unknown_destinations() {
+        let owner = Pubkey::new_unique();
+        let token_program = Pubkey::from_str(TOKEN_PROGRAM_ID).unwrap();
+        let keys = vec![owner, system_program::id(), token_program];
+
+        let sol = policy(owner).inspect(&message(keys.clone(), vec![transfer(1, 0, 3, 1)]));
+        assert_eq!(sol.violations, vec!["instruction 0: SOL transfer to unknown destination (lookup table account)"]);
+
+        let tokens = policy(owner).inspect(&message(keys, vec![token(2, TOKEN_TRANSFER, vec![0, 4, 0])]));
+        assert_eq!(tokens.violations, vec!["instruction 0: token transfer to unknown destination (lookup table account)"]);
+    }
+
+    #[test]
+    fn delegation_and_cap_are_enforced() {
+        let owner = Pubkey::new_unique();
+        let token_program = Pubkey::from_str(TOKEN_PROGRAM_ID).unwrap();
+        let keys = vec![owner, system_program::id(), token_program, Pubkey::from_str(JITO_TIP_ACCOUNTS[1]).unwrap()];
+
+        let approve = policy(owner).inspect(&message(keys.clone(), vec![token(2, TOKEN_APPROVE, vec![0, 3, 0])]));
+        assert_eq!(approve.violations.len(), 1);
+        assert!(approve.violations[0].contains("delegate/authority change"));
+
+        let over_cap = policy(owner).inspect(&message(keys, vec![transfer(1, 0, 3, 1_000_001)]));
+        assert_eq!(over_cap.violations, vec!["transfers 1000001 lamports out, above the 1000000 lamport cap"]);
+    }
+
+    #[test]
+    fn only_allow_listed_token_instructions_pass() {
+        let owner = Pubkey::new_unique();
+        let token_program = Pubkey::from_str(TOKEN_PROGRAM_ID).unwrap();
+        let keys = vec![owner, system_program::id(), token_program, wsol_account(&owner), Pubkey::from_str(WSOL_MINT).unwrap()];
+
+        let sync = policy(owner).inspect(&message(keys.clone(), vec![token(2, TOKEN_SYNC_NATIVE, vec![3])]));
+        assert!(sync.violations.is_empty(), "{:?}", sync.violations);
+        // Burn, then MintTo.
+        for tag in [8, 7] {
+            let refused = policy(owner).inspect(&message(keys.clone(), vec![token(2, tag, vec![3, 4, 0])]));
+            assert_eq!(refused.violations, vec![format!("instruction 0: token instruction {} is not allowed", tag)]);
+        }
+        let empty = CompiledInstruction { program_id_index: 2, accounts: vec![3], data: vec![] };
+        assert_eq!(policy(owner).inspect(&message(keys, vec![empty])).violations, vec!["instruction 0: undecodable token instruction"]);
+    }
+
+    fn swap(name: &str, source: u8, amount: u64, quoted: u64, slippage_bps: u16) -> CompiledInstruction {
+        let mut data = discriminator(name).to_vec();
+        data.extend(0u32.to_le_bytes()); // empty route plan
+        data.extend(amount.to_le_bytes());
+        data.extend(quoted.to_le_bytes());
+        data.extend(slippage_bps.to_le_bytes());
+        data.push(0);
+        // Output into the wrapped-SOL account at 3; the optional destination at 4 left out.
+        let mut accounts = vec![2, 0, 2, 3, 1, 2, 3, 2, 2];
+        accounts[if name.starts_with("shared") { 3 } else { 2 }] = source;
+        CompiledInstruction { program_id_index: 1, accounts, data }
+    }
+
+    #[test]
+    fn swaps_spending_wrapped_sol_count_as_sol_out() {
+        let owner = Pubkey::new_unique();
+        let keys = vec![owner, Pubkey::from_str(JUPITER_V6_PROGRAM_ID).unwrap(), system_program::id(), wsol_account(&owner), Pubkey::new_unique()];
+
+        // Wrap then swap: the SOL leaves once, in the swap.
+        let wrap_and_swap = policy(owner).inspect(&message(keys.clone(), vec![transfer(2, 0, 3, 600_000), swap("route", 3, 600_000, 42, 50)]));
+        assert!(wrap_and_swap.violations.is_empty(), "{:?}", wrap_and_swap.violations);
+        assert_eq!(wrap_and_swap.sol_out_lamports, 600_000);
+
+        let shared = policy(owner).inspect(&message(keys.clone(), vec![swap("shared_accounts_route", 3, 700_000, 42, 50)]));
+        assert_eq!(shared.sol_out_lamports, 700_000);
+
+        // Exact-out spends up to the quoted input plus slippage.
+        let exact_out = policy(owner).inspect(&message(keys.clone(), vec![swap("exact_out_route", 3, 42, 1_000_000, 100)]));
+        assert_eq!(exact_out.sol_out_lamports, 1_010_000);
+        assert_eq!(exact_out.violations, vec!["transfers 1010000 lamports out, above the 1000000 lamport cap"]);
+
+        let ledger = policy(owner).inspect(&message(keys.clone(), vec![swap("route_with_token_ledger", 3, 0, 0, 0)]));
+        assert_eq!(ledger.violations, vec!["instruction 0: token-ledger swap spends an unbounded amount of SOL"]);
+
+        // Selling a token spends no SOL.
+        let sell = policy(owner).inspect(&message(keys, vec![swap("route", 4, 5_000_000, 42, 50)]));
+        assert!(sell.violations.is_empty(), "{:?}", sell.violations);
+        assert_eq!(sell.sol_out_lamports, 0);
+    }
+
+    #[test]
+    fn wrapped_sol_token_transfers_count_as_sol_out() {
+        let owner = Pubkey::new_unique();
+        let tip = Pubkey::from_str(JITO_TIP_ACCOUNTS[2]).unwrap();
+        let keys = vec![owner, system_program::id(), Pubkey::from_str(TOKEN_PROGRAM_ID).unwrap(), wsol_account(&owner), tip];
+        let mut data = vec![TOKEN_TRANSFER];
+        data.extend(250_000u64.to_le_bytes());
+        let ix = CompiledInstruction { program_id_index: 2, accounts: vec![3, 4, 0], data };
+        let inspection = policy(owner).inspect(&message(keys, vec![ix]));
+        assert!(inspection.violations.is_empty(), "{:?}", inspection.violations);
+        assert_eq!(inspection.sol_out_lamports, 250_000);
+    }
+
+    #[test]
+    fn swap_output_goes_only_to_the_wallet_or_known_destinations() {
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let own_ata = associated_token_address(&owner, &mint, &Pubkey::from_str(TOKEN_PROGRAM_ID).unwrap()).unwrap();
+        let stranger = Pubkey::new_unique();
+        let keys = vec![owner, Pubkey::from_str(JUPITER_V6_PROGRAM_ID).unwrap(), system_program::id(), wsol_account(&owner), stranger, mint, own_ata];
+        let into = |name: &str, destination: usize, to: u8| {
+            let mut ix = swap(name, 3, 400_000, 42, 50);
+            ix.accounts[destination] = to;
+            ix.accounts[if name.starts_with("shared") { 8 } else { 5 }] = 5;
+            ix
+        };
+
+        let own = policy(owner).inspect(&message(keys.clone(), vec![into("route", 3, 6), into("shared_accounts_route", 6, 6)]));
+        assert!(own.violations.is_empty(), "{:?}", own.violations);
+
+        let optional = policy(owner).inspect(&message(keys.clone(), vec![into("route", 4, 4)]));
+        assert_eq!(optional.violations, vec![format!("instruction 0: swap output to unknown destination {}", stranger)]);
+
+        let shared = policy(owner).inspect(&message(keys.clone(), vec![into("shared_accounts_exact_out_route", 6, 4)]));
+        assert_eq!(shared.violations, vec![format!("instruction 0: swap output to unknown destination {}", stranger)]);
+
+        let lookup = policy(owner).inspect(&message(keys, vec![into("route", 3, 7)]));
+        assert_eq!(lookup.violations, vec!["instruction 0: swap output to unknown destination (lookup table account)"]);
+    }
+
+    #[test]
+    fn unknown_jupiter_instructions_are_rejected() {
+        let owner = Pubkey::new_unique();
+        let keys = vec![owner, Pubkey::from_str(JUPITER_V6_PROGRAM_ID).unwrap(), system_program::id(), wsol_account(&owner)];
+        for name in ["route_v2", "shared_accounts_route_v2", "claim", "claim_token"] {
+            let mut ix = swap("route", 3, 400_000, 42, 50);
+            ix.data[..8].copy_from_slice(&discriminator(name));
+            let inspection = policy(owner).inspect(&message(keys.clone(), vec![ix]));
+            assert_eq!(inspection.violations, vec!["instruction 0: jupiter instruction is not allowed"], "{}", name);
+        }
+        let short = CompiledInstruction { program_id_index: 1, accounts: vec![], data: vec![1, 2, 3] };
+        let inspection = policy(owner).inspect(&message(keys, vec![short]));
+        assert_eq!(inspection.violations, vec!["instruction 0: jupiter instruction is not allowed"]);
+    }
+
+    /// `place_perp_order` for 1 base unit (1e9) at `price` (1e6) on market 3.
+    fn perp_order(price: u64, reduce_only: bool) -> Vec<u8> {
+        let mut data = discriminator("place_perp_order").to_vec();
+        data.extend([0, 1, 1, 0]);
+        data.extend(1_000_000_000u64.to_le_bytes());
+        data.extend(price.to_le_bytes());
+        data.extend(3u16.to_le_bytes());
+        data.extend([reduce_only as u8, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn drift_instructions_are_limited_to_the_wallets_own_orders() {
+        let owner = Pubkey::new_unique();
+        let drift = Pubkey::from_str(DRIFT_PROGRAM_ID).unwrap();
+        let mut policy = policy(owner);
+        policy.allowed_programs.insert(drift);
+        let keys = vec![owner, drift, Pubkey::new_unique(), Pubkey::new_unique()];
+        let ix = |name: &str, accounts: Vec<u8>| CompiledInstruction { program_id_index: 1, accounts, data: discriminator(name).to_vec() };
+        let order = |accounts: Vec<u8>| CompiledInstruction { program_id_index: 1, accounts, data: perp_order(150_000_000, false) };
+
+        let own = policy.inspect(&message(keys.clone(), vec![order(vec![2, 3, 0]), ix("cancel_orders", vec![2, 3, 0]), ix("initialize_user", vec![3, 2, 2, 0, 0])]));
+        assert!(own.violations.is_empty(), "{:?}", own.violations);
+
+        let withdraw = policy.inspect(&message(keys.clone(), vec![ix("withdraw", vec![2, 3, 0])]));
+        assert_eq!(withdraw.violations, vec!["instruction 0: drift instruction is not allowed"]);
+
+        let someone_else = policy.inspect(&message(keys.clone(), vec![order(vec![2, 3, 3])]));
+        assert_eq!(someone_else.violations, vec![format!("instruction 0: drift place_perp_order for authority {}", keys[3])]);
+    }
+
+    #[test]
+    fn perp_orders_are_capped_at_their_limit_price() {
+        let owner = Pubkey::new_unique();
+        let drift = Pubkey::from_str(DRIFT_PROGRAM_ID).unwrap();
+        let mut policy = policy(owner);
+        policy.allowed_programs.insert(drift);
+        let keys = vec![owner, drift, Pubkey::new_unique()];
+        let inspect = |data: Vec<u8>| policy.inspect(&message(keys.clone(), vec![CompiledInstruction { program_id_index: 1, accounts: vec![2, 2, 0], data }])).violations;
+
+        assert!(inspect(perp_order(1_000_000_000, false)).is_empty(), "exactly at the $1000 cap");
+        assert_eq!(inspect(perp_order(1_500_000_000, false)), vec!["instruction 0: drift order on perp market 3 for $1500.00 notional, above the $1000 cap"]);
+        assert!(inspect(perp_order(1_500_000_000, true)).is_empty(), "reduce-only orders close, not open");
+        assert_eq!(inspect(perp_order(0, false)), vec!["instruction 0: drift order on perp market 3 has no limit price"]);
+        assert_eq!(inspect(discriminator("place_perp_order").to_vec()), vec!["instruction 0: undecodable drift order"]);
+    }
+
+    #[test]
+    fn created_accounts_are_checked_as_destinations() {
+        let owner = Pubkey::new_unique();
+        let stranger = Pubkey::new_unique();
+        let create = |to: u8| CompiledInstruction {
+            program_id_index: 1,
+            accounts: vec![0, to],
+            data: bincode::serialize(&SystemInstruction::CreateAccount { lamports: 890_880, space: 165, owner: Pubkey::from_str(TOKEN_PROGRAM_ID).unwrap() }).unwrap(),
+        };
+        let allowed = Pubkey::new_unique();
+        let mut policy = policy(owner);
+        policy.allowed_destinations.insert(allowed);
+        let keys = vec![owner, system_program::id(), allowed, stranger];
+
+        let known = policy.inspect(&message(keys.clone(), vec![create(2)]));
+        assert!(known.violations.is_empty(), "{:?}", known.violations);
+        assert_eq!(known.sol_out_lamports, 890_880);
+
+        let unknown = policy.inspect(&message(keys, vec![create(3)]));
+        assert_eq!(unknown.violations, vec![format!("instruction 0: SOL transfer to unknown destination {}", stranger)]);
+    }
+
+    #[test]
+    fn overflowing_amounts_are_violations() {
+        let owner = Pubkey::new_unique();
+        let tip = Pubkey::from_str(JITO_TIP_ACCOUNTS[0]).unwrap();
+        let keys = vec![owner, system_program::id(), tip];
+        let inspection = policy(owner).inspect(&message(keys, vec![transfer(1, 0, 2, u64::MAX), transfer(1, 0, 2, 1)]));
+        assert_eq!(inspection.violations[0], "instruction 1: SOL out overflows");
+
+        let keys = vec![owner, Pubkey::from_str(JUPITER_V6_PROGRAM_ID).unwrap(), system_program::id(), wsol_account(&owner)];
+        let exact_out = policy(owner).inspect(&message(keys, vec![swap("exact_out_route", 3, 42, u64::MAX, 100)]));
+        assert_eq!(exact_out.violations, vec!["instruction 0: swap input overflows"]);
+    }
+}
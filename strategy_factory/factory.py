@@ -21,7 +21,7 @@ def get_default_params(family):
     elif family == "liquidity_migration":
         return {"min_volume_migrate_usd": 50000.0}
     elif family == "perp_basis_arb":
-        return {"basis_threshold_pct": 0.5}
+        return {"entry_basis_pct": 0.5, "exit_basis_pct": 0.1, "round_trip_fee_bps": 40.0, "holding_hours": 24.0}
     elif family == "dev_wallet_drain":
         return {"dev_balance_threshold_pct": 2.0}
     elif family == "airdrop_rotation":